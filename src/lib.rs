@@ -1,10 +1,21 @@
 use anyhow::anyhow;
 
+mod ocb;
 mod parsers;
-mod pgp;
+pub mod pgp;
+mod rsa;
 mod utils;
 
-use pgp::signature::CleartextSignature;
+use pgp::aead::AeadAlgorithm;
+use pgp::encryption::{preferred_symmetric_algorithm, EncryptedMessage};
+use pgp::key::format_fingerprint;
+use pgp::message::SignedMessage;
+use pgp::secret_key::{KeyType, SecretKey};
+use pgp::signature::{CleartextSignature, DetachedSignature, SignaturePacket};
+use pgp::{AsciiArmor, AsciiArmorKind, PgpPacket, PublicKey};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
 use utils::read_to_string_convert_newlines;
 
 pub fn verify_cleartext_message(source: &str, public_key_path: &str) -> anyhow::Result<()> {
@@ -13,13 +24,328 @@ pub fn verify_cleartext_message(source: &str, public_key_path: &str) -> anyhow::
     println!("File read. Checksum is valid.");
 
     let key = read_to_string_convert_newlines(public_key_path)?;
-    let key = pgp::PublicKey::parse(&key)?;
+    let key = PublicKey::parse(&key)?;
 
+    let signer = describe_signer(&key, cleartext_signature.signature())?;
     if cleartext_signature.verify(&key)? {
-        println!("Signature is valid.");
+        println!("Good signature from {}", signer);
     } else {
         return Err(anyhow!("Signature is invalid."));
     }
 
     Ok(())
 }
+
+pub fn verify_detached_signature(
+    signature_path: &str,
+    source: &str,
+    public_key_path: &str,
+) -> anyhow::Result<()> {
+    let signature = fs::read(signature_path)?;
+    let detached_signature = DetachedSignature::parse(&signature)?;
+    println!("Signature read.");
+
+    let data = fs::read(source)?;
+
+    let key = read_to_string_convert_newlines(public_key_path)?;
+    let key = PublicKey::parse(&key)?;
+
+    let signer = describe_signer(&key, detached_signature.signature())?;
+    if detached_signature.verify(&key, &data)? {
+        println!("Good signature from {}", signer);
+    } else {
+        return Err(anyhow!("Signature is invalid."));
+    }
+
+    Ok(())
+}
+
+/// Verify a message signed with one-pass signatures, writing the signed data to `output` (or to
+/// stdout if no output is given). Since the signed data may be written to stdout, progress is
+/// reported on stderr.
+pub fn verify_signed_message(
+    source: &str,
+    public_key_path: &str,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    let data = fs::read(source)?;
+    let signed_message = SignedMessage::parse(&data)?;
+    eprintln!("File read.");
+
+    let key = read_to_string_convert_newlines(public_key_path)?;
+    let key = PublicKey::parse(&key)?;
+
+    let results = signed_message.verify(&key)?;
+    for (signature, valid) in signed_message.signatures().iter().zip(results.iter()) {
+        let signer = describe_signer(&key, signature)?;
+        if *valid {
+            eprintln!("Good signature from {}", signer);
+        } else {
+            eprintln!("Bad signature from {}", signer);
+        }
+    }
+
+    if results.iter().any(|valid| !valid) {
+        return Err(anyhow!("Message has an invalid signature."));
+    }
+
+    write_output(output, signed_message.literal_data())
+}
+
+/// Sign the text in `source` as a cleartext signature, with the secret key in `secret_key_path`
+/// (unlocked with `passphrase`, if one is given). The signed document is written to `output` (or
+/// to stdout if no output is given), so progress is reported on stderr.
+pub fn sign_cleartext(
+    source: &str,
+    secret_key_path: &str,
+    passphrase: Option<&str>,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    let text = fs::read_to_string(source)?;
+
+    let key = read_secret_key(secret_key_path, passphrase)?;
+    let cleartext_signature = CleartextSignature::create(&text, &key)?;
+    eprintln!(
+        "Signed with key 0x{:016X}.",
+        key.signing_key()?.public_key.key_id()
+    );
+
+    write_output(output, cleartext_signature.to_document()?.as_bytes())
+}
+
+/// Make a detached signature over the file `source` with the secret key in `secret_key_path`
+/// (unlocked with `passphrase`, if one is given), in text mode if `text` is set. The signature is
+/// written to `output` (or to stdout if no output is given), ascii armored if `armor` is set.
+pub fn sign_detached(
+    source: &str,
+    secret_key_path: &str,
+    passphrase: Option<&str>,
+    text: bool,
+    armor: bool,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    let data = fs::read(source)?;
+
+    let key = read_secret_key(secret_key_path, passphrase)?;
+    let detached_signature = DetachedSignature::create(&data, text, &key)?;
+    eprintln!(
+        "Signed with key 0x{:016X}.",
+        key.signing_key()?.public_key.key_id()
+    );
+
+    let signature = detached_signature.to_bytes()?;
+    write_output(
+        output,
+        &maybe_armor(AsciiArmorKind::Signature, signature, armor),
+    )
+}
+
+/// Sign the file `source` as a message with a one-pass signature, with the secret key in
+/// `secret_key_path` (unlocked with `passphrase`, if one is given), in text mode if `text` is
+/// set. The message is written to `output` (or to stdout if no output is given), ascii armored if
+/// `armor` is set.
+pub fn sign_message(
+    source: &str,
+    secret_key_path: &str,
+    passphrase: Option<&str>,
+    text: bool,
+    armor: bool,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    let data = fs::read(source)?;
+    let filename = Path::new(source)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let key = read_secret_key(secret_key_path, passphrase)?;
+    let signed_message = SignedMessage::create(data, filename.as_bytes(), text, &key)?;
+    eprintln!(
+        "Signed with key 0x{:016X}.",
+        key.signing_key()?.public_key.key_id()
+    );
+
+    let message = signed_message.to_bytes()?;
+    write_output(
+        output,
+        &maybe_armor(AsciiArmorKind::Message, message, armor),
+    )
+}
+
+/// Encrypt the file `source` to the keys in `recipient_paths`, and to `passphrase` if one is
+/// given, using the symmetric algorithm the recipients all prefer. With `aead_algorithm`, the
+/// message is encrypted as described by RFC 9580, with v2 encrypted data. The message is written
+/// to `output` (or to stdout if no output is given), ascii armored if `armor` is set.
+pub fn encrypt(
+    source: &str,
+    recipient_paths: &[&str],
+    passphrase: Option<&str>,
+    aead_algorithm: Option<AeadAlgorithm>,
+    armor: bool,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    let data = fs::read(source)?;
+    let filename = Path::new(source)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let recipients = recipient_paths
+        .iter()
+        .map(|path| PublicKey::parse(&read_to_string_convert_newlines(path)?))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let symmetric_algorithm = preferred_symmetric_algorithm(&recipients);
+
+    let encrypted_message = EncryptedMessage::create(
+        data,
+        filename.as_bytes(),
+        &recipients,
+        passphrase,
+        symmetric_algorithm,
+        aead_algorithm,
+    )?;
+    for recipient in recipients.iter() {
+        eprintln!(
+            "Encrypted to key 0x{:016X}.",
+            recipient.find_encryption_key()?.key_id()
+        );
+    }
+    if passphrase.is_some() {
+        eprintln!("Encrypted with a passphrase.");
+    }
+    match aead_algorithm {
+        Some(aead_algorithm) => eprintln!(
+            "Encrypted with {} in {} mode.",
+            symmetric_algorithm, aead_algorithm
+        ),
+        None => eprintln!("Encrypted with {}.", symmetric_algorithm),
+    }
+
+    let message = encrypted_message.to_bytes()?;
+    write_output(
+        output,
+        &maybe_armor(AsciiArmorKind::Message, message, armor),
+    )
+}
+
+/// Decrypt the message in `source` with the secret key in `secret_key_path` (unlocked with
+/// `passphrase`, if one is given). If no secret key is given and the message is encrypted with a
+/// passphrase, it is decrypted with `passphrase` instead. The recovered literal data is written
+/// to `output` (or to stdout if no output is given).
+pub fn decrypt(
+    source: &str,
+    secret_key_path: Option<&str>,
+    passphrase: Option<&str>,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    let message = EncryptedMessage::parse(&fs::read(source)?)?;
+
+    let packets = match (secret_key_path, passphrase) {
+        (None, Some(passphrase)) if message.is_encrypted_with_passphrase() => {
+            let packets = message.decrypt_with_passphrase(passphrase)?;
+            eprintln!("Decrypted with a passphrase.");
+            packets
+        }
+        (secret_key_path, passphrase) => {
+            let key = read_secret_key(secret_key_path.unwrap_or("secret.pgp"), passphrase)?;
+            let (decryption_key, session_key) = message.decrypt_session_key(&key)?;
+            eprintln!(
+                "Decrypted with key 0x{:016X}.",
+                decryption_key.public_key.key_id()
+            );
+            message.decrypt(&session_key)?
+        }
+    };
+
+    if packets
+        .iter()
+        .any(|packet| matches!(packet, PgpPacket::SignaturePacket(_)))
+    {
+        eprintln!("The message is also signed, the signature was not checked.");
+    }
+    let literal = packets
+        .into_iter()
+        .find_map(|packet| match packet {
+            PgpPacket::LiteralDataPacket(literal) => Some(literal),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("decrypted message has no literal data packet"))?;
+
+    write_output(output, &literal.data)
+}
+
+/// Read a secret key, which may be either ascii armored or binary, unlocking it with
+/// `passphrase` if one is given.
+fn read_secret_key(path: &str, passphrase: Option<&str>) -> anyhow::Result<SecretKey> {
+    let mut key = SecretKey::parse(&fs::read(path)?)?;
+    if let Some(passphrase) = passphrase {
+        key.unlock(passphrase)?;
+    }
+
+    Ok(key)
+}
+
+/// Wrap binary data in ascii armor of the given kind, if `armor` is set.
+fn maybe_armor(kind: AsciiArmorKind, data: Vec<u8>, armor: bool) -> Vec<u8> {
+    if armor {
+        AsciiArmor::new(kind, data).to_string().into_bytes()
+    } else {
+        data
+    }
+}
+
+/// Write data to `output`, or to stdout if no output is given.
+fn write_output(output: Option<&str>, data: &[u8]) -> anyhow::Result<()> {
+    match output {
+        Some(output) => fs::write(output, data)?,
+        None => io::stdout().write_all(data)?,
+    }
+
+    Ok(())
+}
+
+/// Generate a key of type `key_type` for `user_id`, writing the public key to `public_key_path`
+/// and the secret key (protected with `passphrase`, if one is given) to `secret_key_path`. The
+/// secret key is protected with `aead_algorithm` and Argon2 if it is given, and in CFB mode
+/// otherwise.
+pub fn generate_key(
+    user_id: &str,
+    key_type: KeyType,
+    passphrase: Option<&str>,
+    aead_algorithm: Option<AeadAlgorithm>,
+    public_key_path: &str,
+    secret_key_path: &str,
+) -> anyhow::Result<()> {
+    println!("Generating {}.", key_type);
+    let mut secret_key = SecretKey::generate(key_type, user_id)?;
+    println!(
+        "Key fingerprint is {}.",
+        format_fingerprint(&secret_key.fingerprint())
+    );
+
+    fs::write(public_key_path, secret_key.public_key_armor()?.to_string())?;
+    println!("Public key written to {}.", public_key_path);
+
+    match (passphrase, aead_algorithm) {
+        (Some(passphrase), Some(aead_algorithm)) => {
+            secret_key.protect_aead(passphrase, aead_algorithm)?
+        }
+        (Some(passphrase), None) => secret_key.protect(passphrase)?,
+        (None, _) => {}
+    }
+    fs::write(secret_key_path, secret_key.secret_key_armor()?.to_string())?;
+    println!("Secret key written to {}.", secret_key_path);
+
+    Ok(())
+}
+
+/// Describe who made a signature: the primary user ID of the certificate, or the key ID of the
+/// signing key if the certificate has no self-certified user IDs.
+fn describe_signer(key: &PublicKey, signature: &SignaturePacket) -> anyhow::Result<String> {
+    let signing_key = key.find_signing_key(signature)?;
+
+    Ok(match key.primary_user_id() {
+        Some(user_id) => user_id.to_owned(),
+        None => format!("key 0x{:016X}", signing_key.key_id()),
+    })
+}
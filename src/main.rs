@@ -1,5 +1,8 @@
 use anyhow::anyhow;
 use clap::{clap_app, ArgMatches};
+use pgp_rs::pgp::aead::AeadAlgorithm;
+use pgp_rs::pgp::secret_key::KeyType;
+use std::fs;
 
 fn main() -> anyhow::Result<()> {
     let matches = clap_app!(("pgp-rs") =>
@@ -7,12 +10,92 @@ fn main() -> anyhow::Result<()> {
         (author: "Andrew Halle <ahalle@berkeley.edu>")
         (about: "PGP tool written in Rust.")
         (@subcommand ("verify") =>
-            (about: "verify a clearsigned message")
+            (about: "verify a clearsigned or signed message, or a file against a detached signature")
             (@arg source: -s --source +takes_value
                 "Sets the source file containing the message to verify. Defaults to 'msg.txt.asc'.")
             (@arg publicKey: --publicKey +takes_value
                 "Sets the public key containing the public key which verifies the \
                  message. Defaults to 'public.pgp'.")
+            (@arg detached: --detached +takes_value requires[file]
+                "Sets the detached signature (armored or binary) to verify the file against.")
+            (@arg file: "Sets the file to verify against a detached signature.")
+            (@arg output: -o --output +takes_value
+                "Sets the file to write the contents of a signed message to. Defaults to stdout.")
+        )
+        (@subcommand ("sign") =>
+            (about: "sign a message")
+            (@arg source: -s --source +takes_value
+                "Sets the source file containing the message to sign. Defaults to 'msg.txt'.")
+            (@arg secretKey: --secretKey +takes_value
+                "Sets the file containing the secret key which signs the message. Defaults to \
+                 'secret.pgp'.")
+            (@arg passphrase: --passphrase +takes_value
+                "Sets the passphrase which unlocks the secret key, if it is protected.")
+            (@arg clear: --clear conflicts_with[detach]
+                "Makes a cleartext signature, which keeps the message readable.")
+            (@arg detach: --detach
+                "Makes a detached signature, without the message. By default, the message is \
+                 signed with a one-pass signature.")
+            (@arg text: --text conflicts_with[clear]
+                "Signs the message as text, whose line endings are canonicalized.")
+            (@arg armor: -a --armor conflicts_with[clear]
+                "Writes the signed message or detached signature with ascii armor.")
+            (@arg output: -o --output +takes_value
+                "Sets the file to write the signed message to. Defaults to stdout.")
+        )
+        (@subcommand ("encrypt") =>
+            (about: "encrypt a message")
+            (@arg source: -s --source +takes_value
+                "Sets the source file containing the message to encrypt. Defaults to 'msg.txt'.")
+            (@arg recipient: -r --recipient +takes_value +multiple number_of_values(1)
+                required_unless_present[symmetric]
+                "Adds a file containing the public key of a recipient. May be given more than \
+                 once.")
+            (@arg symmetric: -c --symmetric requires[passphrase]
+                "Also encrypts the message with a passphrase, which is all that is needed to \
+                 decrypt it.")
+            (@arg passphrase: --passphrase +takes_value requires[symmetric]
+                "Sets the passphrase to encrypt the message with.")
+            (@arg aead: --aead +takes_value possible_value[ocb eax gcm]
+                "Encrypts the data with an AEAD algorithm, in the format of RFC 9580.")
+            (@arg armor: -a --armor "Writes the encrypted message with ascii armor.")
+            (@arg output: -o --output +takes_value
+                "Sets the file to write the encrypted message to. Defaults to stdout.")
+        )
+        (@subcommand ("decrypt") =>
+            (about: "decrypt a message")
+            (@arg source: -s --source +takes_value
+                "Sets the source file containing the message to decrypt. Defaults to \
+                 'msg.txt.gpg'.")
+            (@arg secretKey: --secretKey +takes_value
+                "Sets the file containing the secret key which decrypts the message. Defaults \
+                 to 'secret.pgp', unless the message is decrypted with a passphrase.")
+            (@arg passphrase: --passphrase +takes_value
+                "Sets the passphrase which unlocks the secret key, if it is protected. Without \
+                 a secret key, decrypts a message encrypted with a passphrase.")
+            (@arg output: -o --output +takes_value
+                "Sets the file to write the decrypted message to. Defaults to stdout.")
+        )
+        (@subcommand ("generate-key") =>
+            (about: "generate a new key pair")
+            (@arg userId: -u --userId +takes_value +required
+                "Sets the user ID of the key, e.g. 'Alice <alice@example.org>'.")
+            (@arg algorithm: --algorithm +takes_value possible_value[rsa ed25519]
+                "Sets the algorithm of the key. 'ed25519' generates an Ed25519 primary key with \
+                 a Curve25519 encryption subkey. Defaults to 'rsa'.")
+            (@arg bits: --bits +takes_value possible_value[2048 3072 4096]
+                "Sets the size of the RSA key in bits. Defaults to 3072.")
+            (@arg passphrase: --passphrase +takes_value
+                "Sets the passphrase which protects the secret key. By default, the secret key \
+                 is not protected.")
+            (@arg aead: --aead +takes_value possible_value[ocb eax gcm] requires[passphrase]
+                "Protects the secret key with the given AEAD mode and an Argon2 S2K, as \
+                 described by RFC 9580. Older implementations, such as GnuPG 2.2, can't read \
+                 such keys. By default, the secret key is protected in CFB mode.")
+            (@arg publicKey: --publicKey +takes_value
+                "Sets the file to write the public key to. Defaults to 'public.pgp'.")
+            (@arg secretKey: --secretKey +takes_value
+                "Sets the file to write the secret key to. Defaults to 'secret.pgp'.")
         )
 
     )
@@ -20,14 +103,117 @@ fn main() -> anyhow::Result<()> {
 
     if let Some(matches) = matches.subcommand_matches("verify") {
         verify(matches)
+    } else if let Some(matches) = matches.subcommand_matches("sign") {
+        sign(matches)
+    } else if let Some(matches) = matches.subcommand_matches("encrypt") {
+        encrypt(matches)
+    } else if let Some(matches) = matches.subcommand_matches("decrypt") {
+        decrypt(matches)
+    } else if let Some(matches) = matches.subcommand_matches("generate-key") {
+        generate_key(matches)
     } else {
         Err(anyhow!("unknown subcommand"))
     }
 }
 
 fn verify(matches: &ArgMatches) -> anyhow::Result<()> {
+    let public_key_path = matches.value_of("publicKey").unwrap_or("public.pgp");
+
+    if let Some(signature_path) = matches.value_of("detached") {
+        let file = matches.value_of("file").unwrap();
+
+        return pgp_rs::verify_detached_signature(signature_path, file, public_key_path);
+    }
+
     let source = matches.value_of("source").unwrap_or("msg.txt.asc");
+
+    if fs::read(source)?.starts_with(b"-----BEGIN PGP SIGNED MESSAGE-----") {
+        pgp_rs::verify_cleartext_message(source, public_key_path)
+    } else {
+        let output = matches.value_of("output");
+
+        pgp_rs::verify_signed_message(source, public_key_path, output)
+    }
+}
+
+fn sign(matches: &ArgMatches) -> anyhow::Result<()> {
+    let source = matches.value_of("source").unwrap_or("msg.txt");
+    let secret_key_path = matches.value_of("secretKey").unwrap_or("secret.pgp");
+    let passphrase = matches.value_of("passphrase");
+    let output = matches.value_of("output");
+
+    let text = matches.is_present("text");
+    let armor = matches.is_present("armor");
+
+    if matches.is_present("clear") {
+        pgp_rs::sign_cleartext(source, secret_key_path, passphrase, output)
+    } else if matches.is_present("detach") {
+        pgp_rs::sign_detached(source, secret_key_path, passphrase, text, armor, output)
+    } else {
+        pgp_rs::sign_message(source, secret_key_path, passphrase, text, armor, output)
+    }
+}
+
+fn encrypt(matches: &ArgMatches) -> anyhow::Result<()> {
+    let source = matches.value_of("source").unwrap_or("msg.txt");
+    let recipient_paths = matches
+        .values_of("recipient")
+        .map(|paths| paths.collect::<Vec<_>>())
+        .unwrap_or_default();
+    let passphrase = matches.value_of("passphrase");
+    let aead_algorithm = parse_aead_algorithm(matches.value_of("aead"));
+    let armor = matches.is_present("armor");
+    let output = matches.value_of("output");
+
+    pgp_rs::encrypt(
+        source,
+        &recipient_paths,
+        passphrase,
+        aead_algorithm,
+        armor,
+        output,
+    )
+}
+
+fn decrypt(matches: &ArgMatches) -> anyhow::Result<()> {
+    let source = matches.value_of("source").unwrap_or("msg.txt.gpg");
+    let secret_key_path = matches.value_of("secretKey");
+    let passphrase = matches.value_of("passphrase");
+    let output = matches.value_of("output");
+
+    pgp_rs::decrypt(source, secret_key_path, passphrase, output)
+}
+
+fn generate_key(matches: &ArgMatches) -> anyhow::Result<()> {
+    let user_id = matches.value_of("userId").unwrap();
+    let key_type = match matches.value_of("algorithm").unwrap_or("rsa") {
+        "rsa" => KeyType::Rsa(matches.value_of("bits").unwrap_or("3072").parse()?),
+        _ if matches.is_present("bits") => {
+            return Err(anyhow!("--bits can only be used with RSA keys"))
+        }
+        _ => KeyType::Ed25519,
+    };
+    let passphrase = matches.value_of("passphrase");
+    let aead_algorithm = parse_aead_algorithm(matches.value_of("aead"));
     let public_key_path = matches.value_of("publicKey").unwrap_or("public.pgp");
+    let secret_key_path = matches.value_of("secretKey").unwrap_or("secret.pgp");
 
-    pgp_rs::verify_cleartext_message(source, public_key_path)
+    pgp_rs::generate_key(
+        user_id,
+        key_type,
+        passphrase,
+        aead_algorithm,
+        public_key_path,
+        secret_key_path,
+    )
+}
+
+/// The AEAD algorithm named by an `--aead` argument.
+fn parse_aead_algorithm(name: Option<&str>) -> Option<AeadAlgorithm> {
+    match name {
+        Some("ocb") => Some(AeadAlgorithm::Ocb),
+        Some("eax") => Some(AeadAlgorithm::Eax),
+        Some("gcm") => Some(AeadAlgorithm::Gcm),
+        _ => None,
+    }
 }
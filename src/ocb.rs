@@ -0,0 +1,334 @@
+//! OCB authenticated encryption, which OpenPGP uses as its default AEAD mode. It is implemented
+//! here because the RustCrypto `ocb3` crate is not among the dependencies this crate can be built
+//! with, and OCB is simple enough on top of the `aes` block ciphers, which are. The tests check
+//! it against the sample results of RFC 7253.
+
+use aes::cipher::consts::U16;
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser};
+use anyhow::anyhow;
+use std::convert::TryInto;
+
+/// The size of a block, and of the tag, in octets.
+const BLOCK_SIZE: usize = 16;
+
+/// The largest nonce OCB allows, in octets.
+pub const MAX_NONCE_SIZE: usize = 15;
+
+/// OCB authenticated encryption (RFC 7253) with a 128 bit tag, over any block cipher with 128 bit
+/// blocks. Blocks are handled as big-endian `u128`s, which makes the offset arithmetic simple.
+pub struct Ocb<C> {
+    cipher: C,
+    l_star: u128,
+    l_dollar: u128,
+    /// L_i for each i, enough for any message whose length fits in a `usize`.
+    l: Vec<u128>,
+}
+
+impl<C> Ocb<C>
+where
+    C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>,
+{
+    pub fn new(cipher: C) -> Ocb<C> {
+        let mut ocb = Ocb {
+            cipher,
+            l_star: 0,
+            l_dollar: 0,
+            l: Vec::new(),
+        };
+
+        ocb.l_star = ocb.encipher(0);
+        ocb.l_dollar = double(ocb.l_star);
+        let mut l_i = double(ocb.l_dollar);
+        for _ in 0..usize::BITS {
+            ocb.l.push(l_i);
+            l_i = double(l_i);
+        }
+
+        ocb
+    }
+
+    /// Encrypt `plaintext`, returning the ciphertext followed by the tag.
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        associated_data: &[u8],
+        plaintext: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut offset = self.initial_offset(nonce)?;
+        let mut checksum = 0;
+
+        let mut ciphertext = Vec::with_capacity(plaintext.len() + BLOCK_SIZE);
+        let mut blocks = plaintext.chunks_exact(BLOCK_SIZE);
+        for (i, block) in (&mut blocks).enumerate() {
+            let block = to_block(block);
+            offset ^= self.l[(i + 1).trailing_zeros() as usize];
+            ciphertext.extend_from_slice(&(offset ^ self.encipher(block ^ offset)).to_be_bytes());
+            checksum ^= block;
+        }
+
+        let remainder = blocks.remainder();
+        if !remainder.is_empty() {
+            offset ^= self.l_star;
+            let pad = self.encipher(offset).to_be_bytes();
+            ciphertext.extend(remainder.iter().zip(pad.iter()).map(|(p, pad)| p ^ pad));
+            checksum ^= pad_block(remainder);
+        }
+
+        let tag = self.encipher(checksum ^ offset ^ self.l_dollar) ^ self.hash(associated_data);
+        ciphertext.extend_from_slice(&tag.to_be_bytes());
+
+        Ok(ciphertext)
+    }
+
+    /// Decrypt `ciphertext`, which ends with the tag, returning the plaintext if the tag is
+    /// valid.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        associated_data: &[u8],
+        ciphertext: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        if ciphertext.len() < BLOCK_SIZE {
+            return Err(anyhow!("OCB ciphertext is too short to hold a tag"));
+        }
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - BLOCK_SIZE);
+
+        let mut offset = self.initial_offset(nonce)?;
+        let mut checksum = 0;
+
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        let mut blocks = ciphertext.chunks_exact(BLOCK_SIZE);
+        for (i, block) in (&mut blocks).enumerate() {
+            offset ^= self.l[(i + 1).trailing_zeros() as usize];
+            let block = offset ^ self.decipher(to_block(block) ^ offset);
+            plaintext.extend_from_slice(&block.to_be_bytes());
+            checksum ^= block;
+        }
+
+        let remainder = blocks.remainder();
+        if !remainder.is_empty() {
+            offset ^= self.l_star;
+            let pad = self.encipher(offset).to_be_bytes();
+            let block: Vec<u8> = remainder
+                .iter()
+                .zip(pad.iter())
+                .map(|(c, pad)| c ^ pad)
+                .collect();
+            checksum ^= pad_block(&block);
+            plaintext.extend_from_slice(&block);
+        }
+
+        let expected =
+            self.encipher(checksum ^ offset ^ self.l_dollar) ^ self.hash(associated_data);
+        // the comparison doesn't stop at the first difference, so it takes the same time however
+        // much of the tag is right.
+        let difference = expected
+            .to_be_bytes()
+            .iter()
+            .zip(tag.iter())
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+        if difference != 0 {
+            return Err(anyhow!("OCB tag did not match"));
+        }
+
+        Ok(plaintext)
+    }
+
+    /// The offset before the first block, derived from the nonce as described by the RFC in
+    /// section 4.2.
+    fn initial_offset(&self, nonce: &[u8]) -> anyhow::Result<u128> {
+        if nonce.is_empty() || nonce.len() > MAX_NONCE_SIZE {
+            return Err(anyhow!("OCB nonces are 1 to 15 octets long"));
+        }
+
+        // with a 128 bit tag the encoded tag length is zero, so the nonce is just the given
+        // nonce, preceded by a single one bit.
+        let mut block = [0; BLOCK_SIZE];
+        block[BLOCK_SIZE - nonce.len()..].copy_from_slice(nonce);
+        block[BLOCK_SIZE - 1 - nonce.len()] |= 1;
+        let block = u128::from_be_bytes(block);
+
+        let bottom = (block & 0x3f) as u32;
+        let ktop = self.encipher(block & !0x3f);
+        let stretch = ((ktop >> 64) as u64) ^ ((ktop >> 56) as u64);
+
+        if bottom == 0 {
+            Ok(ktop)
+        } else {
+            Ok((ktop << bottom) | (stretch >> (64 - bottom)) as u128)
+        }
+    }
+
+    /// HASH of the associated data, as defined by the RFC in section 4.1.
+    fn hash(&self, associated_data: &[u8]) -> u128 {
+        let mut offset = 0;
+        let mut sum = 0;
+
+        let mut blocks = associated_data.chunks_exact(BLOCK_SIZE);
+        for (i, block) in (&mut blocks).enumerate() {
+            offset ^= self.l[(i + 1).trailing_zeros() as usize];
+            sum ^= self.encipher(to_block(block) ^ offset);
+        }
+
+        let remainder = blocks.remainder();
+        if !remainder.is_empty() {
+            offset ^= self.l_star;
+            sum ^= self.encipher(pad_block(remainder) ^ offset);
+        }
+
+        sum
+    }
+
+    fn encipher(&self, block: u128) -> u128 {
+        let mut block = GenericArray::from(block.to_be_bytes());
+        self.cipher.encrypt_block(&mut block);
+
+        u128::from_be_bytes(block.into())
+    }
+
+    fn decipher(&self, block: u128) -> u128 {
+        let mut block = GenericArray::from(block.to_be_bytes());
+        self.cipher.decrypt_block(&mut block);
+
+        u128::from_be_bytes(block.into())
+    }
+}
+
+/// Doubling in GF(2^128), as defined by the RFC in section 2.
+fn double(block: u128) -> u128 {
+    let carry = if block >> 127 == 1 { 0x87 } else { 0 };
+
+    (block << 1) ^ carry
+}
+
+fn to_block(data: &[u8]) -> u128 {
+    u128::from_be_bytes(data.try_into().expect("a full block"))
+}
+
+/// Pad a partial block with a single one bit, followed by zeros.
+fn pad_block(data: &[u8]) -> u128 {
+    let mut block = [0; BLOCK_SIZE];
+    block[..data.len()].copy_from_slice(data);
+    block[data.len()] = 0x80;
+
+    u128::from_be_bytes(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::KeyInit;
+    use aes::{Aes128, Aes256};
+
+    fn hex(data: &str) -> Vec<u8> {
+        (0..data.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&data[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// The sample results from RFC 7253 appendix A, with key 000102030405060708090A0B0C0D0E0F.
+    #[test]
+    fn test_encrypt() {
+        let ocb =
+            Ocb::new(Aes128::new_from_slice(&hex("000102030405060708090A0B0C0D0E0F")).unwrap());
+
+        let samples = [
+            (
+                "BBAA99887766554433221100",
+                "",
+                "",
+                "785407BFFFC8AD9EDCC5520AC9111EE6",
+            ),
+            (
+                "BBAA99887766554433221101",
+                "0001020304050607",
+                "0001020304050607",
+                "6820B3657B6F615A5725BDA0D3B4EB3A257C9AF1F8F03009",
+            ),
+            (
+                "BBAA99887766554433221102",
+                "0001020304050607",
+                "",
+                "81017F8203F081277152FADE694A0A00",
+            ),
+            (
+                "BBAA99887766554433221103",
+                "",
+                "0001020304050607",
+                "45DD69F8F5AAE72414054CD1F35D82760B2CD00D2F99BFA9",
+            ),
+        ];
+        for (nonce, associated_data, plaintext, ciphertext) in samples.iter() {
+            let (nonce, associated_data) = (hex(nonce), hex(associated_data));
+            let (plaintext, ciphertext) = (hex(plaintext), hex(ciphertext));
+
+            assert_eq!(
+                ocb.encrypt(&nonce, &associated_data, &plaintext).unwrap(),
+                ciphertext
+            );
+            assert_eq!(
+                ocb.decrypt(&nonce, &associated_data, &ciphertext).unwrap(),
+                plaintext
+            );
+        }
+    }
+
+    #[test]
+    fn test_decrypt_tampered() {
+        let ocb = Ocb::new(Aes128::new_from_slice(&[7; 16]).unwrap());
+        let nonce = [1; MAX_NONCE_SIZE];
+        let plaintext: Vec<u8> = (0..100).collect();
+
+        let mut ciphertext = ocb.encrypt(&nonce, b"header", &plaintext).unwrap();
+        assert_eq!(
+            ocb.decrypt(&nonce, b"header", &ciphertext).unwrap(),
+            plaintext
+        );
+        assert!(ocb.decrypt(&nonce, b"other", &ciphertext).is_err());
+
+        ciphertext[40] ^= 1;
+        assert!(ocb.decrypt(&nonce, b"header", &ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_invalid_nonce_size() {
+        let ocb = Ocb::new(Aes128::new_from_slice(&[7; 16]).unwrap());
+
+        for nonce in [&[][..], &[1; MAX_NONCE_SIZE + 1][..]].iter() {
+            assert!(ocb.encrypt(nonce, b"header", b"plaintext").is_err());
+            assert!(ocb.decrypt(nonce, b"header", &[0; 32]).is_err());
+        }
+    }
+
+    /// OpenPGP uses 15 octet nonces with OCB. The results were computed with OpenSSL.
+    #[test]
+    fn test_encrypt_with_long_nonce() {
+        let key: Vec<u8> = (0..32).collect();
+        let ocb = Ocb::new(Aes256::new_from_slice(&key).unwrap());
+        let nonce: Vec<u8> = (100..115).collect();
+        let plaintext: Vec<u8> = (0..70).collect();
+        let ciphertext = hex(
+            "6efeec443afe3a2904c589894c23464cd2e3f293cf6a7adf0bc070a605617efab7368812b45d741c7cfd6f\
+             43431a3b588c1544e4cf787d205c714276fa8d4af238e080826ba0a1a293275d615f309375414e7897a03a",
+        );
+        let associated_data = [0xd2, 0x02, 0x09, 0x02, 0x0c];
+
+        assert_eq!(
+            ocb.encrypt(&nonce, &associated_data, &plaintext).unwrap(),
+            ciphertext
+        );
+        assert_eq!(
+            ocb.decrypt(&nonce, &associated_data, &ciphertext).unwrap(),
+            plaintext
+        );
+
+        let key: Vec<u8> = (0..16).collect();
+        let ocb = Ocb::new(Aes128::new_from_slice(&key).unwrap());
+        assert_eq!(
+            ocb.encrypt(&[0; MAX_NONCE_SIZE], b"x", b"").unwrap(),
+            hex("4afc97466de32a1c937e15a553ace4b5")
+        );
+    }
+}
@@ -8,19 +8,15 @@ use super::utils::fold_into_string;
 const BASE64_LINE_LENGTH: usize = 64_usize;
 
 fn is_base64_digit(c: char) -> bool {
-    (c >= '0' && c <= '9')
-        || (c >= 'A' && c <= 'Z')
-        || (c >= 'a' && c <= 'z')
-        || c == '+'
-        || c == '/'
-        || c == '='
+    c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '='
 }
 
 /// Parse a chunk of base64 encoded text.
 pub fn parse_base64(input: &str) -> IResult<&str, String> {
     let (input, mut base64) = fold_into_string(input, parse_base64_line)?;
 
-    if input.chars().next() == Some('=') {
+    // the data is followed by either the checksum or, if the checksum is omitted, the footer.
+    if input.starts_with('=') || input.starts_with('-') {
         return Ok((input, base64));
     }
 
@@ -35,7 +31,7 @@ pub fn parse_base64(input: &str) -> IResult<&str, String> {
 /// Parse a single line of length BASE64_LINE_LENGTH which contains only base64 characters.
 /// (and does not begin with an '='.)
 fn parse_base64_line(input: &str) -> IResult<&str, &str> {
-    if input.chars().next() == Some('=') {
+    if input.starts_with('=') {
         return Err(nom::Err::Error((input, nom::error::ErrorKind::Char)));
     }
 
@@ -57,7 +53,7 @@ mod tests {
                      aaa\n";
         let expected = "";
         assert_eq!(
-            parse_base64(&input),
+            parse_base64(input),
             Ok((
                 expected,
                 "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
@@ -71,7 +67,7 @@ mod tests {
                      =a";
         let expected = "=a";
         assert_eq!(
-            parse_base64(&input),
+            parse_base64(input),
             Ok((
                 expected,
                 "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_owned()
@@ -84,7 +80,7 @@ mod tests {
         let input = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\nbbb";
         let expected = "bbb";
         assert_eq!(
-            parse_base64_line(&input),
+            parse_base64_line(input),
             Ok((
                 expected,
                 "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
@@ -0,0 +1,362 @@
+use nom::bytes::complete::take;
+use nom::combinator::{all_consuming, map, verify};
+use nom::error::ErrorKind;
+use nom::{Err, IResult};
+
+use super::pgp_utils::{
+    parse_aead_algorithm, parse_mpi, parse_mpi_bytes, parse_symmetric_algorithm,
+};
+use super::s2k::parse_string_to_key;
+use super::utils::{parse_u64, take_single_byte};
+use crate::pgp::encryption::{
+    AeadParameters, EncryptedSessionKey, IntegrityProtectedDataPacket,
+    PublicKeyEncryptedSessionKeyPacket, SymmetricKeyEncryptedSessionKeyPacket,
+    MAX_CHUNK_SIZE_OCTET, SALT_SIZE,
+};
+use crate::pgp::key::PublicKeyAlgorithm;
+use crate::pgp::PgpPacket;
+use std::convert::TryInto;
+
+/// Parse a public-key encrypted session key packet as defined by the RFC in section 5.1. v6
+/// packets (RFC 9580 section 5.1) carry the version and fingerprint of the recipient key instead
+/// of its key ID, or nothing if the recipient is hidden.
+pub fn parse_public_key_encrypted_session_key_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (input, version) = take_single_byte(input)?;
+    let (input, key_id, key_version, fingerprint) = if version == 6 {
+        let (input, length) = take_single_byte(input)?;
+        let (input, recipient) = take(length)(input)?;
+        match recipient.split_first() {
+            Some((key_version, fingerprint)) => {
+                // v6 key IDs are the start of the fingerprint, v4 key IDs the end.
+                let key_id_offset = if *key_version == 6 {
+                    0
+                } else {
+                    fingerprint.len().saturating_sub(8)
+                };
+                let (_, key_id) = parse_u64(&fingerprint[key_id_offset..])?;
+                (
+                    input,
+                    key_id,
+                    Some(*key_version),
+                    Some(fingerprint.to_owned()),
+                )
+            }
+            None => (input, 0, None, None),
+        }
+    } else {
+        let (input, key_id) = parse_u64(input)?;
+        (input, key_id, None, None)
+    };
+    let (input, public_key_algorithm) = map(take_single_byte, PublicKeyAlgorithm::from)(input)?;
+
+    let (empty, encrypted_session_key) = match public_key_algorithm {
+        PublicKeyAlgorithm::Rsa | PublicKeyAlgorithm::RsaEncryptOnly => {
+            let (input, value) = parse_mpi(input)?;
+            (input, EncryptedSessionKey::Rsa { value })
+        }
+        PublicKeyAlgorithm::Ecdh => {
+            let (input, ephemeral_point) = parse_mpi_bytes(input)?;
+            let (input, length) = take_single_byte(input)?;
+            let (input, wrapped_key) = take(length)(input)?;
+            (
+                input,
+                EncryptedSessionKey::Ecdh {
+                    ephemeral_point: ephemeral_point.to_owned(),
+                    wrapped_key: wrapped_key.to_owned(),
+                },
+            )
+        }
+        PublicKeyAlgorithm::X25519 => {
+            let (input, ephemeral) = take(32_usize)(input)?;
+            let (input, length) = take_single_byte(input)?;
+            let (input, fields) = take(length)(input)?;
+            // the symmetric algorithm is only given, in the clear, by v3 packets.
+            let (wrapped_key, symmetric_algorithm) = if version == 3 {
+                let (fields, symmetric_algorithm) = take_single_byte(fields)?;
+                (fields, Some(symmetric_algorithm))
+            } else {
+                (fields, None)
+            };
+            (
+                input,
+                EncryptedSessionKey::X25519 {
+                    ephemeral: ephemeral.to_owned(),
+                    symmetric_algorithm,
+                    wrapped_key: wrapped_key.to_owned(),
+                },
+            )
+        }
+        _ => {
+            let (empty, data) = take(input.len())(input)?;
+            (
+                empty,
+                EncryptedSessionKey::Unknown {
+                    data: data.to_owned(),
+                },
+            )
+        }
+    };
+
+    Ok((
+        empty,
+        PgpPacket::PublicKeyEncryptedSessionKeyPacket(PublicKeyEncryptedSessionKeyPacket {
+            version,
+            key_id,
+            key_version,
+            fingerprint,
+            public_key_algorithm,
+            encrypted_session_key,
+        }),
+    ))
+}
+
+/// Parse a symmetric-key encrypted session key packet as defined by the RFC in section 5.3.
+/// Versions 4 and 6 are supported. v6 packets (RFC 9580 section 5.3.2) add an AEAD algorithm and
+/// nonce, and give the length of the parameters, and of the S2K specifier.
+pub fn parse_symmetric_key_encrypted_session_key_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (input, version) =
+        verify(take_single_byte, |version| *version == 4 || *version == 6)(input)?;
+
+    let (input, (symmetric_algorithm, aead_algorithm, s2k, nonce)) = if version == 6 {
+        let (input, length) = take_single_byte(input)?;
+        let (input, parameters) = take(length)(input)?;
+
+        let (parameters, symmetric_algorithm) = parse_symmetric_algorithm(parameters)?;
+        let (parameters, aead_algorithm) = parse_aead_algorithm(parameters)?;
+        let (parameters, s2k_length) = take_single_byte(parameters)?;
+        let (nonce, s2k) = take(s2k_length)(parameters)?;
+        let (_, s2k) = all_consuming(parse_string_to_key)(s2k)?;
+        if nonce.len() != aead_algorithm.nonce_size() {
+            return Err(Err::Error((input, ErrorKind::Verify)));
+        }
+
+        (
+            input,
+            (
+                symmetric_algorithm,
+                Some(aead_algorithm),
+                s2k,
+                Some(nonce.to_owned()),
+            ),
+        )
+    } else {
+        let (input, symmetric_algorithm) = parse_symmetric_algorithm(input)?;
+        let (input, s2k) = parse_string_to_key(input)?;
+
+        (input, (symmetric_algorithm, None, s2k, None))
+    };
+    let (empty, encrypted_session_key) = take(input.len())(input)?;
+
+    Ok((
+        empty,
+        PgpPacket::SymmetricKeyEncryptedSessionKeyPacket(SymmetricKeyEncryptedSessionKeyPacket {
+            version,
+            symmetric_algorithm,
+            aead_algorithm,
+            s2k,
+            nonce,
+            encrypted_session_key: encrypted_session_key.to_owned(),
+        }),
+    ))
+}
+
+/// Parse a symmetrically encrypted integrity protected data packet as defined by the RFC in
+/// section 5.13. v2 packets (RFC 9580 section 5.13.2) start with their AEAD parameters. The data
+/// is left encrypted.
+pub fn parse_integrity_protected_data_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (input, version) = take_single_byte(input)?;
+    let (input, aead) = if version == 2 {
+        let (input, symmetric_algorithm) = parse_symmetric_algorithm(input)?;
+        let (input, aead_algorithm) = parse_aead_algorithm(input)?;
+        let (input, chunk_size_octet) = verify(take_single_byte, |chunk_size_octet| {
+            *chunk_size_octet <= MAX_CHUNK_SIZE_OCTET
+        })(input)?;
+        let (input, salt) = take(SALT_SIZE)(input)?;
+
+        let parameters = AeadParameters {
+            symmetric_algorithm,
+            aead_algorithm,
+            chunk_size_octet,
+            salt: salt.try_into().expect("salt has the right size"),
+        };
+        (input, Some(parameters))
+    } else {
+        (input, None)
+    };
+    let (empty, data) = take(input.len())(input)?;
+
+    Ok((
+        empty,
+        PgpPacket::IntegrityProtectedDataPacket(IntegrityProtectedDataPacket {
+            version,
+            aead,
+            data: data.to_owned(),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgp::aead::AeadAlgorithm;
+    use crate::pgp::hash::HashAlgorithm;
+    use crate::pgp::s2k::StringToKey;
+    use crate::pgp::symmetric::SymmetricAlgorithm;
+    use num::BigUint;
+
+    #[test]
+    fn test_parse_public_key_encrypted_session_key_packet() {
+        let input: [u8; 14] = [
+            0x03, 0xe7, 0x88, 0x8a, 0xe6, 0x8c, 0xbc, 0x7f, 0x2b, 0x01, 0x00, 0x09, 0x01, 0xff,
+        ];
+        let (rest, packet) = parse_public_key_encrypted_session_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicKeyEncryptedSessionKeyPacket(packet) => {
+                assert_eq!(packet.version, 3);
+                assert_eq!(packet.key_id, 0xe788_8ae6_8cbc_7f2b);
+                assert_eq!(packet.public_key_algorithm, PublicKeyAlgorithm::Rsa);
+                match &packet.encrypted_session_key {
+                    EncryptedSessionKey::Rsa { value } => {
+                        assert_eq!(*value, BigUint::from(0x1ff_u32))
+                    }
+                    _ => panic!("expected an rsa encrypted session key"),
+                }
+                assert_eq!(packet.to_bytes().unwrap(), input);
+            }
+            _ => panic!("expected a public-key encrypted session key packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_public_key_encrypted_session_key_packet_v6() {
+        // a hidden recipient, followed by the X25519 ephemeral key and a wrapped AES-128 key,
+        // without a symmetric algorithm.
+        let mut input = vec![0x06, 0x00, 0x19];
+        input.extend_from_slice(&[0x01; 32]);
+        input.push(0x18);
+        input.extend_from_slice(&[0x02; 24]);
+        let (rest, packet) = parse_public_key_encrypted_session_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicKeyEncryptedSessionKeyPacket(packet) => {
+                assert_eq!(packet.version, 6);
+                assert_eq!(packet.key_id, 0);
+                assert!(packet.fingerprint.is_none());
+                assert_eq!(packet.public_key_algorithm, PublicKeyAlgorithm::X25519);
+                match &packet.encrypted_session_key {
+                    EncryptedSessionKey::X25519 {
+                        ephemeral,
+                        symmetric_algorithm,
+                        wrapped_key,
+                    } => {
+                        assert_eq!(*ephemeral, [0x01; 32]);
+                        assert!(symmetric_algorithm.is_none());
+                        assert_eq!(*wrapped_key, [0x02; 24]);
+                    }
+                    _ => panic!("expected an X25519 encrypted session key"),
+                }
+                assert_eq!(packet.to_bytes().unwrap(), input);
+            }
+            _ => panic!("expected a public-key encrypted session key packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_symmetric_key_encrypted_session_key_packet() {
+        // AES-256 with an iterated and salted SHA-1 specifier, and no encrypted session key.
+        let input: [u8; 13] = [
+            0x04, 0x09, 0x03, 0x02, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0xff,
+        ];
+        let (rest, packet) = parse_symmetric_key_encrypted_session_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::SymmetricKeyEncryptedSessionKeyPacket(packet) => {
+                assert_eq!(packet.version, 4);
+                assert_eq!(packet.symmetric_algorithm, SymmetricAlgorithm::Aes256);
+                assert_eq!(
+                    packet.s2k,
+                    StringToKey::IteratedSalted {
+                        hash_algorithm: HashAlgorithm::Sha1,
+                        salt: [1, 2, 3, 4, 5, 6, 7, 8],
+                        count: 0xff,
+                    }
+                );
+                assert!(packet.encrypted_session_key.is_empty());
+                assert_eq!(packet.to_bytes().unwrap(), input);
+            }
+            _ => panic!("expected a symmetric-key encrypted session key packet"),
+        }
+
+        let mut unsupported = input;
+        unsupported[0] = 5;
+        assert!(parse_symmetric_key_encrypted_session_key_packet(&unsupported).is_err());
+    }
+
+    #[test]
+    fn test_parse_symmetric_key_encrypted_session_key_packet_v6() {
+        // AES-128 with OCB and a salted SHA-256 specifier, then the 15 octet nonce, and an
+        // encrypted session key with its tag.
+        let mut input = vec![0x06, 0x1c, 0x07, 0x02, 0x0a, 0x01, 0x08];
+        input.extend_from_slice(&[0x5a; 8]);
+        input.extend_from_slice(&[0x4e; 15]);
+        input.extend_from_slice(&[0xe5; 32]);
+        let (rest, packet) = parse_symmetric_key_encrypted_session_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::SymmetricKeyEncryptedSessionKeyPacket(packet) => {
+                assert_eq!(packet.version, 6);
+                assert_eq!(packet.symmetric_algorithm, SymmetricAlgorithm::Aes128);
+                assert_eq!(packet.aead_algorithm, Some(AeadAlgorithm::Ocb));
+                assert_eq!(
+                    packet.s2k,
+                    StringToKey::Salted {
+                        hash_algorithm: HashAlgorithm::Sha256,
+                        salt: [0x5a; 8],
+                    }
+                );
+                assert_eq!(packet.nonce, Some(vec![0x4e; 15]));
+                assert_eq!(packet.encrypted_session_key, vec![0xe5; 32]);
+                assert_eq!(packet.to_bytes().unwrap(), input);
+            }
+            _ => panic!("expected a symmetric-key encrypted session key packet"),
+        }
+
+        // a nonce which is too short for OCB.
+        let mut short_nonce = input[..29].to_vec();
+        short_nonce[1] = 0x1b;
+        assert!(parse_symmetric_key_encrypted_session_key_packet(&short_nonce).is_err());
+    }
+
+    #[test]
+    fn test_parse_integrity_protected_data_packet_v2() {
+        let mut input = vec![0x02, 0x09, 0x03, 0x0c];
+        input.extend_from_slice(&[0x33; 32]);
+        input.extend_from_slice(&[0xd0; 20]);
+        let (rest, packet) = parse_integrity_protected_data_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::IntegrityProtectedDataPacket(packet) => {
+                assert_eq!(packet.version, 2);
+                let parameters = packet.aead.as_ref().unwrap();
+                assert_eq!(parameters.symmetric_algorithm, SymmetricAlgorithm::Aes256);
+                assert_eq!(parameters.aead_algorithm, AeadAlgorithm::Gcm);
+                assert_eq!(parameters.chunk_size(), 262_144);
+                assert_eq!(parameters.salt, [0x33; 32]);
+                assert_eq!(packet.data, vec![0xd0; 20]);
+                assert_eq!(packet.to_bytes(), input);
+            }
+            _ => panic!("expected an encrypted data packet"),
+        }
+
+        // chunks larger than 4 MiB aren't allowed.
+        let mut large_chunks = input.clone();
+        large_chunks[3] = 17;
+        assert!(parse_integrity_protected_data_packet(&large_chunks).is_err());
+    }
+}
@@ -1,33 +1,826 @@
-use nom::bytes::complete::take;
-use nom::IResult;
+use nom::bytes::complete::{tag, take};
+use nom::combinator::{all_consuming, cond, map, verify};
+use nom::error::ErrorKind;
+use nom::sequence::tuple;
+use nom::{Err, IResult};
 
-use super::pgp_utils::parse_mpi;
-use crate::pgp::key::PublicKeyPacket;
+use super::pgp_utils::{
+    parse_aead_algorithm, parse_long_length_tagged_data, parse_mpi, parse_mpi_bytes,
+    parse_symmetric_algorithm,
+};
+use super::s2k::{parse_gnu_dummy_s2k, parse_string_to_key};
+use super::utils::{parse_u16, parse_u32, take_single_byte};
+use crate::pgp::aead::AeadAlgorithm;
+use crate::pgp::key::{
+    Curve, PublicKeyAlgorithm, PublicKeyMaterial, PublicKeyPacket, UserIdPacket,
+    NATIVE_POINT_PREFIX,
+};
+use crate::pgp::s2k::StringToKey;
+use crate::pgp::secret_key::{
+    SecretKeyChecksum, SecretKeyMaterial, SecretKeyPacket, SecretKeyProtection,
+};
+use crate::pgp::symmetric::SymmetricAlgorithm;
 use crate::pgp::PgpPacket;
+use crate::utils::sum16;
 
-pub fn parse_public_key_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
-    // skips the unneeded fields
-    //  - version (assumed to be 4)
-    //  - time key was created
-    //  - public-key algorithm (assumed to be RSA)
-    let (input, _) = take(6_usize)(input)?;
+/// The largest v4 key packet, whose length is hashed as two octets for fingerprints and key
+/// signatures.
+const MAX_V4_KEY_PACKET_SIZE: usize = 0xffff;
+
+/// Parse the body shared by public key and public subkey packets, as defined by the RFC in
+/// section 5.5.2.
+fn parse_key_packet_body(input: &[u8]) -> IResult<&[u8], PublicKeyPacket> {
+    let (rest, mut key) = parse_public_key_fields(input)?;
+
+    // skip the rest, which is still part of the key when hashing it.
+    let (empty, _) = take(rest.len())(rest)?;
+    if key.version != 6 && input.len() > MAX_V4_KEY_PACKET_SIZE {
+        return Err(Err::Error((input, ErrorKind::TooLarge)));
+    }
+    key.packet_data = input.to_owned();
+
+    Ok((empty, key))
+}
+
+/// Parse the public fields of a key, which also start secret key packets. The packet data of the
+/// returned key is only the part of the input which was parsed. Only v4 and v6 keys are
+/// supported; v3 and v5 keys are laid out and fingerprinted differently, and are rejected.
+fn parse_public_key_fields(packet: &[u8]) -> IResult<&[u8], PublicKeyPacket> {
+    let input = packet;
+    let (input, version) =
+        verify(take_single_byte, |version| *version == 4 || *version == 6)(input)?;
+    let (input, creation_time) = parse_u32(input)?;
+    let (input, public_key_algorithm) = map(take_single_byte, PublicKeyAlgorithm::from)(input)?;
+    let (input, key_material) = if version == 6 {
+        // v6 keys give the length of the key material, as described by RFC 9580 section 5.5.2.3.
+        let (input, key_material) = parse_long_length_tagged_data(input)?;
+        let (_, key_material) = parse_key_material(key_material, public_key_algorithm)?;
+        (input, key_material)
+    } else {
+        parse_key_material(input, public_key_algorithm)?
+    };
+    let packet_data = &packet[..packet.len() - input.len()];
+    if version != 6 && packet_data.len() > MAX_V4_KEY_PACKET_SIZE {
+        return Err(Err::Error((packet, ErrorKind::TooLarge)));
+    }
+
+    Ok((
+        input,
+        PublicKeyPacket {
+            version,
+            creation_time,
+            public_key_algorithm,
+            key_material,
+            packet_data: packet_data.to_owned(),
+        },
+    ))
+}
+
+/// Parse the body shared by secret key and secret subkey packets, as defined by the RFC in
+/// section 5.5.3. Unprotected key material is parsed right away, while protected key material
+/// is kept encrypted until the key is unlocked.
+fn parse_secret_key_packet_body(input: &[u8]) -> IResult<&[u8], SecretKeyPacket> {
+    let (input, public_key) = parse_public_key_fields(input)?;
+    let (input, s2k_usage) = take_single_byte(input)?;
+
+    match s2k_usage {
+        0 => {
+            let (empty, secret_key_material) = parse_unprotected_material(input, &public_key)?;
+
+            Ok((
+                empty,
+                SecretKeyPacket {
+                    public_key,
+                    protection: SecretKeyProtection::Unprotected,
+                    secret_key_material: Some(secret_key_material),
+                },
+            ))
+        }
+        253 => {
+            let (encrypted_data, (symmetric_algorithm, aead_algorithm, s2k, nonce)) =
+                parse_protection_parameters(input, public_key.version, true)?;
+            let aead_algorithm = aead_algorithm.expect("AEAD parameters were parsed");
+            let (empty, encrypted_data) = take(encrypted_data.len())(encrypted_data)?;
+
+            Ok((
+                empty,
+                SecretKeyPacket {
+                    public_key,
+                    protection: SecretKeyProtection::Aead {
+                        symmetric_algorithm,
+                        aead_algorithm,
+                        s2k,
+                        nonce: nonce.to_owned(),
+                        encrypted_data: encrypted_data.to_owned(),
+                    },
+                    secret_key_material: None,
+                },
+            ))
+        }
+        254 | 255 if public_key.version == 4 && is_gnu_dummy(input) => {
+            let (empty, (symmetric_algorithm, hash_algorithm)) =
+                all_consuming(tuple((take_single_byte, parse_gnu_dummy_s2k)))(input)?;
+
+            Ok((
+                empty,
+                SecretKeyPacket {
+                    public_key,
+                    protection: SecretKeyProtection::GnuDummy {
+                        s2k_usage,
+                        symmetric_algorithm,
+                        hash_algorithm,
+                    },
+                    secret_key_material: None,
+                },
+            ))
+        }
+        254 | 255 => {
+            let (encrypted_data, (symmetric_algorithm, _, s2k, iv)) =
+                parse_protection_parameters(input, public_key.version, false)?;
+            let checksum = if s2k_usage == 254 {
+                SecretKeyChecksum::Sha1
+            } else {
+                SecretKeyChecksum::Sum16
+            };
+            let (empty, encrypted_data) = take(encrypted_data.len())(encrypted_data)?;
+
+            Ok((
+                empty,
+                SecretKeyPacket {
+                    public_key,
+                    protection: SecretKeyProtection::Cfb {
+                        symmetric_algorithm,
+                        s2k,
+                        iv: iv.to_owned(),
+                        encrypted_data: encrypted_data.to_owned(),
+                        checksum,
+                    },
+                    secret_key_material: None,
+                },
+            ))
+        }
+        _ => Err(Err::Error((input, ErrorKind::Switch))),
+    }
+}
+
+/// Whether the protection parameters of a v4 key, which start with the symmetric algorithm, use
+/// the GNU dummy S2K.
+fn is_gnu_dummy(input: &[u8]) -> bool {
+    input
+        .get(1..)
+        .is_some_and(|s2k| parse_gnu_dummy_s2k(s2k).is_ok())
+}
+
+/// Parse unprotected key material. v4 keys follow it with a two octet checksum, which v6 keys
+/// leave out.
+fn parse_unprotected_material<'a>(
+    input: &'a [u8],
+    public_key: &PublicKeyPacket,
+) -> IResult<&'a [u8], SecretKeyMaterial> {
+    let (empty, material_data, checksum) = if public_key.version == 6 {
+        (&input[input.len()..], input, None)
+    } else {
+        let (checksum, material_data) = take(input.len().saturating_sub(2))(input)?;
+        let (empty, checksum) = parse_u16(checksum)?;
+        (empty, material_data, Some(checksum))
+    };
+
+    if checksum.is_some_and(|checksum| checksum != sum16(material_data)) {
+        return Err(Err::Error((input, ErrorKind::Verify)));
+    }
+
+    let (_, secret_key_material) =
+        parse_secret_key_material_all_consuming(material_data, &public_key.key_material)?;
+
+    Ok((empty, secret_key_material))
+}
+
+/// The symmetric algorithm, AEAD algorithm, S2K specifier and IV or nonce of encrypted key
+/// material.
+type ProtectionParameters<'a> = (
+    SymmetricAlgorithm,
+    Option<AeadAlgorithm>,
+    StringToKey,
+    &'a [u8],
+);
+
+/// Parse the parameters of encrypted key material: the symmetric algorithm, the AEAD algorithm
+/// (only for AEAD protected material), the S2K specifier and the IV or nonce. v6 keys give the
+/// length of the parameters, and of the S2K specifier, as described by RFC 9580 section 5.5.3.
+/// Argon2 may only be used with AEAD.
+fn parse_protection_parameters(
+    input: &[u8],
+    version: u8,
+    aead: bool,
+) -> IResult<&[u8], ProtectionParameters<'_>> {
+    let (input, parameters) = if version == 6 {
+        let (input, length) = take_single_byte(input)?;
+        let (input, parameters) = take(length)(input)?;
+
+        let (parameters, symmetric_algorithm) = parse_symmetric_algorithm(parameters)?;
+        let (parameters, aead_algorithm) = cond(aead, parse_aead_algorithm)(parameters)?;
+        let (parameters, s2k_length) = take_single_byte(parameters)?;
+        let (iv, s2k) = take(s2k_length)(parameters)?;
+        let (_, s2k) = all_consuming(parse_string_to_key)(s2k)?;
+        if iv.len() != iv_size(symmetric_algorithm, aead_algorithm) {
+            return Err(Err::Error((input, ErrorKind::Verify)));
+        }
+
+        (input, (symmetric_algorithm, aead_algorithm, s2k, iv))
+    } else {
+        let (input, symmetric_algorithm) = parse_symmetric_algorithm(input)?;
+        let (input, aead_algorithm) = cond(aead, parse_aead_algorithm)(input)?;
+        let (input, s2k) = parse_string_to_key(input)?;
+        let (input, iv) = take(iv_size(symmetric_algorithm, aead_algorithm))(input)?;
+
+        (input, (symmetric_algorithm, aead_algorithm, s2k, iv))
+    };
+
+    if !aead && matches!(parameters.2, StringToKey::Argon2 { .. }) {
+        return Err(Err::Error((input, ErrorKind::Verify)));
+    }
+
+    Ok((input, parameters))
+}
+
+/// The size of the IV of CFB encrypted key material, or of the nonce of AEAD encrypted key
+/// material.
+fn iv_size(
+    symmetric_algorithm: SymmetricAlgorithm,
+    aead_algorithm: Option<AeadAlgorithm>,
+) -> usize {
+    match aead_algorithm {
+        Some(aead_algorithm) => aead_algorithm.nonce_size(),
+        None => symmetric_algorithm.block_size(),
+    }
+}
+
+/// Parse the secret key material of a key with the given public key material, which must take
+/// up all of the input.
+pub fn parse_secret_key_material_all_consuming<'a>(
+    input: &'a [u8],
+    key_material: &PublicKeyMaterial,
+) -> IResult<&'a [u8], SecretKeyMaterial> {
+    all_consuming(|input| parse_secret_key_material(input, key_material))(input)
+}
+
+/// Parse the algorithm specific secret part of a key. The material of algorithms which can't be
+/// used is kept as is.
+fn parse_secret_key_material<'a>(
+    input: &'a [u8],
+    key_material: &PublicKeyMaterial,
+) -> IResult<&'a [u8], SecretKeyMaterial> {
+    match key_material {
+        PublicKeyMaterial::Rsa { .. } => {
+            let (input, d) = parse_mpi(input)?;
+            let (input, p) = parse_mpi(input)?;
+            let (input, q) = parse_mpi(input)?;
+            let (input, u) = parse_mpi(input)?;
+
+            Ok((input, SecretKeyMaterial::Rsa { d, p, q, u }))
+        }
+        PublicKeyMaterial::EdDsa {
+            curve: Curve::Ed25519,
+            ..
+        } => map(parse_mpi_bytes, |seed| SecretKeyMaterial::EdDsa {
+            seed: pad_left(seed, 32),
+        })(input),
+        PublicKeyMaterial::Ecdh { curve, .. } => {
+            map(parse_mpi_bytes, |secret| SecretKeyMaterial::Ecdh {
+                secret: match curve {
+                    Curve::Cv25519 => pad_left(secret, 32),
+                    _ => secret.to_owned(),
+                },
+            })(input)
+        }
+        PublicKeyMaterial::Ed25519 { .. } => {
+            map(take(32_usize), |secret: &[u8]| SecretKeyMaterial::Ed25519 {
+                secret: secret.to_owned(),
+            })(input)
+        }
+        PublicKeyMaterial::X25519 { .. } => {
+            map(take(32_usize), |secret: &[u8]| SecretKeyMaterial::X25519 {
+                secret: secret.to_owned(),
+            })(input)
+        }
+        _ => map(take(input.len()), |data: &[u8]| {
+            SecretKeyMaterial::Unknown {
+                data: data.to_owned(),
+            }
+        })(input),
+    }
+}
+
+/// Restore the leading zero octets of a fixed size value which was stored as an MPI.
+fn pad_left(value: &[u8], size: usize) -> Vec<u8> {
+    let mut padded = vec![0; size.saturating_sub(value.len())];
+    padded.extend_from_slice(value);
+
+    padded
+}
+
+/// Parse the algorithm specific part of a key. The material of unsupported algorithms is skipped.
+fn parse_key_material(
+    input: &[u8],
+    public_key_algorithm: PublicKeyAlgorithm,
+) -> IResult<&[u8], PublicKeyMaterial> {
+    match public_key_algorithm {
+        PublicKeyAlgorithm::Rsa
+        | PublicKeyAlgorithm::RsaEncryptOnly
+        | PublicKeyAlgorithm::RsaSignOnly => {
+            let (input, n) = parse_mpi(input)?;
+            let (input, e) = parse_mpi(input)?;
+
+            Ok((input, PublicKeyMaterial::Rsa { n, e }))
+        }
+        PublicKeyAlgorithm::Dsa => {
+            let (input, p) = parse_mpi(input)?;
+            let (input, q) = parse_mpi(input)?;
+            let (input, g) = parse_mpi(input)?;
+            let (input, y) = parse_mpi(input)?;
+
+            Ok((input, PublicKeyMaterial::Dsa { p, q, g, y }))
+        }
+        PublicKeyAlgorithm::ElGamal => {
+            let (input, p) = parse_mpi(input)?;
+            let (input, g) = parse_mpi(input)?;
+            let (input, y) = parse_mpi(input)?;
+
+            Ok((input, PublicKeyMaterial::ElGamal { p, g, y }))
+        }
+        PublicKeyAlgorithm::Ecdsa => {
+            let (input, curve) = parse_curve_oid(input)?;
+            let (input, point) = parse_mpi_bytes(input)?;
+
+            let key_material = match curve {
+                Some(curve) => PublicKeyMaterial::Ecdsa {
+                    curve,
+                    point: point.to_owned(),
+                },
+                None => PublicKeyMaterial::Unknown,
+            };
 
-    // get the needed fields from the key
-    let (input, n) = parse_mpi(input)?;
-    let (input, e) = parse_mpi(input)?;
+            Ok((input, key_material))
+        }
+        PublicKeyAlgorithm::EdDsa => {
+            let (input, curve) = parse_curve_oid(input)?;
+            let (input, point) = parse_mpi_bytes(input)?;
 
-    // skip the rest
-    let (empty, _) = take(input.len())(input)?;
+            match (curve, point) {
+                (Some(curve), [NATIVE_POINT_PREFIX, public_key @ ..]) => Ok((
+                    input,
+                    PublicKeyMaterial::EdDsa {
+                        curve,
+                        public_key: public_key.to_owned(),
+                    },
+                )),
+                (Some(_), _) => Err(Err::Error((input, ErrorKind::Verify))),
+                (None, _) => Ok((input, PublicKeyMaterial::Unknown)),
+            }
+        }
+        PublicKeyAlgorithm::Ecdh => {
+            let (input, curve) = parse_curve_oid(input)?;
+            let (input, point) = parse_mpi_bytes(input)?;
+            let (input, (kdf_hash_algorithm, kdf_symmetric_algorithm)) =
+                parse_kdf_parameters(input)?;
 
-    Ok((empty, PgpPacket::PublicKeyPacket(PublicKeyPacket { n, e })))
+            let key_material = match curve {
+                Some(curve) => PublicKeyMaterial::Ecdh {
+                    curve,
+                    point: point.to_owned(),
+                    kdf_hash_algorithm,
+                    kdf_symmetric_algorithm,
+                },
+                None => PublicKeyMaterial::Unknown,
+            };
+
+            Ok((input, key_material))
+        }
+        PublicKeyAlgorithm::Ed25519 => map(take(32_usize), |public_key: &[u8]| {
+            PublicKeyMaterial::Ed25519 {
+                public_key: public_key.to_owned(),
+            }
+        })(input),
+        PublicKeyAlgorithm::X25519 => map(take(32_usize), |public_key: &[u8]| {
+            PublicKeyMaterial::X25519 {
+                public_key: public_key.to_owned(),
+            }
+        })(input),
+        PublicKeyAlgorithm::Unknown(_) => Ok((input, PublicKeyMaterial::Unknown)),
+    }
+}
+
+/// Parse the KDF parameters of an ECDH key, as defined by RFC 6637 section 9: a length octet, a
+/// reserved octet which must be 1, the hash algorithm ID and the symmetric algorithm ID.
+fn parse_kdf_parameters(input: &[u8]) -> IResult<&[u8], (u8, u8)> {
+    let (input, _) = tag(&[0x03, 0x01])(input)?;
+    let (input, hash_algorithm) = take_single_byte(input)?;
+    let (input, symmetric_algorithm) = take_single_byte(input)?;
+
+    Ok((input, (hash_algorithm, symmetric_algorithm)))
+}
+
+/// Parse the curve OID of an ECC key, as defined by RFC 4880bis section 5.6. Curves which aren't
+/// supported are returned as `None`.
+fn parse_curve_oid(input: &[u8]) -> IResult<&[u8], Option<Curve>> {
+    let (input, length) = take_single_byte(input)?;
+    let (input, oid) = take(length)(input)?;
+
+    Ok((input, Curve::from_oid(oid)))
+}
+
+pub fn parse_public_key_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (empty, key) = parse_key_packet_body(input)?;
+    Ok((empty, PgpPacket::PublicKeyPacket(key)))
 }
 
+/// Parse a user ID packet as defined by the RFC in section 5.11. User IDs are UTF-8 by
+/// convention, invalid sequences are replaced rather than rejected.
 pub fn parse_user_id_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
-    let (empty, _) = take(input.len())(input)?;
-    Ok((empty, PgpPacket::UserIdPacket))
+    let (empty, user_id) = take(input.len())(input)?;
+
+    Ok((
+        empty,
+        PgpPacket::UserIdPacket(UserIdPacket {
+            user_id: String::from_utf8_lossy(user_id).into_owned(),
+        }),
+    ))
 }
 
 pub fn parse_public_subkey_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
-    let (empty, _) = take(input.len())(input)?;
-    Ok((empty, PgpPacket::PublicSubkeyPacket))
+    let (empty, key) = parse_key_packet_body(input)?;
+    Ok((empty, PgpPacket::PublicSubkeyPacket(key)))
+}
+
+pub fn parse_secret_key_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (empty, key) = parse_secret_key_packet_body(input)?;
+    Ok((empty, PgpPacket::SecretKeyPacket(key)))
+}
+
+pub fn parse_secret_subkey_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (empty, key) = parse_secret_key_packet_body(input)?;
+    Ok((empty, PgpPacket::SecretSubkeyPacket(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgp::hash::HashAlgorithm;
+    use num::BigUint;
+
+    #[test]
+    fn test_parse_public_subkey_packet() {
+        let input: [u8; 13] = [
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x01, 0x00, 0x09, 0x01, 0x2b, 0x00, 0x02, 0x03,
+        ];
+        let (rest, packet) = parse_public_subkey_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicSubkeyPacket(key) => {
+                assert_eq!(key.version, 4);
+                assert_eq!(key.creation_time, 0x5f77d913);
+                assert_eq!(key.public_key_algorithm, PublicKeyAlgorithm::Rsa);
+                match key.key_material {
+                    PublicKeyMaterial::Rsa { n, e } => {
+                        assert_eq!(n, BigUint::from(0x12b_u32));
+                        assert_eq!(e, BigUint::from(3_u32));
+                    }
+                    _ => panic!("expected an rsa key"),
+                }
+                assert_eq!(key.packet_data, input);
+            }
+            _ => panic!("expected a public subkey packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_secret_key_packet() {
+        let input: [u8; 28] = [
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x01, 0x00, 0x09, 0x01, 0x2b, 0x00, 0x02, 0x03, 0x00,
+            0x00, 0x02, 0x03, 0x00, 0x02, 0x02, 0x00, 0x03, 0x05, 0x00, 0x01, 0x01, 0x00, 0x13,
+        ];
+        let (rest, packet) = parse_secret_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::SecretKeyPacket(key) => {
+                assert_eq!(key.public_key.packet_data, &input[..13]);
+                assert!(matches!(key.protection, SecretKeyProtection::Unprotected));
+                match key.secret_key_material {
+                    Some(SecretKeyMaterial::Rsa { d, p, q, u }) => {
+                        assert_eq!(d, BigUint::from(3_u32));
+                        assert_eq!(p, BigUint::from(2_u32));
+                        assert_eq!(q, BigUint::from(5_u32));
+                        assert_eq!(u, BigUint::from(1_u32));
+                    }
+                    _ => panic!("expected rsa secret key material"),
+                }
+            }
+            _ => panic!("expected a secret key packet"),
+        }
+
+        // a wrong checksum is rejected.
+        let mut corrupted = input;
+        corrupted[27] = 0x14;
+        assert!(parse_secret_key_packet(&corrupted).is_err());
+    }
+
+    #[test]
+    fn test_parse_secret_key_packet_protected() {
+        let mut input = vec![
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x01, 0x00, 0x09, 0x01, 0x2b, 0x00, 0x02, 0x03, 0xff,
+            0x07, 0x00, 0x02,
+        ];
+        input.extend_from_slice(&[0xaa; 16]);
+        input.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+        let (rest, packet) = parse_secret_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::SecretKeyPacket(key) => {
+                assert!(key.secret_key_material.is_none());
+                match &key.protection {
+                    SecretKeyProtection::Cfb {
+                        symmetric_algorithm,
+                        s2k,
+                        iv,
+                        encrypted_data,
+                        checksum,
+                    } => {
+                        assert_eq!(*symmetric_algorithm, SymmetricAlgorithm::Aes128);
+                        assert_eq!(
+                            *s2k,
+                            StringToKey::Simple {
+                                hash_algorithm: HashAlgorithm::Sha1
+                            }
+                        );
+                        assert_eq!(iv, &[0xaa; 16]);
+                        assert_eq!(encrypted_data, &[0x01, 0x02, 0x03, 0x04]);
+                        assert_eq!(*checksum, SecretKeyChecksum::Sum16);
+                    }
+                    _ => panic!("expected cfb protection"),
+                }
+                assert_eq!(key.to_bytes().unwrap(), input);
+            }
+            _ => panic!("expected a secret key packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_secret_key_packet_aead() {
+        // the v6 Ed25519 key from RFC 9580 appendix A.3, followed by AES256, OCB, and the
+        // lengths of the parameters and of the Argon2 S2K.
+        let mut input = vec![
+            0x06, 0x63, 0x87, 0x7f, 0xe3, 0x1b, 0x00, 0x00, 0x00, 0x20, 0xf9, 0x4d, 0xa7, 0xbb,
+            0x48, 0xd6, 0x0a, 0x61, 0xe5, 0x67, 0x70, 0x6a, 0x65, 0x87, 0xd0, 0x33, 0x19, 0x99,
+            0xbb, 0x9d, 0x89, 0x1a, 0x08, 0x24, 0x2e, 0xad, 0x84, 0x54, 0x3d, 0xf8, 0x95, 0xa3,
+            0xfd, 0x26, 0x09, 0x02, 0x14, 0x04,
+        ];
+        input.extend_from_slice(&[0x55; 16]);
+        input.extend_from_slice(&[0x03, 0x04, 0x10]);
+        input.extend_from_slice(&[0xbb; 15]);
+        input.extend_from_slice(&[0xcc; 48]);
+        let (rest, packet) = parse_secret_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::SecretKeyPacket(key) => {
+                assert!(key.secret_key_material.is_none());
+                match &key.protection {
+                    SecretKeyProtection::Aead {
+                        symmetric_algorithm,
+                        aead_algorithm,
+                        s2k,
+                        nonce,
+                        encrypted_data,
+                    } => {
+                        assert_eq!(*symmetric_algorithm, SymmetricAlgorithm::Aes256);
+                        assert_eq!(*aead_algorithm, AeadAlgorithm::Ocb);
+                        assert_eq!(
+                            *s2k,
+                            StringToKey::Argon2 {
+                                salt: [0x55; 16],
+                                t: 3,
+                                p: 4,
+                                encoded_m: 16,
+                            }
+                        );
+                        assert_eq!(nonce, &[0xbb; 15]);
+                        assert_eq!(encrypted_data, &[0xcc; 48][..]);
+                    }
+                    _ => panic!("expected aead protection"),
+                }
+                assert_eq!(key.to_bytes().unwrap(), input);
+            }
+            _ => panic!("expected a secret key packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_secret_key_packet_argon2_without_aead() {
+        let mut input = vec![
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x01, 0x00, 0x09, 0x01, 0x2b, 0x00, 0x02, 0x03, 0xfe,
+            0x07, 0x04,
+        ];
+        input.extend_from_slice(&[0x55; 16]);
+        input.extend_from_slice(&[0x03, 0x04, 0x10]);
+        input.extend_from_slice(&[0xaa; 16]);
+        input.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+
+        assert!(parse_secret_key_packet(&input).is_err());
+    }
+
+    #[test]
+    fn test_parse_public_key_packet_dsa() {
+        let input: [u8; 18] = [
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x11, 0x00, 0x02, 0x03, 0x00, 0x01, 0x01, 0x00, 0x02,
+            0x02, 0x00, 0x02, 0x03,
+        ];
+        let (rest, packet) = parse_public_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicKeyPacket(key) => {
+                assert_eq!(key.public_key_algorithm, PublicKeyAlgorithm::Dsa);
+                match key.key_material {
+                    PublicKeyMaterial::Dsa { p, q, g, y } => {
+                        assert_eq!(p, BigUint::from(3_u32));
+                        assert_eq!(q, BigUint::from(1_u32));
+                        assert_eq!(g, BigUint::from(2_u32));
+                        assert_eq!(y, BigUint::from(3_u32));
+                    }
+                    _ => panic!("expected a dsa key"),
+                }
+            }
+            _ => panic!("expected a public key packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_public_key_packet_too_long() {
+        // a v4 key whose packet is too long for the two-octet length it is hashed with.
+        let mut input = vec![
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x11, 0x00, 0x02, 0x03, 0x00, 0x01, 0x01, 0x00, 0x02,
+            0x02, 0x00, 0x02, 0x03,
+        ];
+        input.resize(0x10000, 0);
+
+        assert!(parse_public_key_packet(&input).is_err());
+        assert!(parse_public_key_packet(&input[..0xffff]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_public_key_packet_unknown_algorithm() {
+        let input = [0x04, 0x5f, 0x77, 0xd9, 0x13, 0x63, 0x01, 0x02, 0x03];
+        let (rest, packet) = parse_public_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicKeyPacket(key) => {
+                assert_eq!(key.public_key_algorithm, PublicKeyAlgorithm::Unknown(0x63));
+                assert_eq!(key.public_key_algorithm.id(), 0x63);
+                assert!(matches!(key.key_material, PublicKeyMaterial::Unknown));
+            }
+            _ => panic!("expected a public key packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_public_key_packet_unsupported_version() {
+        let mut input = [
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x01, 0x00, 0x01, 0x01, 0x00, 0x01, 0x01,
+        ];
+        assert!(parse_public_key_packet(&input).is_ok());
+
+        // v3 keys have a validity period, and v5 keys a material length and other fingerprints.
+        for version in [3, 5].iter() {
+            input[0] = *version;
+            assert!(parse_public_key_packet(&input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_public_key_packet_eddsa() {
+        let mut input = vec![
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x16, 0x09, 0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47,
+            0x0f, 0x01, 0x01, 0x07, 0x40,
+        ];
+        input.extend_from_slice(&[0xab; 32]);
+        let (rest, packet) = parse_public_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicKeyPacket(key) => match key.key_material {
+                PublicKeyMaterial::EdDsa { curve, public_key } => {
+                    assert_eq!(curve, Curve::Ed25519);
+                    assert_eq!(public_key, [0xab; 32]);
+                }
+                _ => panic!("expected an eddsa key"),
+            },
+            _ => panic!("expected a public key packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_curve_oid() {
+        let expected: &[u8] = &[];
+        assert_eq!(
+            parse_curve_oid(&[0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07]),
+            Ok((expected, Some(Curve::NistP256)))
+        );
+        assert_eq!(
+            parse_curve_oid(&[0x05, 0x2b, 0x81, 0x04, 0x00, 0x22]),
+            Ok((expected, Some(Curve::NistP384)))
+        );
+        assert_eq!(
+            parse_curve_oid(&[0x05, 0x2b, 0x81, 0x04, 0x00, 0x23]),
+            Ok((expected, Some(Curve::NistP521)))
+        );
+        assert_eq!(parse_curve_oid(&[0x02, 0x2b, 0x81]), Ok((expected, None)));
+    }
+
+    #[test]
+    fn test_parse_public_subkey_packet_ecdh() {
+        // the cv25519 subkey from tests/13.
+        let input: [u8; 56] = [
+            0x04, 0x6a, 0xd2, 0xe6, 0x8e, 0x12, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x97, 0x55,
+            0x01, 0x05, 0x01, 0x01, 0x07, 0x40, 0xa2, 0xd9, 0xdb, 0xd8, 0x62, 0x6c, 0xc9, 0x25,
+            0xf7, 0x8c, 0xda, 0x75, 0x97, 0x5b, 0x81, 0x72, 0xad, 0xa7, 0xe9, 0xaa, 0xdf, 0x89,
+            0x78, 0x13, 0x17, 0x0d, 0x4d, 0x46, 0xf9, 0xf2, 0xee, 0x74, 0x03, 0x01, 0x08, 0x07,
+        ];
+        let (rest, packet) = parse_public_subkey_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicSubkeyPacket(key) => match key.key_material {
+                PublicKeyMaterial::Ecdh {
+                    curve,
+                    point,
+                    kdf_hash_algorithm,
+                    kdf_symmetric_algorithm,
+                } => {
+                    assert_eq!(curve, Curve::Cv25519);
+                    assert_eq!(point, &input[19..52]);
+                    assert_eq!(kdf_hash_algorithm, 8);
+                    assert_eq!(kdf_symmetric_algorithm, 7);
+                }
+                _ => panic!("expected an ecdh key"),
+            },
+            _ => panic!("expected a public subkey packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_public_subkey_packet_elgamal() {
+        let input: [u8; 15] = [
+            0x04, 0x5f, 0x77, 0xd9, 0x13, 0x10, 0x00, 0x02, 0x03, 0x00, 0x02, 0x02, 0x00, 0x01,
+            0x01,
+        ];
+        let (rest, packet) = parse_public_subkey_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicSubkeyPacket(key) => match key.key_material {
+                PublicKeyMaterial::ElGamal { p, g, y } => {
+                    assert_eq!(p, BigUint::from(3_u32));
+                    assert_eq!(g, BigUint::from(2_u32));
+                    assert_eq!(y, BigUint::from(1_u32));
+                }
+                _ => panic!("expected an elgamal key"),
+            },
+            _ => panic!("expected a public subkey packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_public_key_packet_v6() {
+        // the v6 Ed25519 primary key from the sample certificate in RFC 9580 appendix A.3.
+        let input: [u8; 42] = [
+            0x06, 0x63, 0x87, 0x7f, 0xe3, 0x1b, 0x00, 0x00, 0x00, 0x20, 0xf9, 0x4d, 0xa7, 0xbb,
+            0x48, 0xd6, 0x0a, 0x61, 0xe5, 0x67, 0x70, 0x6a, 0x65, 0x87, 0xd0, 0x33, 0x19, 0x99,
+            0xbb, 0x9d, 0x89, 0x1a, 0x08, 0x24, 0x2e, 0xad, 0x84, 0x54, 0x3d, 0xf8, 0x95, 0xa3,
+        ];
+        let (rest, packet) = parse_public_key_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::PublicKeyPacket(key) => {
+                assert_eq!(key.version, 6);
+                assert_eq!(key.public_key_algorithm, PublicKeyAlgorithm::Ed25519);
+                assert_eq!(
+                    crate::pgp::key::format_fingerprint(&key.fingerprint()),
+                    "CB186C4F0609A697E4D52DFA6C722B0C1F1E27C18A56708F6525EC27BAD9ACC9"
+                );
+                assert_eq!(key.key_id(), 0xcb18_6c4f_0609_a697);
+                match key.key_material {
+                    PublicKeyMaterial::Ed25519 { public_key } => {
+                        assert_eq!(public_key, &input[10..]);
+                    }
+                    _ => panic!("expected an ed25519 key"),
+                }
+            }
+            _ => panic!("expected a public key packet"),
+        }
+    }
 }
@@ -0,0 +1,145 @@
+use nom::bytes::complete::take;
+use nom::combinator::map;
+use nom::IResult;
+
+use super::utils::{parse_u32, parse_u64, take_single_byte};
+use crate::pgp::key::PublicKeyAlgorithm;
+use crate::pgp::message::{CompressedDataPacket, LiteralDataPacket, OnePassSignaturePacket};
+use crate::pgp::PgpPacket;
+
+/// Parse a one-pass signature packet as defined by the RFC in section 5.4. v6 one-pass
+/// signatures (RFC 9580 section 5.4) carry the salt and the fingerprint instead of the key ID.
+pub fn parse_one_pass_signature_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (input, version) = take_single_byte(input)?;
+    let (input, signature_type) = take_single_byte(input)?;
+    let (input, hash_algorithm) = take_single_byte(input)?;
+    let (input, public_key_algorithm) = map(take_single_byte, PublicKeyAlgorithm::from)(input)?;
+    let (input, salt, fingerprint, key_id) = if version == 6 {
+        let (input, salt_size) = take_single_byte(input)?;
+        let (input, salt) = take(salt_size)(input)?;
+        let (input, fingerprint) = take(32_usize)(input)?;
+        let (_, key_id) = parse_u64(fingerprint)?;
+        (input, salt, Some(fingerprint.to_owned()), key_id)
+    } else {
+        let (input, key_id) = parse_u64(input)?;
+        (input, &[][..], None, key_id)
+    };
+    let (input, last) = take_single_byte(input)?;
+
+    Ok((
+        input,
+        PgpPacket::OnePassSignaturePacket(OnePassSignaturePacket {
+            version,
+            signature_type,
+            hash_algorithm,
+            public_key_algorithm,
+            salt: salt.to_owned(),
+            fingerprint,
+            key_id,
+            last: last != 0,
+        }),
+    ))
+}
+
+/// Parse a literal data packet as defined by the RFC in section 5.9.
+pub fn parse_literal_data_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (input, format) = take_single_byte(input)?;
+    let (input, filename_length) = take_single_byte(input)?;
+    let (input, filename) = take(filename_length)(input)?;
+    let (input, date) = parse_u32(input)?;
+    let (empty, data) = take(input.len())(input)?;
+
+    Ok((
+        empty,
+        PgpPacket::LiteralDataPacket(LiteralDataPacket {
+            format,
+            filename: filename.to_owned(),
+            date,
+            data: data.to_owned(),
+        }),
+    ))
+}
+
+/// Parse a compressed data packet as defined by the RFC in section 5.6. The data is left
+/// compressed.
+pub fn parse_compressed_data_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
+    let (input, algorithm) = take_single_byte(input)?;
+    let (empty, data) = take(input.len())(input)?;
+
+    Ok((
+        empty,
+        PgpPacket::CompressedDataPacket(CompressedDataPacket {
+            algorithm,
+            data: data.to_owned(),
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_one_pass_signature_packet() {
+        let input: [u8; 13] = [
+            0x03, 0x00, 0x0a, 0x01, 0xcc, 0xc0, 0xd1, 0x34, 0xd4, 0xdb, 0x1f, 0x93, 0x01,
+        ];
+        let (rest, packet) = parse_one_pass_signature_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::OnePassSignaturePacket(packet) => {
+                assert_eq!(packet.signature_type, 0x00);
+                assert_eq!(packet.hash_algorithm, 10);
+                assert_eq!(packet.key_id, 0xccc0d134d4db1f93);
+                assert!(packet.last);
+            }
+            _ => panic!("expected a one-pass signature packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_literal_data_packet() {
+        let input = b"b\x07msg.txt\x5f\x5c\x1e\x1ahello world\n";
+        let (rest, packet) = parse_literal_data_packet(input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::LiteralDataPacket(packet) => {
+                assert_eq!(packet.format, b'b');
+                assert_eq!(packet.filename, b"msg.txt");
+                assert_eq!(packet.date, 0x5f5c1e1a);
+                assert_eq!(packet.data, b"hello world\n");
+            }
+            _ => panic!("expected a literal data packet"),
+        }
+    }
+
+    #[test]
+    fn test_write_one_pass_signature_packet() {
+        let input: [u8; 13] = [
+            0x03, 0x00, 0x0a, 0x01, 0xcc, 0xc0, 0xd1, 0x34, 0xd4, 0xdb, 0x1f, 0x93, 0x01,
+        ];
+        let (_, packet) = parse_one_pass_signature_packet(&input).unwrap();
+
+        match packet {
+            PgpPacket::OnePassSignaturePacket(packet) => {
+                assert_eq!(packet.to_bytes().unwrap(), input)
+            }
+            _ => panic!("expected a one-pass signature packet"),
+        }
+    }
+
+    #[test]
+    fn test_write_literal_data_packet() {
+        let input = b"t\x07msg.txt\x5f\x5c\x1e\x1ahello world\r\n";
+        let (_, packet) = parse_literal_data_packet(input).unwrap();
+
+        match packet {
+            PgpPacket::LiteralDataPacket(packet) => {
+                assert_eq!(packet.to_bytes().unwrap(), input)
+            }
+            _ => panic!("expected a literal data packet"),
+        }
+    }
+}
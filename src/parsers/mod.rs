@@ -1,8 +1,12 @@
 mod base64;
+mod encryption;
 mod key;
+mod message;
 mod pgp_utils;
+mod s2k;
 mod signature;
 mod utils;
 
-pub use pgp_utils::{parse_ascii_armor_parts_all_consuming, parse_pgp_packets, parse_pkcs1};
+pub use key::parse_secret_key_material_all_consuming;
+pub use pgp_utils::{parse_ascii_armor_parts_all_consuming, parse_pgp_packets};
 pub use signature::parse_cleartext_signature_parts;
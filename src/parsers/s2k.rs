@@ -0,0 +1,187 @@
+use nom::bytes::complete::{tag, take};
+use nom::error::ErrorKind;
+use nom::{Err, IResult};
+use std::convert::{TryFrom, TryInto};
+
+use super::utils::take_single_byte;
+use crate::pgp::hash::HashAlgorithm;
+use crate::pgp::s2k::{StringToKey, GNU_DUMMY_S2K_MODE, GNU_S2K_MARKER, GNU_S2K_TYPE};
+
+/// Parse a string-to-key specifier as defined by the RFC in section 3.7.1.
+pub fn parse_string_to_key(input: &[u8]) -> IResult<&[u8], StringToKey> {
+    let (input, s2k_type) = take_single_byte(input)?;
+
+    match s2k_type {
+        0 => {
+            let (input, hash_algorithm) = parse_hash_algorithm(input)?;
+
+            Ok((input, StringToKey::Simple { hash_algorithm }))
+        }
+        1 => {
+            let (input, hash_algorithm) = parse_hash_algorithm(input)?;
+            let (input, salt) = parse_salt(input)?;
+
+            Ok((
+                input,
+                StringToKey::Salted {
+                    hash_algorithm,
+                    salt,
+                },
+            ))
+        }
+        3 => {
+            let (input, hash_algorithm) = parse_hash_algorithm(input)?;
+            let (input, salt) = parse_salt(input)?;
+            let (input, count) = take_single_byte(input)?;
+
+            Ok((
+                input,
+                StringToKey::IteratedSalted {
+                    hash_algorithm,
+                    salt,
+                    count,
+                },
+            ))
+        }
+        4 => {
+            let (input, salt) = take(16_usize)(input)?;
+            let (input, t) = take_single_byte(input)?;
+            let (input, p) = take_single_byte(input)?;
+            let (input, encoded_m) = take_single_byte(input)?;
+
+            Ok((
+                input,
+                StringToKey::Argon2 {
+                    salt: salt.try_into().expect("took 16 octets"),
+                    t,
+                    p,
+                    encoded_m,
+                },
+            ))
+        }
+        _ => Err(Err::Error((input, ErrorKind::Switch))),
+    }
+}
+
+/// Parse the GNU dummy S2K specifier of GnuPG: S2K type 101, a hash algorithm octet, "GNU" and
+/// mode 1. It marks a key whose secret key material was left out, as by `gpg
+/// --export-secret-subkeys`, and is followed by nothing else. Returns the hash algorithm octet,
+/// which is meaningless but kept to write the specifier back.
+pub fn parse_gnu_dummy_s2k(input: &[u8]) -> IResult<&[u8], u8> {
+    let (input, _) = tag([GNU_S2K_TYPE])(input)?;
+    let (input, hash_algorithm) = take_single_byte(input)?;
+    let (input, _) = tag(GNU_S2K_MARKER)(input)?;
+    let (input, _) = tag([GNU_DUMMY_S2K_MODE])(input)?;
+
+    Ok((input, hash_algorithm))
+}
+
+fn parse_hash_algorithm(input: &[u8]) -> IResult<&[u8], HashAlgorithm> {
+    let (rest, hash_algorithm) = take_single_byte(input)?;
+    let hash_algorithm = HashAlgorithm::try_from(hash_algorithm)
+        .map_err(|_| Err::Error((input, ErrorKind::Verify)))?;
+
+    Ok((rest, hash_algorithm))
+}
+
+fn parse_salt(input: &[u8]) -> IResult<&[u8], [u8; 8]> {
+    let (input, salt) = take(8_usize)(input)?;
+
+    Ok((input, salt.try_into().expect("took 8 octets")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_string_to_key_simple() {
+        let input: [u8; 2] = [0x00, 0x08];
+        let (rest, s2k) = parse_string_to_key(&input).unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(
+            s2k,
+            StringToKey::Simple {
+                hash_algorithm: HashAlgorithm::Sha256
+            }
+        );
+        assert_eq!(s2k.to_bytes(), input);
+    }
+
+    #[test]
+    fn test_parse_string_to_key_salted() {
+        let input: [u8; 10] = [0x01, 0x02, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let (rest, s2k) = parse_string_to_key(&input).unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(
+            s2k,
+            StringToKey::Salted {
+                hash_algorithm: HashAlgorithm::Sha1,
+                salt: [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+            }
+        );
+        assert_eq!(s2k.to_bytes(), input);
+    }
+
+    #[test]
+    fn test_parse_string_to_key_iterated_salted() {
+        let input: [u8; 11] = [
+            0x03, 0x0a, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0xe0,
+        ];
+        let (rest, s2k) = parse_string_to_key(&input).unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(
+            s2k,
+            StringToKey::IteratedSalted {
+                hash_algorithm: HashAlgorithm::Sha512,
+                salt: [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+                count: 0xe0,
+            }
+        );
+        assert_eq!(s2k.to_bytes(), input);
+    }
+
+    #[test]
+    fn test_parse_string_to_key_argon2() {
+        let mut input = vec![0x04];
+        input.extend(0x01..=0x10);
+        input.extend_from_slice(&[0x01, 0x04, 0x15]);
+        let (rest, s2k) = parse_string_to_key(&input).unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(
+            s2k,
+            StringToKey::Argon2 {
+                salt: [
+                    0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                    0x0e, 0x0f, 0x10,
+                ],
+                t: 1,
+                p: 4,
+                encoded_m: 21,
+            }
+        );
+        assert_eq!(s2k.to_bytes(), input);
+    }
+
+    #[test]
+    fn test_parse_string_to_key_unknown() {
+        let input: [u8; 2] = [0x02, 0x08];
+
+        assert!(parse_string_to_key(&input).is_err());
+    }
+
+    #[test]
+    fn test_parse_gnu_dummy_s2k() {
+        let input = [0x65, 0x00, 0x47, 0x4e, 0x55, 0x01];
+        assert_eq!(parse_gnu_dummy_s2k(&input), Ok((&[][..], 0x00)));
+
+        // mode 2 stores the secret key material on a smartcard, which isn't supported.
+        let input = [0x65, 0x00, 0x47, 0x4e, 0x55, 0x02];
+        assert!(parse_gnu_dummy_s2k(&input).is_err());
+        assert!(parse_string_to_key(&input).is_err());
+    }
+}
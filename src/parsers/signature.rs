@@ -1,20 +1,27 @@
 use super::utils::fold_into_string;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
+use nom::bytes::complete::take;
+use nom::character::complete::newline;
 use nom::combinator::all_consuming;
-use nom::combinator::{map, not, peek};
-use nom::multi::many1;
+use nom::combinator::{map, map_res, not, peek, rest, verify};
+use nom::multi::{many0, many1};
 use nom::sequence::tuple;
 use nom::IResult;
+use num::BigUint;
 
 use super::pgp_utils::{
-    parse_ascii_armor_parts, parse_hash_armor_header, parse_length_tagged_data, parse_mpi,
+    parse_ascii_armor_parts, parse_hash_armor_header, parse_length_tagged_data,
+    parse_long_length_tagged_data, parse_mpi,
 };
 use super::utils::parse_line_newline_inclusive;
 use super::utils::take_single_byte;
-use super::utils::{parse_dash, parse_space, parse_u16};
+use super::utils::{parse_dash, parse_space, parse_u16, parse_u32, parse_u64};
 
-use crate::pgp::signature::{CleartextSignatureParts, SignaturePacket};
+use crate::pgp::key::PublicKeyAlgorithm;
+use crate::pgp::signature::{
+    CleartextSignatureParts, SignaturePacket, SignatureSubPacket, SubPacket,
+};
 use crate::pgp::PgpPacket;
 
 /// Parse a set of lines (that may be dash-escaped) into a String. Stops when reaching a line
@@ -53,7 +60,11 @@ pub fn parse_non_dash_line(input: &str) -> IResult<&str, &str> {
 pub fn parse_cleartext_signature_parts(input: &str) -> IResult<&str, CleartextSignatureParts> {
     let parser = tuple((
         tag("-----BEGIN PGP SIGNED MESSAGE-----\n"),
-        map(parse_hash_armor_header, |s| String::from(s)),
+        // the "Hash" header may be omitted, in which case an empty line follows.
+        alt((
+            map(parse_hash_armor_header, |hash| Some(hash.to_owned())),
+            map(newline, |_| None),
+        )),
         parse_possibly_dash_escaped_chunk,
         parse_ascii_armor_parts,
     ));
@@ -63,17 +74,43 @@ pub fn parse_cleartext_signature_parts(input: &str) -> IResult<&str, CleartextSi
     Ok(("", (hash, msg, ascii_armor_parts)))
 }
 
+/// Parse a signature packet as defined by the RFC in section 5.2.3. Versions 4 and 6 (RFC 9580
+/// section 5.2.3) are supported.
 pub fn parse_signature_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
-    let (input, version) = take_single_byte(input)?;
+    let (input, version) =
+        verify(take_single_byte, |version| *version == 4 || *version == 6)(input)?;
     let (input, signature_type) = take_single_byte(input)?;
-    let (input, public_key_algorithm) = take_single_byte(input)?;
+    let (input, public_key_algorithm) = map(take_single_byte, PublicKeyAlgorithm::from)(input)?;
     let (input, hash_algorithm) = take_single_byte(input)?;
 
-    let (input, hashed_subpacket_data) = parse_length_tagged_data(input)?;
-    let (input, unhashed_subpacket_data) = parse_length_tagged_data(input)?;
+    // v6 signatures use 4 octet lengths for the subpacket areas.
+    let parse_subpacket_area = if version == 6 {
+        parse_long_length_tagged_data
+    } else {
+        parse_length_tagged_data
+    };
+    let (input, hashed_subpacket_data) = parse_subpacket_area(input)?;
+    let (_, hashed_subpackets) = parse_subpackets(hashed_subpacket_data)?;
+    let (input, unhashed_subpacket_data) = parse_subpacket_area(input)?;
+    let (_, unhashed_subpackets) = parse_subpackets(unhashed_subpacket_data)?;
     let (input, signed_hash_value_head) = parse_u16(input)?;
 
-    let (input, signature) = many1(parse_mpi)(input)?;
+    let (input, salt) = if version == 6 {
+        let (input, salt_size) = take_single_byte(input)?;
+        take(salt_size)(input)?
+    } else {
+        (input, &[][..])
+    };
+
+    let (input, signature) = if public_key_algorithm == PublicKeyAlgorithm::Ed25519 {
+        // native Ed25519 signatures are 64 raw octets rather than MPIs. they're split into the
+        // same two values as a legacy EdDSA signature, so that both can be verified alike.
+        map(tuple((take(32_usize), take(32_usize))), |(r, s)| {
+            vec![BigUint::from_bytes_be(r), BigUint::from_bytes_be(s)]
+        })(input)?
+    } else {
+        many1(parse_mpi)(input)?
+    };
 
     Ok((
         input,
@@ -83,13 +120,161 @@ pub fn parse_signature_packet(input: &[u8]) -> IResult<&[u8], PgpPacket> {
             public_key_algorithm,
             hash_algorithm,
             hashed_subpacket_data: hashed_subpacket_data.to_owned(),
-            unhashed_subpacket_data: unhashed_subpacket_data.to_owned(),
+            hashed_subpackets,
+            unhashed_subpackets,
             signed_hash_value_head,
+            salt: salt.to_owned(),
             signature,
         }),
     ))
 }
 
+/// Parse the length of a signature subpacket, as defined by the RFC in section 5.2.3.1.
+fn parse_subpacket_length(input: &[u8]) -> IResult<&[u8], u32> {
+    let (input, first) = take_single_byte(input)?;
+
+    match first {
+        0..=191 => Ok((input, first.into())),
+        192..=254 => {
+            let (input, second) = take_single_byte(input)?;
+            let length = ((u32::from(first) - 192) << 8) + u32::from(second) + 192;
+
+            Ok((input, length))
+        }
+        255 => parse_u32(input),
+    }
+}
+
+/// Parse an area of signature subpackets (either hashed or unhashed).
+pub fn parse_subpackets(input: &[u8]) -> IResult<&[u8], Vec<SubPacket>> {
+    all_consuming(many0(parse_subpacket))(input)
+}
+
+/// Parse a single signature subpacket. The high bit of the type octet is the critical bit.
+pub fn parse_subpacket(input: &[u8]) -> IResult<&[u8], SubPacket> {
+    let (input, length) = parse_subpacket_length(input)?;
+    let (input, body) = take(length)(input)?;
+
+    let (body, type_octet) = take_single_byte(body)?;
+    let critical = type_octet & 0x80 != 0;
+    let subpacket_type = type_octet & 0x7f;
+
+    let (_, subpacket) = all_consuming(|body| parse_subpacket_body(body, subpacket_type))(body)?;
+
+    Ok((
+        input,
+        SubPacket {
+            critical,
+            subpacket,
+        },
+    ))
+}
+
+fn parse_subpacket_body(input: &[u8], subpacket_type: u8) -> IResult<&[u8], SignatureSubPacket> {
+    // subpackets holding text which is not valid UTF-8 are kept as unknown subpackets, rather
+    // than failing the whole signature.
+    let unknown = |input| parse_unknown_subpacket(input, subpacket_type);
+
+    match subpacket_type {
+        2 => map(parse_u32, SignatureSubPacket::SignatureCreationTime)(input),
+        3 => map(parse_u32, SignatureSubPacket::SignatureExpirationTime)(input),
+        9 => map(parse_u32, SignatureSubPacket::KeyExpirationTime)(input),
+        11 => map(
+            parse_rest_owned,
+            SignatureSubPacket::PreferredSymmetricAlgorithms,
+        )(input),
+        16 => map(parse_u64, SignatureSubPacket::Issuer)(input),
+        20 => alt((parse_notation_data, unknown))(input),
+        21 => map(
+            parse_rest_owned,
+            SignatureSubPacket::PreferredHashAlgorithms,
+        )(input),
+        22 => map(
+            parse_rest_owned,
+            SignatureSubPacket::PreferredCompressionAlgorithms,
+        )(input),
+        25 => map(take_single_byte, |primary| {
+            SignatureSubPacket::PrimaryUserId(primary != 0)
+        })(input),
+        26 => alt((map(parse_rest_utf8, SignatureSubPacket::PolicyUri), unknown))(input),
+        27 => map(parse_rest_owned, SignatureSubPacket::KeyFlags)(input),
+        28 => alt((
+            map(parse_rest_utf8, SignatureSubPacket::SignersUserId),
+            unknown,
+        ))(input),
+        29 => alt((parse_reason_for_revocation, unknown))(input),
+        30 => map(parse_rest_owned, SignatureSubPacket::Features)(input),
+        32 => {
+            let (input, packet) = parse_signature_packet(input)?;
+            match packet {
+                PgpPacket::SignaturePacket(signature) => Ok((
+                    input,
+                    SignatureSubPacket::EmbeddedSignature(Box::new(signature)),
+                )),
+                _ => unreachable!(),
+            }
+        }
+        33 => {
+            let (input, version) = take_single_byte(input)?;
+            let (input, fingerprint) = parse_rest_owned(input)?;
+
+            Ok((
+                input,
+                SignatureSubPacket::IssuerFingerprint {
+                    version,
+                    fingerprint,
+                },
+            ))
+        }
+        _ => unknown(input),
+    }
+}
+
+/// Parse a subpacket which is not understood, keeping its body.
+fn parse_unknown_subpacket(input: &[u8], subpacket_type: u8) -> IResult<&[u8], SignatureSubPacket> {
+    map(parse_rest_owned, |data| SignatureSubPacket::Unknown {
+        subpacket_type,
+        data,
+    })(input)
+}
+
+/// Parse a reason for revocation subpacket, as defined by the RFC in section 5.2.3.23.
+fn parse_reason_for_revocation(input: &[u8]) -> IResult<&[u8], SignatureSubPacket> {
+    let (input, code) = take_single_byte(input)?;
+    let (input, reason) = parse_rest_utf8(input)?;
+
+    Ok((
+        input,
+        SignatureSubPacket::ReasonForRevocation { code, reason },
+    ))
+}
+
+/// Parse a notation data subpacket, as defined by the RFC in section 5.2.3.16.
+fn parse_notation_data(input: &[u8]) -> IResult<&[u8], SignatureSubPacket> {
+    let (input, flags) = parse_u32(input)?;
+    let (input, name_length) = parse_u16(input)?;
+    let (input, value_length) = parse_u16(input)?;
+    let (input, name) = map_res(take(name_length), std::str::from_utf8)(input)?;
+    let (input, value) = take(value_length)(input)?;
+
+    Ok((
+        input,
+        SignatureSubPacket::NotationData {
+            human_readable: flags & 0x8000_0000 != 0,
+            name: name.to_owned(),
+            value: value.to_owned(),
+        },
+    ))
+}
+
+fn parse_rest_owned(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
+    map(rest, |data: &[u8]| data.to_owned())(input)
+}
+
+fn parse_rest_utf8(input: &[u8]) -> IResult<&[u8], String> {
+    map(map_res(rest, std::str::from_utf8), String::from)(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,7 +285,7 @@ mod tests {
         let input = "- aa\n- bb\ncc\n- dd\n-a";
         let expected = "-a";
         assert_eq!(
-            parse_possibly_dash_escaped_chunk(&input),
+            parse_possibly_dash_escaped_chunk(input),
             Ok((expected, String::from("aa\nbb\ncc\ndd")))
         );
     }
@@ -110,20 +295,20 @@ mod tests {
         let input = "- aa\nbb";
         let expected = "bb";
         assert_eq!(
-            parse_possibly_dash_escaped_line(&input),
+            parse_possibly_dash_escaped_line(input),
             Ok((expected, "aa\n"))
         );
 
         let input = "aa\nbb";
         let expected = "bb";
         assert_eq!(
-            parse_possibly_dash_escaped_line(&input),
+            parse_possibly_dash_escaped_line(input),
             Ok((expected, "aa\n"))
         );
 
         let input = "-aa\nbb";
         assert_eq!(
-            parse_possibly_dash_escaped_line(&input),
+            parse_possibly_dash_escaped_line(input),
             Err(nom::Err::Error(("-aa\nbb", nom::error::ErrorKind::Not)))
         );
     }
@@ -132,17 +317,17 @@ mod tests {
     fn test_parse_dash_escaped_line() {
         let input = "- aa\nbb";
         let expected = "bb";
-        assert_eq!(parse_dash_escaped_line(&input), Ok((expected, "aa\n")));
+        assert_eq!(parse_dash_escaped_line(input), Ok((expected, "aa\n")));
 
         let input = "aa\nbb";
         assert_eq!(
-            parse_dash_escaped_line(&input),
+            parse_dash_escaped_line(input),
             Err(nom::Err::Error(("aa\nbb", nom::error::ErrorKind::Char)))
         );
 
         let input = "-aa\nbb";
         assert_eq!(
-            parse_dash_escaped_line(&input),
+            parse_dash_escaped_line(input),
             Err(nom::Err::Error(("aa\nbb", nom::error::ErrorKind::Char)))
         );
     }
@@ -151,11 +336,11 @@ mod tests {
     fn test_parse_non_dash_line() {
         let input = "aa\nbb";
         let expected = "bb";
-        assert_eq!(parse_non_dash_line(&input), Ok((expected, "aa\n")));
+        assert_eq!(parse_non_dash_line(input), Ok((expected, "aa\n")));
 
         let input = "-aa\n";
         assert_eq!(
-            parse_non_dash_line(&input),
+            parse_non_dash_line(input),
             Err(nom::Err::Error(("-aa\n", nom::error::ErrorKind::Not)))
         );
     }
@@ -164,7 +349,14 @@ mod tests {
     fn test_parse_hash_armor_header() {
         let input = "Hash: aaaa\n";
         let expected = "";
-        assert_eq!(parse_hash_armor_header(&input), Ok((expected, "aaaa")));
+        assert_eq!(parse_hash_armor_header(input), Ok((expected, "aaaa")));
+
+        let input = "Hash: SHA3-256, SHA512\n\nbb";
+        let expected = "bb";
+        assert_eq!(
+            parse_hash_armor_header(input),
+            Ok((expected, "SHA3-256, SHA512"))
+        );
     }
 
     #[test]
@@ -172,4 +364,160 @@ mod tests {
         let input = read_to_string_convert_newlines("./tests/01/msg.txt.asc").unwrap();
         let (_, (_hash, _msg, _ascii_armor)) = parse_cleartext_signature_parts(&input).unwrap();
     }
+
+    #[test]
+    fn test_parse_subpacket_length() {
+        let expected: &[u8] = &[];
+        assert_eq!(parse_subpacket_length(&[0x64]), Ok((expected, 100)));
+        assert_eq!(parse_subpacket_length(&[0xc5, 0xfb]), Ok((expected, 1723)));
+        assert_eq!(parse_subpacket_length(&[0xf0, 0x00]), Ok((expected, 12480)));
+        assert_eq!(
+            parse_subpacket_length(&[0xff, 0x00, 0x01, 0x86, 0xa0]),
+            Ok((expected, 100000))
+        );
+    }
+
+    #[test]
+    fn test_parse_subpackets() {
+        // hashed subpackets of the signature in tests/01.
+        let input: [u8; 29] = [
+            0x16, 0x21, 0x04, 0x2e, 0xcf, 0x30, 0x1f, 0xe9, 0x18, 0xf4, 0x73, 0xa8, 0x65, 0x51,
+            0x0c, 0x84, 0xfa, 0x31, 0x82, 0x76, 0x01, 0x7b, 0x00, 0x05, 0x02, 0x5f, 0x77, 0xd9,
+            0x13,
+        ];
+        let (_, subpackets) = parse_subpackets(&input).unwrap();
+
+        assert_eq!(subpackets.len(), 2);
+        assert!(!subpackets[0].critical);
+        match &subpackets[0].subpacket {
+            SignatureSubPacket::IssuerFingerprint {
+                version,
+                fingerprint,
+            } => {
+                assert_eq!(*version, 4);
+                assert_eq!(fingerprint, &input[3..23]);
+            }
+            _ => panic!("expected an issuer fingerprint subpacket"),
+        }
+        assert!(matches!(
+            subpackets[1].subpacket,
+            SignatureSubPacket::SignatureCreationTime(0x5f77d913)
+        ));
+
+        // unhashed subpackets of the same signature.
+        let input: [u8; 10] = [0x09, 0x10, 0x84, 0xfa, 0x31, 0x82, 0x76, 0x01, 0x7b, 0x00];
+        let (_, subpackets) = parse_subpackets(&input).unwrap();
+
+        assert!(matches!(
+            subpackets[0].subpacket,
+            SignatureSubPacket::Issuer(0x84fa_3182_7601_7b00)
+        ));
+    }
+
+    #[test]
+    fn test_parse_subpacket_critical() {
+        // a critical notation "a@b" = "c", followed by an unknown critical subpacket.
+        let input: [u8; 20] = [
+            0x0d, 0x94, 0x80, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x01, b'a', b'@', b'b', b'c',
+            0x03, 0xe4, 0x01, 0x02, 0x01, 0x9b,
+        ];
+        let (_, subpackets) = parse_subpackets(&input[..14]).unwrap();
+
+        assert!(subpackets[0].critical);
+        match &subpackets[0].subpacket {
+            SignatureSubPacket::NotationData {
+                human_readable,
+                name,
+                value,
+            } => {
+                assert!(*human_readable);
+                assert_eq!(name, "a@b");
+                assert_eq!(value, b"c");
+            }
+            _ => panic!("expected a notation data subpacket"),
+        }
+
+        let (rest, subpacket) = parse_subpacket(&input[14..]).unwrap();
+        assert_eq!(rest, &[0x01, 0x9b]);
+        assert!(subpacket.critical);
+        assert!(matches!(
+            subpacket.subpacket,
+            SignatureSubPacket::Unknown {
+                subpacket_type: 100,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_subpacket_invalid_utf8() {
+        // a reason for revocation "key was\xff", followed by a valid policy URI.
+        let input: [u8; 16] = [
+            0x0a, 0x1d, 0x02, b'k', b'e', b'y', b' ', b'w', b'a', b's', 0xff, 0x04, 0x1a, b'a',
+            b':', b'b',
+        ];
+        let (_, subpackets) = parse_subpackets(&input).unwrap();
+
+        assert_eq!(subpackets.len(), 2);
+        match &subpackets[0].subpacket {
+            SignatureSubPacket::Unknown {
+                subpacket_type,
+                data,
+            } => {
+                assert_eq!(*subpacket_type, 29);
+                assert_eq!(data, &input[2..11]);
+            }
+            _ => panic!("expected an unknown subpacket"),
+        }
+        match &subpackets[1].subpacket {
+            SignatureSubPacket::PolicyUri(uri) => assert_eq!(uri, "a:b"),
+            _ => panic!("expected a policy URI subpacket"),
+        }
+    }
+
+    #[test]
+    fn test_parse_signature_packet_v6() {
+        // a v6 Ed25519 signature using SHA256, with a creation time subpacket.
+        let mut input = vec![
+            0x06, 0x00, 0x1b, 0x08, 0x00, 0x00, 0x00, 0x06, 0x05, 0x02, 0x63, 0x87, 0x7f, 0xe3,
+            0x00, 0x00, 0x00, 0x00, 0xab, 0xcd, 0x10,
+        ];
+        input.extend_from_slice(&[0x11; 16]);
+        input.extend_from_slice(&[0x22; 32]);
+        input.extend_from_slice(&[0x33; 32]);
+        let (rest, packet) = parse_signature_packet(&input).unwrap();
+
+        assert!(rest.is_empty());
+        match packet {
+            PgpPacket::SignaturePacket(signature) => {
+                assert_eq!(signature.version, 6);
+                assert_eq!(signature.hashed_subpacket_data, &input[8..14]);
+                assert_eq!(signature.creation_time(), Some(0x6387_7fe3));
+                assert_eq!(signature.signed_hash_value_head, 0xabcd);
+                assert_eq!(signature.salt, [0x11; 16]);
+                assert_eq!(
+                    signature.signature,
+                    [
+                        BigUint::from_bytes_be(&[0x22; 32]),
+                        BigUint::from_bytes_be(&[0x33; 32])
+                    ]
+                );
+            }
+            _ => panic!("expected a signature packet"),
+        }
+    }
+
+    #[test]
+    fn test_parse_signature_packet_unsupported_version() {
+        // an RSA signature without subpackets, which is only accepted as a v4 signature.
+        let mut input = [
+            0x04, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0xab, 0xcd, 0x00, 0x01, 0x01,
+        ];
+        assert!(parse_signature_packet(&input).is_ok());
+
+        for version in [3, 5].iter() {
+            input[0] = *version;
+            assert!(parse_signature_packet(&input).is_err());
+        }
+    }
 }
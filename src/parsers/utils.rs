@@ -44,6 +44,20 @@ pub fn parse_u16(input: &[u8]) -> IResult<&[u8], u16> {
     Ok((input, num))
 }
 
+pub fn parse_u32(input: &[u8]) -> IResult<&[u8], u32> {
+    let (input, mut num) = take(4_usize)(input)?;
+    let num = num.read_u32::<BigEndian>().unwrap();
+
+    Ok((input, num))
+}
+
+pub fn parse_u64(input: &[u8]) -> IResult<&[u8], u64> {
+    let (input, mut num) = take(8_usize)(input)?;
+    let num = num.read_u64::<BigEndian>().unwrap();
+
+    Ok((input, num))
+}
+
 /// Parse until a newline is encountered, but return a string slice that includes the newline.
 pub fn parse_line_newline_inclusive(input: &str) -> IResult<&str, &str> {
     let (input, line) = take_till(is_newline)(input)?;
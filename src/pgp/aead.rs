@@ -0,0 +1,209 @@
+use aes::cipher::consts::{U12, U16};
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+use aes::{Aes128, Aes192, Aes256};
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::AesGcm;
+use anyhow::anyhow;
+use eax::Eax;
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::symmetric::SymmetricAlgorithm;
+use crate::ocb::Ocb;
+
+/// The size of the authentication tag of every AEAD algorithm, in octets.
+pub const AEAD_TAG_SIZE: usize = 16;
+
+/// The AEAD algorithms which are supported, as defined by RFC 9580 section 9.6.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AeadAlgorithm {
+    Eax,
+    Ocb,
+    Gcm,
+}
+
+impl AeadAlgorithm {
+    pub fn id(&self) -> u8 {
+        match self {
+            AeadAlgorithm::Eax => 1,
+            AeadAlgorithm::Ocb => 2,
+            AeadAlgorithm::Gcm => 3,
+        }
+    }
+
+    /// The size of a nonce, in octets.
+    pub fn nonce_size(&self) -> usize {
+        match self {
+            AeadAlgorithm::Eax => 16,
+            AeadAlgorithm::Ocb => 15,
+            AeadAlgorithm::Gcm => 12,
+        }
+    }
+
+    /// Encrypt `data` with `symmetric_algorithm` in this mode, authenticating it along with
+    /// `associated_data`. The tag is appended to the ciphertext.
+    pub fn encrypt(
+        &self,
+        symmetric_algorithm: SymmetricAlgorithm,
+        key: &[u8],
+        nonce: &[u8],
+        associated_data: &[u8],
+        data: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.check_nonce(nonce)?;
+
+        match (self, symmetric_algorithm) {
+            (AeadAlgorithm::Eax, SymmetricAlgorithm::Aes128) => {
+                encrypt::<Eax<Aes128>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Eax, SymmetricAlgorithm::Aes192) => {
+                encrypt::<Eax<Aes192>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Eax, SymmetricAlgorithm::Aes256) => {
+                encrypt::<Eax<Aes256>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Ocb, SymmetricAlgorithm::Aes128) => {
+                ocb::<Aes128>(key)?.encrypt(nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Ocb, SymmetricAlgorithm::Aes192) => {
+                ocb::<Aes192>(key)?.encrypt(nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Ocb, SymmetricAlgorithm::Aes256) => {
+                ocb::<Aes256>(key)?.encrypt(nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Gcm, SymmetricAlgorithm::Aes128) => {
+                encrypt::<AesGcm<Aes128, U12>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Gcm, SymmetricAlgorithm::Aes192) => {
+                encrypt::<AesGcm<Aes192, U12>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Gcm, SymmetricAlgorithm::Aes256) => {
+                encrypt::<AesGcm<Aes256, U12>>(key, nonce, associated_data, data)
+            }
+        }
+    }
+
+    /// Decrypt and authenticate `data`, which ends with the tag. An error is returned if the
+    /// data or the associated data has been modified, or if the key is wrong.
+    pub fn decrypt(
+        &self,
+        symmetric_algorithm: SymmetricAlgorithm,
+        key: &[u8],
+        nonce: &[u8],
+        associated_data: &[u8],
+        data: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.check_nonce(nonce)?;
+
+        match (self, symmetric_algorithm) {
+            (AeadAlgorithm::Eax, SymmetricAlgorithm::Aes128) => {
+                decrypt::<Eax<Aes128>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Eax, SymmetricAlgorithm::Aes192) => {
+                decrypt::<Eax<Aes192>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Eax, SymmetricAlgorithm::Aes256) => {
+                decrypt::<Eax<Aes256>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Ocb, SymmetricAlgorithm::Aes128) => {
+                ocb::<Aes128>(key)?.decrypt(nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Ocb, SymmetricAlgorithm::Aes192) => {
+                ocb::<Aes192>(key)?.decrypt(nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Ocb, SymmetricAlgorithm::Aes256) => {
+                ocb::<Aes256>(key)?.decrypt(nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Gcm, SymmetricAlgorithm::Aes128) => {
+                decrypt::<AesGcm<Aes128, U12>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Gcm, SymmetricAlgorithm::Aes192) => {
+                decrypt::<AesGcm<Aes192, U12>>(key, nonce, associated_data, data)
+            }
+            (AeadAlgorithm::Gcm, SymmetricAlgorithm::Aes256) => {
+                decrypt::<AesGcm<Aes256, U12>>(key, nonce, associated_data, data)
+            }
+        }
+    }
+
+    fn check_nonce(&self, nonce: &[u8]) -> anyhow::Result<()> {
+        if nonce.len() != self.nonce_size() {
+            return Err(anyhow!(
+                "invalid nonce size {} for {:?}, expected {}",
+                nonce.len(),
+                self,
+                self.nonce_size()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<u8> for AeadAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(val: u8) -> anyhow::Result<Self> {
+        match val {
+            1 => Ok(AeadAlgorithm::Eax),
+            2 => Ok(AeadAlgorithm::Ocb),
+            3 => Ok(AeadAlgorithm::Gcm),
+            _ => Err(anyhow!("unsupported AEAD algorithm {}", val)),
+        }
+    }
+}
+
+impl fmt::Display for AeadAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AeadAlgorithm::Eax => write!(f, "EAX"),
+            AeadAlgorithm::Ocb => write!(f, "OCB"),
+            AeadAlgorithm::Gcm => write!(f, "GCM"),
+        }
+    }
+}
+
+fn encrypt<A: Aead + KeyInit>(
+    key: &[u8],
+    nonce: &[u8],
+    associated_data: &[u8],
+    data: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let payload = Payload {
+        msg: data,
+        aad: associated_data,
+    };
+
+    A::new_from_slice(key)
+        .map_err(|_| anyhow!("invalid AEAD key size {}", key.len()))?
+        .encrypt(GenericArray::from_slice(nonce), payload)
+        .map_err(|_| anyhow!("AEAD encryption failed"))
+}
+
+fn decrypt<A: Aead + KeyInit>(
+    key: &[u8],
+    nonce: &[u8],
+    associated_data: &[u8],
+    data: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let payload = Payload {
+        msg: data,
+        aad: associated_data,
+    };
+
+    A::new_from_slice(key)
+        .map_err(|_| anyhow!("invalid AEAD key size {}", key.len()))?
+        .decrypt(GenericArray::from_slice(nonce), payload)
+        .map_err(|_| anyhow!("AEAD authentication failed"))
+}
+
+fn ocb<C>(key: &[u8]) -> anyhow::Result<Ocb<C>>
+where
+    C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16> + KeyInit,
+{
+    let cipher =
+        C::new_from_slice(key).map_err(|_| anyhow!("invalid AEAD key size {}", key.len()))?;
+
+    Ok(Ocb::new(cipher))
+}
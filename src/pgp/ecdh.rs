@@ -0,0 +1,206 @@
+use anyhow::anyhow;
+use std::convert::{TryFrom, TryInto};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use super::encryption::hkdf_sha256;
+use super::hash::HashAlgorithm;
+use super::key::{
+    Curve, PublicKeyAlgorithm, PublicKeyMaterial, PublicKeyPacket, NATIVE_POINT_PREFIX,
+};
+use super::symmetric::SymmetricAlgorithm;
+use crate::utils::random_bytes;
+
+/// The sender identifier which is part of the parameters of the ECDH key derivation function, as
+/// defined by RFC 6637 section 8.
+const ANONYMOUS_SENDER: &[u8; 20] = b"Anonymous Sender    ";
+
+/// The info parameter of the HKDF used by native X25519 keys, as defined by RFC 9580 section
+/// 5.1.6.
+const X25519_HKDF_INFO: &[u8] = b"OpenPGP X25519";
+
+/// The size of X25519 public keys, secrets and shared secrets, in octets.
+const X25519_SIZE: usize = 32;
+
+/// Encrypt `message` to the ECDH key `key`, as described by RFC 6637 section 8: the message is
+/// padded to a multiple of 8 octets as in PKCS#5, and wrapped with a key derived from an
+/// ephemeral key agreement. Only Curve25519 keys are supported. Returns the ephemeral point, in
+/// native encoding, and the wrapped message.
+pub fn ecdh_encrypt(key: &PublicKeyPacket, message: &[u8]) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let recipient = cv25519_public_key(key)?;
+    let ephemeral_secret = StaticSecret::from(random_x25519_secret());
+    let shared = ephemeral_secret.diffie_hellman(&recipient);
+
+    let (symmetric_algorithm, key_encryption_key) = ecdh_kdf(key, shared.as_bytes())?;
+    let padding = 8 - message.len() % 8;
+    let mut padded = message.to_owned();
+    padded.resize(message.len() + padding, padding as u8);
+    let wrapped_key = symmetric_algorithm.key_wrap(&key_encryption_key, &padded)?;
+
+    let mut ephemeral_point = vec![NATIVE_POINT_PREFIX];
+    ephemeral_point.extend_from_slice(X25519PublicKey::from(&ephemeral_secret).as_bytes());
+
+    Ok((ephemeral_point, wrapped_key))
+}
+
+/// Decrypt a message wrapped to the ECDH key `key`, whose secret scalar is `secret` (in the
+/// big-endian order it is stored in), and remove its padding.
+pub fn ecdh_decrypt(
+    key: &PublicKeyPacket,
+    secret: &[u8],
+    ephemeral_point: &[u8],
+    wrapped_key: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    cv25519_public_key(key)?;
+    let ephemeral = match ephemeral_point.split_first() {
+        Some((&NATIVE_POINT_PREFIX, point)) => x25519_public_key(point)?,
+        _ => return Err(anyhow!("ephemeral point is not a native Curve25519 point")),
+    };
+
+    // the scalar is stored in big-endian order, while X25519 takes it in little-endian order.
+    if secret.len() > X25519_SIZE {
+        return Err(anyhow!("Curve25519 secret scalar is too large"));
+    }
+    let mut scalar = [0; X25519_SIZE];
+    for (octet, value) in scalar.iter_mut().zip(secret.iter().rev()) {
+        *octet = *value;
+    }
+    let shared = StaticSecret::from(scalar).diffie_hellman(&ephemeral);
+    if !shared.was_contributory() {
+        return Err(anyhow!("ephemeral point is of low order"));
+    }
+
+    let (symmetric_algorithm, key_encryption_key) = ecdh_kdf(key, shared.as_bytes())?;
+    let mut message = symmetric_algorithm.key_unwrap(&key_encryption_key, wrapped_key)?;
+    let padding = message.last().copied().unwrap_or(0) as usize;
+    if padding == 0
+        || padding > 8
+        || padding > message.len()
+        || message[message.len() - padding..]
+            .iter()
+            .any(|octet| *octet as usize != padding)
+    {
+        return Err(anyhow!(
+            "could not decrypt the session key: invalid padding"
+        ));
+    }
+    message.truncate(message.len() - padding);
+
+    Ok(message)
+}
+
+/// Wrap `session_key` to the native X25519 public key `public_key`, as described by RFC 9580
+/// section 5.1.6. Returns the ephemeral public key and the wrapped session key.
+pub fn x25519_encrypt(public_key: &[u8], session_key: &[u8]) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let recipient = x25519_public_key(public_key)?;
+    let ephemeral_secret = StaticSecret::from(random_x25519_secret());
+    let ephemeral = X25519PublicKey::from(&ephemeral_secret);
+    let shared = ephemeral_secret.diffie_hellman(&recipient);
+
+    let key_encryption_key = x25519_kdf(ephemeral.as_bytes(), public_key, shared.as_bytes())?;
+    let wrapped_key = SymmetricAlgorithm::Aes128.key_wrap(&key_encryption_key, session_key)?;
+
+    Ok((ephemeral.as_bytes().to_vec(), wrapped_key))
+}
+
+/// Unwrap a session key wrapped to the native X25519 key with public key `public_key` and secret
+/// `secret`.
+pub fn x25519_decrypt(
+    public_key: &[u8],
+    secret: &[u8],
+    ephemeral: &[u8],
+    wrapped_key: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let secret: [u8; X25519_SIZE] = secret
+        .try_into()
+        .map_err(|_| anyhow!("X25519 secret has the wrong size"))?;
+    let shared = StaticSecret::from(secret).diffie_hellman(&x25519_public_key(ephemeral)?);
+    if !shared.was_contributory() {
+        return Err(anyhow!("ephemeral public key is of low order"));
+    }
+
+    let key_encryption_key = x25519_kdf(ephemeral, public_key, shared.as_bytes())?;
+    SymmetricAlgorithm::Aes128.key_unwrap(&key_encryption_key, wrapped_key)
+}
+
+/// The public key of a Curve25519 ECDH key, without its native point prefix.
+fn cv25519_public_key(key: &PublicKeyPacket) -> anyhow::Result<X25519PublicKey> {
+    match &key.key_material {
+        PublicKeyMaterial::Ecdh {
+            curve: Curve::Cv25519,
+            point,
+            ..
+        } => match point.split_first() {
+            Some((&NATIVE_POINT_PREFIX, point)) => x25519_public_key(point),
+            _ => Err(anyhow!("Curve25519 key is not a native point")),
+        },
+        PublicKeyMaterial::Ecdh { curve, .. } => {
+            Err(anyhow!("ECDH on curve {:?} is not supported", curve))
+        }
+        _ => Err(anyhow!("key is not an ECDH key")),
+    }
+}
+
+/// Derive the key encryption key of an ECDH key from the shared secret, as described by RFC
+/// 6637 section 7, along with the symmetric algorithm it is used with.
+fn ecdh_kdf(key: &PublicKeyPacket, shared: &[u8]) -> anyhow::Result<(SymmetricAlgorithm, Vec<u8>)> {
+    let (curve, kdf_hash_algorithm, kdf_symmetric_algorithm) = match &key.key_material {
+        PublicKeyMaterial::Ecdh {
+            curve,
+            kdf_hash_algorithm,
+            kdf_symmetric_algorithm,
+            ..
+        } => (*curve, *kdf_hash_algorithm, *kdf_symmetric_algorithm),
+        _ => return Err(anyhow!("key is not an ECDH key")),
+    };
+    let hash_algorithm = HashAlgorithm::try_from(kdf_hash_algorithm)?;
+    let symmetric_algorithm = SymmetricAlgorithm::try_from(kdf_symmetric_algorithm)?;
+
+    // the parameters identify the recipient key, as described by RFC 6637 section 8.
+    let mut parameters = vec![curve.oid().len() as u8];
+    parameters.extend_from_slice(curve.oid());
+    parameters.push(PublicKeyAlgorithm::Ecdh.id());
+    parameters.extend_from_slice(&[3, 1, kdf_hash_algorithm, kdf_symmetric_algorithm]);
+    parameters.extend_from_slice(ANONYMOUS_SENDER);
+    parameters.extend_from_slice(&key.fingerprint());
+
+    let mut hasher = hash_algorithm.hasher();
+    hasher.update(&1_u32.to_be_bytes());
+    hasher.update(shared);
+    hasher.update(&parameters);
+    let mut key_encryption_key = hasher.finalize().to_vec();
+    if key_encryption_key.len() < symmetric_algorithm.key_size() {
+        return Err(anyhow!(
+            "{} is too short to derive a key for {}",
+            hash_algorithm.name(),
+            symmetric_algorithm
+        ));
+    }
+    key_encryption_key.truncate(symmetric_algorithm.key_size());
+
+    Ok((symmetric_algorithm, key_encryption_key))
+}
+
+/// Derive the key encryption key of a native X25519 key, as described by RFC 9580 section 5.1.6.
+fn x25519_kdf(ephemeral: &[u8], public_key: &[u8], shared: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let input_key = [ephemeral, public_key, shared].concat();
+    hkdf_sha256(
+        None,
+        &input_key,
+        X25519_HKDF_INFO,
+        SymmetricAlgorithm::Aes128.key_size(),
+    )
+}
+
+fn x25519_public_key(public_key: &[u8]) -> anyhow::Result<X25519PublicKey> {
+    let public_key: [u8; X25519_SIZE] = public_key
+        .try_into()
+        .map_err(|_| anyhow!("Curve25519 public key has the wrong size"))?;
+
+    Ok(X25519PublicKey::from(public_key))
+}
+
+fn random_x25519_secret() -> [u8; X25519_SIZE] {
+    random_bytes(X25519_SIZE)
+        .try_into()
+        .expect("random bytes have the requested size")
+}
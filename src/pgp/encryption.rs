@@ -0,0 +1,1039 @@
+use anyhow::anyhow;
+use byteorder::{BigEndian, WriteBytesExt};
+use hkdf::Hkdf;
+use num::BigUint;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::convert::{TryFrom, TryInto};
+
+use super::aead::{AeadAlgorithm, AEAD_TAG_SIZE};
+use super::ecdh::{ecdh_encrypt, x25519_encrypt};
+use super::hash::HashAlgorithm;
+use super::key::{PublicKey, PublicKeyAlgorithm, PublicKeyMaterial, PublicKeyPacket};
+use super::message::LiteralDataPacket;
+use super::s2k::StringToKey;
+use super::secret_key::{SecretKey, SecretKeyPacket};
+use super::symmetric::SymmetricAlgorithm;
+use super::{read_pgp_packets, write_mpi, write_pgp_packets, AsciiArmorKind, PgpPacket};
+use crate::parsers::parse_pgp_packets;
+use crate::rsa::encrypt_pkcs1;
+use crate::utils::{random_bytes, sum16, unix_time_now};
+
+/// The header of the modification detection code packet which ends the plaintext of a v1
+/// encrypted data packet: tag 19, with a length of 20 octets.
+const MDC_HEADER: [u8; 2] = [0xd3, 0x14];
+
+/// The hash algorithm used to derive keys from passphrases when encrypting.
+const S2K_HASH_ALGORITHM: HashAlgorithm = HashAlgorithm::Sha256;
+
+/// The packet tags of symmetric-key encrypted session key packets and encrypted data packets in
+/// the new format encoding, which are part of the keys and associated data of AEAD encryption.
+const SYMMETRIC_KEY_ENCRYPTED_SESSION_KEY_TAG: u8 = 0xc3;
+const INTEGRITY_PROTECTED_DATA_TAG: u8 = 0xd2;
+
+/// The chunk size octet used when encrypting, for chunks of 2^(12 + 6) octets (256 KiB).
+const DEFAULT_CHUNK_SIZE_OCTET: u8 = 12;
+/// The largest chunk size octet allowed by RFC 9580 section 5.13.2, for 4 MiB chunks.
+pub const MAX_CHUNK_SIZE_OCTET: u8 = 16;
+
+/// The size of the salt of v2 encrypted data packets, in octets.
+pub const SALT_SIZE: usize = 32;
+
+/// A public-key encrypted session key packet, as defined by the RFC in section 5.1. v6 packets
+/// (RFC 9580 section 5.1) identify the recipient by its fingerprint instead of its key ID.
+#[derive(Debug, Clone)]
+pub struct PublicKeyEncryptedSessionKeyPacket {
+    pub version: u8,
+    /// the key ID of the recipient key, zero if the recipient is hidden. For v6 packets, it is
+    /// taken from the fingerprint.
+    pub key_id: u64,
+    /// the version of the recipient key, only present in v6 packets which name the recipient.
+    pub key_version: Option<u8>,
+    /// the fingerprint of the recipient key, only present in v6 packets which name the recipient.
+    pub fingerprint: Option<Vec<u8>>,
+    pub public_key_algorithm: PublicKeyAlgorithm,
+    pub encrypted_session_key: EncryptedSessionKey,
+}
+
+/// The algorithm specific fields of a public-key encrypted session key packet.
+#[derive(Debug, Clone)]
+pub enum EncryptedSessionKey {
+    /// m^e mod n, where m is the PKCS#1 v1.5 encoded session key.
+    Rsa { value: BigUint },
+    /// the ephemeral point, in native encoding, and the session key wrapped with the derived key,
+    /// as described by RFC 6637 section 8.
+    Ecdh {
+        ephemeral_point: Vec<u8>,
+        wrapped_key: Vec<u8>,
+    },
+    /// the ephemeral public key and the session key wrapped with the derived key, as described by
+    /// RFC 9580 section 5.1.6. In v3 packets, the symmetric algorithm of the session key is not
+    /// encrypted.
+    X25519 {
+        ephemeral: Vec<u8>,
+        symmetric_algorithm: Option<u8>,
+        wrapped_key: Vec<u8>,
+    },
+    /// the fields of algorithms which aren't supported, kept as is.
+    Unknown { data: Vec<u8> },
+}
+
+/// A symmetric-key encrypted session key packet, as defined by the RFC in section 5.3. The
+/// session key is derived from a passphrase with the string-to-key specifier, or, if there is an
+/// encrypted session key, that key is encrypted with the derived key. v6 packets (RFC 9580
+/// section 5.3.2) always encrypt the session key, with an AEAD algorithm.
+#[derive(Debug, Clone)]
+pub struct SymmetricKeyEncryptedSessionKeyPacket {
+    pub version: u8,
+    pub symmetric_algorithm: SymmetricAlgorithm,
+    /// the AEAD algorithm which encrypts the session key, only present in v6 packets.
+    pub aead_algorithm: Option<AeadAlgorithm>,
+    pub s2k: StringToKey,
+    /// the nonce of the AEAD algorithm, only present in v6 packets.
+    pub nonce: Option<Vec<u8>>,
+    /// the session key, encrypted with the derived key. In v4 packets it is preceded by its
+    /// symmetric algorithm, and empty if the derived key is the session key. In v6 packets it
+    /// is followed by the authentication tag.
+    pub encrypted_session_key: Vec<u8>,
+}
+
+/// A symmetrically encrypted integrity protected data packet, as defined by the RFC in section
+/// 5.13. Version 1 protects the data with a modification detection code, and version 2 (RFC 9580
+/// section 5.13.2) encrypts it in chunks with an AEAD algorithm.
+#[derive(Debug, Clone)]
+pub struct IntegrityProtectedDataPacket {
+    pub version: u8,
+    /// the parameters of v2 packets.
+    pub aead: Option<AeadParameters>,
+    pub data: Vec<u8>,
+}
+
+/// The parameters of a v2 encrypted data packet.
+#[derive(Debug, Clone)]
+pub struct AeadParameters {
+    pub symmetric_algorithm: SymmetricAlgorithm,
+    pub aead_algorithm: AeadAlgorithm,
+    /// the data is split into chunks of 2^(chunk_size_octet + 6) octets.
+    pub chunk_size_octet: u8,
+    pub salt: [u8; SALT_SIZE],
+}
+
+/// A decrypted session key, along with the symmetric algorithm it is used with. Session keys
+/// for v2 encrypted data are used with the symmetric algorithm of the data packet.
+#[derive(Debug, Clone)]
+pub struct SessionKey {
+    pub symmetric_algorithm: SymmetricAlgorithm,
+    pub key: Vec<u8>,
+}
+
+/// A message encrypted to one or more keys or passphrases, as described by the RFC in section
+/// 11.3: the encrypted session keys, followed by the encrypted data.
+#[derive(Debug)]
+pub struct EncryptedMessage {
+    session_keys: Vec<PublicKeyEncryptedSessionKeyPacket>,
+    passphrase_session_keys: Vec<SymmetricKeyEncryptedSessionKeyPacket>,
+    data: IntegrityProtectedDataPacket,
+}
+
+impl PublicKeyEncryptedSessionKeyPacket {
+    /// Encrypt the session key to `key` in a v3 or v6 packet. The session key and its two octet
+    /// checksum are encrypted together, preceded by the symmetric algorithm in v3 packets. RSA
+    /// keys, Curve25519 ECDH keys and native X25519 keys are supported. X25519 keys only wrap the
+    /// session key itself.
+    pub fn encrypt(
+        key: &PublicKeyPacket,
+        session_key: &SessionKey,
+        version: u8,
+    ) -> anyhow::Result<PublicKeyEncryptedSessionKeyPacket> {
+        let (mut message, key_version, fingerprint) = match version {
+            3 => (vec![session_key.symmetric_algorithm.id()], None, None),
+            6 => (Vec::new(), Some(key.version), Some(key.fingerprint())),
+            _ => {
+                return Err(anyhow!(
+                    "unsupported public-key encrypted session key version {}",
+                    version
+                ))
+            }
+        };
+        message.extend_from_slice(&session_key.key);
+        message.extend_from_slice(&sum16(&session_key.key).to_be_bytes());
+
+        let encrypted_session_key = match &key.key_material {
+            PublicKeyMaterial::Rsa { n, e } => EncryptedSessionKey::Rsa {
+                value: encrypt_pkcs1(n, e, &message)
+                    .ok_or_else(|| anyhow!("rsa key is too small to encrypt a session key"))?,
+            },
+            PublicKeyMaterial::Ecdh { .. } => {
+                let (ephemeral_point, wrapped_key) = ecdh_encrypt(key, &message)?;
+                EncryptedSessionKey::Ecdh {
+                    ephemeral_point,
+                    wrapped_key,
+                }
+            }
+            PublicKeyMaterial::X25519 { public_key } => {
+                let (ephemeral, wrapped_key) = x25519_encrypt(public_key, &session_key.key)?;
+                EncryptedSessionKey::X25519 {
+                    ephemeral,
+                    symmetric_algorithm: match version {
+                        3 => Some(session_key.symmetric_algorithm.id()),
+                        _ => None,
+                    },
+                    wrapped_key,
+                }
+            }
+            _ => {
+                return Err(anyhow!(
+                    "encrypting to public key algorithm {:?} is not supported",
+                    key.public_key_algorithm
+                ))
+            }
+        };
+
+        Ok(PublicKeyEncryptedSessionKeyPacket {
+            version,
+            key_id: key.key_id(),
+            key_version,
+            fingerprint,
+            public_key_algorithm: key.public_key_algorithm,
+            encrypted_session_key,
+        })
+    }
+
+    /// Decrypt the session key with the secret key packet it is encrypted to. The decrypted
+    /// session key is followed by a two octet checksum, and for v3 packets preceded by its
+    /// symmetric algorithm. v6 packets leave the symmetric algorithm to the v2 encrypted data
+    /// packet, whose algorithm is given as `data_symmetric_algorithm`. X25519 session keys have
+    /// no checksum, and their symmetric algorithm is not encrypted.
+    pub fn decrypt(
+        &self,
+        key: &SecretKeyPacket,
+        data_symmetric_algorithm: Option<SymmetricAlgorithm>,
+    ) -> anyhow::Result<SessionKey> {
+        // the length the session key decrypts to, which RSA decryption falls back to when the
+        // padding is invalid. the symmetric algorithm of v1 encrypted data packets is part of
+        // the session key, so it is guessed.
+        let session_key_length = match (self.version, data_symmetric_algorithm) {
+            (6, Some(symmetric_algorithm)) => symmetric_algorithm.key_size() + 2,
+            (_, Some(symmetric_algorithm)) => 1 + symmetric_algorithm.key_size() + 2,
+            (_, None) => 1 + SymmetricAlgorithm::Aes256.key_size() + 2,
+        };
+        let decrypted = key.decrypt(&self.encrypted_session_key, session_key_length)?;
+        let data_symmetric_algorithm = || {
+            data_symmetric_algorithm.ok_or_else(|| {
+                anyhow!("v6 session keys can only be used with v2 encrypted data packets")
+            })
+        };
+
+        if let EncryptedSessionKey::X25519 {
+            symmetric_algorithm,
+            ..
+        } = &self.encrypted_session_key
+        {
+            let symmetric_algorithm = match (self.version, symmetric_algorithm) {
+                (3, Some(symmetric_algorithm)) => {
+                    SymmetricAlgorithm::try_from(*symmetric_algorithm)?
+                }
+                _ => data_symmetric_algorithm()?,
+            };
+            if decrypted.len() != symmetric_algorithm.key_size() {
+                return Err(anyhow!(
+                    "decrypted session key has the wrong size for {:?}",
+                    symmetric_algorithm
+                ));
+            }
+
+            return Ok(SessionKey {
+                symmetric_algorithm,
+                key: decrypted,
+            });
+        }
+
+        // whatever is wrong with the decrypted session key gives the same error, which doesn't
+        // tell an attacker how far decryption got.
+        let invalid_session_key = || anyhow!("could not decrypt the session key");
+        let (symmetric_algorithm, data) = match (self.version, decrypted.split_first()) {
+            (3, Some((symmetric_algorithm, data))) => (
+                SymmetricAlgorithm::try_from(*symmetric_algorithm)
+                    .map_err(|_| invalid_session_key())?,
+                data,
+            ),
+            (3, None) => return Err(invalid_session_key()),
+            (6, _) => (data_symmetric_algorithm()?, decrypted.as_slice()),
+            (version, _) => {
+                return Err(anyhow!(
+                    "unsupported public-key encrypted session key version {}",
+                    version
+                ))
+            }
+        };
+        if data.len() != symmetric_algorithm.key_size() + 2 {
+            return Err(invalid_session_key());
+        }
+
+        let (session_key, checksum) = data.split_at(symmetric_algorithm.key_size());
+        if sum16(session_key).to_be_bytes() != checksum {
+            return Err(invalid_session_key());
+        }
+
+        Ok(SessionKey {
+            symmetric_algorithm,
+            key: session_key.to_owned(),
+        })
+    }
+
+    /// Serialize the packet body.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = vec![self.version];
+        match (&self.key_version, &self.fingerprint) {
+            (Some(key_version), Some(fingerprint)) if self.version == 6 => {
+                bytes.push((1 + fingerprint.len()).try_into()?);
+                bytes.push(*key_version);
+                bytes.extend_from_slice(fingerprint);
+            }
+            _ if self.version == 6 => bytes.push(0),
+            _ => bytes.write_u64::<BigEndian>(self.key_id)?,
+        }
+        bytes.push(self.public_key_algorithm.id());
+        match &self.encrypted_session_key {
+            EncryptedSessionKey::Rsa { value } => write_mpi(&mut bytes, value),
+            EncryptedSessionKey::Ecdh {
+                ephemeral_point,
+                wrapped_key,
+            } => {
+                write_mpi(&mut bytes, &BigUint::from_bytes_be(ephemeral_point));
+                bytes.push(wrapped_key.len().try_into()?);
+                bytes.extend_from_slice(wrapped_key);
+            }
+            EncryptedSessionKey::X25519 {
+                ephemeral,
+                symmetric_algorithm,
+                wrapped_key,
+            } => {
+                bytes.extend_from_slice(ephemeral);
+                let length = wrapped_key.len() + symmetric_algorithm.iter().count();
+                bytes.push(length.try_into()?);
+                bytes.extend(symmetric_algorithm);
+                bytes.extend_from_slice(wrapped_key);
+            }
+            EncryptedSessionKey::Unknown { data } => bytes.extend_from_slice(data),
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl SymmetricKeyEncryptedSessionKeyPacket {
+    /// Encrypt the session key with a key derived from `passphrase`. Without an AEAD algorithm,
+    /// this is a v4 packet using an iterated and salted specifier, and otherwise a v6 packet
+    /// using Argon2.
+    pub fn encrypt(
+        passphrase: &str,
+        session_key: &SessionKey,
+        aead_algorithm: Option<AeadAlgorithm>,
+    ) -> anyhow::Result<SymmetricKeyEncryptedSessionKeyPacket> {
+        let symmetric_algorithm = session_key.symmetric_algorithm;
+        if let Some(aead_algorithm) = aead_algorithm {
+            let s2k = StringToKey::new_argon2();
+            let nonce = random_bytes(aead_algorithm.nonce_size());
+            let key =
+                aead_key_encryption_key(&s2k, passphrase, symmetric_algorithm, aead_algorithm)?;
+            let encrypted_session_key = aead_algorithm.encrypt(
+                symmetric_algorithm,
+                &key,
+                &nonce,
+                &aead_key_associated_data(symmetric_algorithm, aead_algorithm),
+                &session_key.key,
+            )?;
+
+            return Ok(SymmetricKeyEncryptedSessionKeyPacket {
+                version: 6,
+                symmetric_algorithm,
+                aead_algorithm: Some(aead_algorithm),
+                s2k,
+                nonce: Some(nonce),
+                encrypted_session_key,
+            });
+        }
+
+        let s2k = StringToKey::new_iterated_salted(S2K_HASH_ALGORITHM);
+        let key = s2k.derive_key(passphrase.as_bytes(), symmetric_algorithm.key_size())?;
+
+        let mut encrypted_session_key = vec![symmetric_algorithm.id()];
+        encrypted_session_key.extend_from_slice(&session_key.key);
+        let block_size = symmetric_algorithm.block_size();
+        symmetric_algorithm.cfb_encrypt(&key, &vec![0; block_size], &mut encrypted_session_key)?;
+
+        Ok(SymmetricKeyEncryptedSessionKeyPacket {
+            version: 4,
+            symmetric_algorithm,
+            aead_algorithm: None,
+            s2k,
+            nonce: None,
+            encrypted_session_key,
+        })
+    }
+
+    /// Derive the session key from `passphrase`. v4 packets have no checksum, so a wrong
+    /// passphrase usually gives a wrong session key, which is only noticed when the data is
+    /// decrypted. v6 packets authenticate the session key.
+    pub fn decrypt(&self, passphrase: &str) -> anyhow::Result<SessionKey> {
+        match (self.version, self.aead_algorithm, &self.nonce) {
+            (4, None, None) => {}
+            (6, Some(aead_algorithm), Some(nonce)) => {
+                let key = aead_key_encryption_key(
+                    &self.s2k,
+                    passphrase,
+                    self.symmetric_algorithm,
+                    aead_algorithm,
+                )?;
+                let session_key = aead_algorithm.decrypt(
+                    self.symmetric_algorithm,
+                    &key,
+                    nonce,
+                    &aead_key_associated_data(self.symmetric_algorithm, aead_algorithm),
+                    &self.encrypted_session_key,
+                )?;
+
+                return Ok(SessionKey {
+                    symmetric_algorithm: self.symmetric_algorithm,
+                    key: session_key,
+                });
+            }
+            (version, _, _) => {
+                return Err(anyhow!(
+                    "unsupported symmetric-key encrypted session key version {}",
+                    version
+                ))
+            }
+        }
+
+        let key = self
+            .s2k
+            .derive_key(passphrase.as_bytes(), self.symmetric_algorithm.key_size())?;
+        if self.encrypted_session_key.is_empty() {
+            return Ok(SessionKey {
+                symmetric_algorithm: self.symmetric_algorithm,
+                key,
+            });
+        }
+
+        let mut decrypted = self.encrypted_session_key.clone();
+        let block_size = self.symmetric_algorithm.block_size();
+        self.symmetric_algorithm
+            .cfb_decrypt(&key, &vec![0; block_size], &mut decrypted)?;
+
+        let (symmetric_algorithm, session_key) = decrypted
+            .split_first()
+            .ok_or_else(|| anyhow!("decrypted session key is empty"))?;
+        let symmetric_algorithm = SymmetricAlgorithm::try_from(*symmetric_algorithm)?;
+        if session_key.len() != symmetric_algorithm.key_size() {
+            return Err(anyhow!(
+                "decrypted session key has the wrong size for {:?}",
+                symmetric_algorithm
+            ));
+        }
+
+        Ok(SessionKey {
+            symmetric_algorithm,
+            key: session_key.to_owned(),
+        })
+    }
+
+    /// Serialize the packet body. v6 packets give the length of the parameters, and of the S2K
+    /// specifier.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let s2k = self.s2k.to_bytes();
+
+        let mut bytes = vec![self.version];
+        match (self.aead_algorithm, &self.nonce) {
+            (Some(aead_algorithm), Some(nonce)) => {
+                bytes.push((3 + s2k.len() + nonce.len()).try_into()?);
+                bytes.push(self.symmetric_algorithm.id());
+                bytes.push(aead_algorithm.id());
+                bytes.push(s2k.len().try_into()?);
+                bytes.extend_from_slice(&s2k);
+                bytes.extend_from_slice(nonce);
+            }
+            _ => {
+                bytes.push(self.symmetric_algorithm.id());
+                bytes.extend_from_slice(&s2k);
+            }
+        }
+        bytes.extend_from_slice(&self.encrypted_session_key);
+
+        Ok(bytes)
+    }
+}
+
+impl IntegrityProtectedDataPacket {
+    /// Encrypt the serialized `packets` with the session key. Without an AEAD algorithm, this
+    /// is a v1 packet, and otherwise a v2 packet.
+    pub fn encrypt(
+        session_key: &SessionKey,
+        aead_algorithm: Option<AeadAlgorithm>,
+        packets: &[u8],
+    ) -> anyhow::Result<IntegrityProtectedDataPacket> {
+        match aead_algorithm {
+            Some(aead_algorithm) => IntegrityProtectedDataPacket::encrypt_v2(
+                session_key,
+                aead_algorithm,
+                DEFAULT_CHUNK_SIZE_OCTET,
+                packets,
+            ),
+            None => IntegrityProtectedDataPacket::encrypt_v1(session_key, packets),
+        }
+    }
+
+    /// Encrypt in a v1 packet, appending the modification detection code: a SHA-1 hash of the
+    /// prefix, the packets and the header of the modification detection code packet itself.
+    fn encrypt_v1(
+        session_key: &SessionKey,
+        packets: &[u8],
+    ) -> anyhow::Result<IntegrityProtectedDataPacket> {
+        let symmetric_algorithm = session_key.symmetric_algorithm;
+        let block_size = symmetric_algorithm.block_size();
+
+        let mut data = random_bytes(block_size);
+        data.extend_from_within(block_size - 2..);
+        data.extend_from_slice(packets);
+        data.extend_from_slice(&MDC_HEADER);
+        let mdc = Sha1::digest(&data);
+        data.extend_from_slice(&mdc);
+
+        symmetric_algorithm.cfb_encrypt(&session_key.key, &vec![0; block_size], &mut data)?;
+
+        Ok(IntegrityProtectedDataPacket {
+            version: 1,
+            aead: None,
+            data,
+        })
+    }
+
+    /// Encrypt in a v2 packet, as described by RFC 9580 section 5.13.2. The packets are split
+    /// into chunks, which are encrypted and authenticated separately, each with a nonce made from
+    /// its index. A final tag authenticates the total length, so that chunks can't be dropped
+    /// from the end.
+    fn encrypt_v2(
+        session_key: &SessionKey,
+        aead_algorithm: AeadAlgorithm,
+        chunk_size_octet: u8,
+        packets: &[u8],
+    ) -> anyhow::Result<IntegrityProtectedDataPacket> {
+        let parameters = AeadParameters {
+            symmetric_algorithm: session_key.symmetric_algorithm,
+            aead_algorithm,
+            chunk_size_octet,
+            salt: rand::random(),
+        };
+        let (key, iv) = parameters.derive_key(&session_key.key)?;
+        let associated_data = parameters.associated_data();
+
+        let mut data = Vec::new();
+        let mut index = 0;
+        for chunk in packets.chunks(parameters.chunk_size()) {
+            data.extend_from_slice(&parameters.encrypt(
+                &key,
+                &iv,
+                index,
+                &associated_data,
+                chunk,
+            )?);
+            index += 1;
+        }
+        let final_associated_data = parameters.final_associated_data(packets.len())?;
+        data.extend_from_slice(&parameters.encrypt(
+            &key,
+            &iv,
+            index,
+            &final_associated_data,
+            &[],
+        )?);
+
+        Ok(IntegrityProtectedDataPacket {
+            version: 2,
+            aead: Some(parameters),
+            data,
+        })
+    }
+
+    /// Decrypt the data with the session key and authenticate it, returning the plaintext
+    /// packets.
+    pub fn decrypt(&self, session_key: &SessionKey) -> anyhow::Result<Vec<u8>> {
+        match (self.version, &self.aead) {
+            (1, None) => self.decrypt_v1(session_key),
+            (2, Some(parameters)) => self.decrypt_v2(parameters, session_key),
+            (version, _) => Err(anyhow!(
+                "unsupported encrypted data packet version {}",
+                version
+            )),
+        }
+    }
+
+    /// Decrypt a v1 packet, and check the modification detection code. The data is encrypted
+    /// in CFB mode with a zero IV, and starts with a block of random data whose last two octets
+    /// are repeated.
+    fn decrypt_v1(&self, session_key: &SessionKey) -> anyhow::Result<Vec<u8>> {
+        let symmetric_algorithm = session_key.symmetric_algorithm;
+        let block_size = symmetric_algorithm.block_size();
+        let mut data = self.data.clone();
+        symmetric_algorithm.cfb_decrypt(&session_key.key, &vec![0; block_size], &mut data)?;
+
+        let prefix_size = block_size + 2;
+        let mdc_size = MDC_HEADER.len() + 20;
+        if data.len() < prefix_size + mdc_size {
+            return Err(anyhow!("encrypted data is too short"));
+        }
+
+        // the repeated octets of the prefix aren't checked: the modification detection code
+        // covers them, and checking them early would leak whether the session key is right.
+        let (data, mdc) = data.split_at(data.len() - 20);
+        if !data.ends_with(&MDC_HEADER) || Sha1::digest(data).as_slice() != mdc {
+            return Err(anyhow!(
+                "encrypted data has been modified: the modification detection code did not match"
+            ));
+        }
+
+        Ok(data[prefix_size..data.len() - MDC_HEADER.len()].to_owned())
+    }
+
+    /// Decrypt a v2 packet, authenticating each chunk and then the final tag. Every chunk but
+    /// the last is full sized, and the final tag follows the last chunk.
+    fn decrypt_v2(
+        &self,
+        parameters: &AeadParameters,
+        session_key: &SessionKey,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (key, iv) = parameters.derive_key(&session_key.key)?;
+        let associated_data = parameters.associated_data();
+
+        if self.data.len() < AEAD_TAG_SIZE {
+            return Err(anyhow!("encrypted data is too short"));
+        }
+        let (chunks, final_tag) = self.data.split_at(self.data.len() - AEAD_TAG_SIZE);
+
+        let mut data = Vec::new();
+        let mut index = 0;
+        for chunk in chunks.chunks(parameters.chunk_size() + AEAD_TAG_SIZE) {
+            let chunk = parameters
+                .decrypt(&key, &iv, index, &associated_data, chunk)
+                .map_err(|_| {
+                    anyhow!(
+                        "encrypted data has been modified: chunk {} failed authentication",
+                        index
+                    )
+                })?;
+            data.extend_from_slice(&chunk);
+            index += 1;
+        }
+
+        let final_associated_data = parameters.final_associated_data(data.len())?;
+        parameters
+            .decrypt(&key, &iv, index, &final_associated_data, final_tag)
+            .map_err(|_| {
+                anyhow!("encrypted data has been modified: the final tag did not match")
+            })?;
+
+        Ok(data)
+    }
+
+    /// Serialize the packet body.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.version];
+        if let Some(parameters) = &self.aead {
+            bytes.push(parameters.symmetric_algorithm.id());
+            bytes.push(parameters.aead_algorithm.id());
+            bytes.push(parameters.chunk_size_octet);
+            bytes.extend_from_slice(&parameters.salt);
+        }
+        bytes.extend_from_slice(&self.data);
+
+        bytes
+    }
+}
+
+impl AeadParameters {
+    /// The size of a chunk of plaintext, in octets.
+    pub fn chunk_size(&self) -> usize {
+        1 << (usize::from(self.chunk_size_octet) + 6)
+    }
+
+    /// The associated data of every chunk: the packet tag, the version and the parameters. It
+    /// is also the info of the key derivation.
+    fn associated_data(&self) -> [u8; 5] {
+        [
+            INTEGRITY_PROTECTED_DATA_TAG,
+            2,
+            self.symmetric_algorithm.id(),
+            self.aead_algorithm.id(),
+            self.chunk_size_octet,
+        ]
+    }
+
+    /// The associated data of the final tag, which also covers the total length of the
+    /// plaintext as an eight octet number.
+    fn final_associated_data(&self, length: usize) -> anyhow::Result<Vec<u8>> {
+        let mut associated_data = self.associated_data().to_vec();
+        associated_data.write_u64::<BigEndian>(length.try_into()?)?;
+
+        Ok(associated_data)
+    }
+
+    /// Derive the message key and the IV from the session key with HKDF-SHA256, salted with the
+    /// packet's salt. The IV is eight octets shorter than a nonce, leaving room for the chunk
+    /// index.
+    fn derive_key(&self, session_key: &[u8]) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+        let key_size = self.symmetric_algorithm.key_size();
+        if session_key.len() != key_size {
+            return Err(anyhow!(
+                "session key has the wrong size for {:?}",
+                self.symmetric_algorithm
+            ));
+        }
+
+        let mut key = hkdf_sha256(
+            Some(&self.salt),
+            session_key,
+            &self.associated_data(),
+            key_size + self.aead_algorithm.nonce_size() - 8,
+        )?;
+        let iv = key.split_off(key_size);
+
+        Ok((key, iv))
+    }
+
+    fn encrypt(
+        &self,
+        key: &[u8],
+        iv: &[u8],
+        index: u64,
+        associated_data: &[u8],
+        data: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.aead_algorithm.encrypt(
+            self.symmetric_algorithm,
+            key,
+            &chunk_nonce(iv, index),
+            associated_data,
+            data,
+        )
+    }
+
+    fn decrypt(
+        &self,
+        key: &[u8],
+        iv: &[u8],
+        index: u64,
+        associated_data: &[u8],
+        data: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        self.aead_algorithm.decrypt(
+            self.symmetric_algorithm,
+            key,
+            &chunk_nonce(iv, index),
+            associated_data,
+            data,
+        )
+    }
+}
+
+impl SessionKey {
+    /// Generate a random session key for `symmetric_algorithm`.
+    pub fn generate(symmetric_algorithm: SymmetricAlgorithm) -> SessionKey {
+        SessionKey {
+            symmetric_algorithm,
+            key: random_bytes(symmetric_algorithm.key_size()),
+        }
+    }
+}
+
+impl EncryptedMessage {
+    /// Encrypt `data` to each of the `recipients`, and to `passphrase` if one is given, with a
+    /// new session key for `symmetric_algorithm`. With an AEAD algorithm, the data is encrypted
+    /// in a v2 packet, and the session keys in v6 packets. The data is stored in a binary literal
+    /// data packet, and filenames longer than 255 octets are truncated.
+    pub fn create(
+        data: Vec<u8>,
+        filename: &[u8],
+        recipients: &[PublicKey],
+        passphrase: Option<&str>,
+        symmetric_algorithm: SymmetricAlgorithm,
+        aead_algorithm: Option<AeadAlgorithm>,
+    ) -> anyhow::Result<EncryptedMessage> {
+        if recipients.is_empty() && passphrase.is_none() {
+            return Err(anyhow!(
+                "a message needs at least one recipient or a passphrase"
+            ));
+        }
+
+        let session_key = SessionKey::generate(symmetric_algorithm);
+        let session_key_version = if aead_algorithm.is_some() { 6 } else { 3 };
+        let session_keys = recipients
+            .iter()
+            .map(|recipient| {
+                PublicKeyEncryptedSessionKeyPacket::encrypt(
+                    recipient.find_encryption_key()?,
+                    &session_key,
+                    session_key_version,
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let passphrase_session_keys = passphrase
+            .map(|passphrase| {
+                SymmetricKeyEncryptedSessionKeyPacket::encrypt(
+                    passphrase,
+                    &session_key,
+                    aead_algorithm,
+                )
+            })
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let literal = LiteralDataPacket {
+            format: b'b',
+            filename: filename[..filename.len().min(255)].to_owned(),
+            date: unix_time_now().try_into()?,
+            data,
+        };
+        let packets = write_pgp_packets(&[PgpPacket::LiteralDataPacket(literal)])?;
+        let data = IntegrityProtectedDataPacket::encrypt(&session_key, aead_algorithm, &packets)?;
+
+        Ok(EncryptedMessage {
+            session_keys,
+            passphrase_session_keys,
+            data,
+        })
+    }
+
+    /// Parse an encrypted message, which may be either ascii armored or binary.
+    pub fn parse(input: &[u8]) -> anyhow::Result<EncryptedMessage> {
+        let mut session_keys = Vec::new();
+        let mut passphrase_session_keys = Vec::new();
+        let mut data = None;
+        for packet in read_pgp_packets(input, AsciiArmorKind::Message)? {
+            match (packet, &data) {
+                (PgpPacket::PublicKeyEncryptedSessionKeyPacket(packet), None) => {
+                    session_keys.push(packet)
+                }
+                (PgpPacket::SymmetricKeyEncryptedSessionKeyPacket(packet), None) => {
+                    passphrase_session_keys.push(packet)
+                }
+                (PgpPacket::IntegrityProtectedDataPacket(packet), None) => data = Some(packet),
+                (packet, _) if packet.is_unknown() => {}
+                _ => {
+                    return Err(anyhow!(
+                        "expected encrypted session key packets followed by a single encrypted \
+                         data packet"
+                    ))
+                }
+            }
+        }
+
+        let data = data.ok_or_else(|| anyhow!("message has no encrypted data packet"))?;
+
+        Ok(EncryptedMessage {
+            session_keys,
+            passphrase_session_keys,
+            data,
+        })
+    }
+
+    /// Whether the message can be decrypted with a passphrase.
+    pub fn is_encrypted_with_passphrase(&self) -> bool {
+        !self.passphrase_session_keys.is_empty()
+    }
+
+    /// Decrypt the session key with the first key from `key` it is encrypted to, returning that
+    /// key along with the session key. Session keys for hidden recipients are tried with every
+    /// key.
+    pub fn decrypt_session_key<'a>(
+        &self,
+        key: &'a SecretKey,
+    ) -> anyhow::Result<(&'a SecretKeyPacket, SessionKey)> {
+        if self.session_keys.is_empty() {
+            return Err(anyhow!(
+                "message is not encrypted to any key, it can only be decrypted with a passphrase"
+            ));
+        }
+
+        let data_symmetric_algorithm = self
+            .data
+            .aead
+            .as_ref()
+            .map(|parameters| parameters.symmetric_algorithm);
+        for session_key in self.session_keys.iter() {
+            let candidates = key.key_packets().filter(|candidate| {
+                candidate.has_secret_material()
+                    && (session_key.key_id == 0
+                        || session_key.key_id == candidate.public_key.key_id()
+                            && session_key.public_key_algorithm
+                                == candidate.public_key.public_key_algorithm)
+            });
+
+            for candidate in candidates {
+                match session_key.decrypt(candidate, data_symmetric_algorithm) {
+                    Ok(decrypted) => return Ok((candidate, decrypted)),
+                    Err(e) if session_key.key_id != 0 => return Err(e),
+                    Err(_) => {}
+                }
+            }
+        }
+
+        let recipients = self
+            .session_keys
+            .iter()
+            .map(|session_key| format!("0x{:016X}", session_key.key_id))
+            .collect::<Vec<_>>();
+        Err(anyhow!(
+            "message is not encrypted to this key, it is encrypted to {}",
+            recipients.join(", ")
+        ))
+    }
+
+    /// Decrypt the message with the session key, returning the packets it contains. A message
+    /// which consists of a single compressed data packet is decompressed.
+    pub fn decrypt(&self, session_key: &SessionKey) -> anyhow::Result<Vec<PgpPacket>> {
+        let data = self.data.decrypt(session_key)?;
+
+        let (_, mut packets) = parse_pgp_packets(&data)
+            .map_err(|_| anyhow!("could not parse decrypted pgp packets"))?;
+        packets.retain(|packet| !packet.is_unknown());
+        if let [PgpPacket::CompressedDataPacket(compressed)] = packets.as_slice() {
+            return compressed.decompress();
+        }
+
+        Ok(packets)
+    }
+
+    /// Decrypt the message with a session key derived from `passphrase`, returning the packets
+    /// it contains. Since a wrong passphrase can't be told apart from modified data, each
+    /// symmetric-key encrypted session key is tried in turn until the data decrypts.
+    pub fn decrypt_with_passphrase(&self, passphrase: &str) -> anyhow::Result<Vec<PgpPacket>> {
+        if !self.is_encrypted_with_passphrase() {
+            return Err(anyhow!("message is not encrypted with a passphrase"));
+        }
+
+        // a session key whose string-to-key parameters are refused can't be tried at all, which
+        // is reported if none of the others decrypt the message.
+        let mut refused = None;
+        for session_key in self.passphrase_session_keys.iter() {
+            if let Err(error) = session_key.s2k.check_parameters() {
+                refused = Some(error);
+                continue;
+            }
+            if let Ok(packets) = session_key
+                .decrypt(passphrase)
+                .and_then(|session_key| self.decrypt(&session_key))
+            {
+                return Ok(packets);
+            }
+        }
+
+        Err(refused.unwrap_or_else(|| {
+            anyhow!(
+                "could not decrypt the message: the passphrase is wrong, or the encrypted data \
+                 has been modified"
+            )
+        }))
+    }
+
+    /// Serialize the message: the encrypted session keys, followed by the encrypted data.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut packets = self
+            .session_keys
+            .iter()
+            .cloned()
+            .map(PgpPacket::PublicKeyEncryptedSessionKeyPacket)
+            .chain(
+                self.passphrase_session_keys
+                    .iter()
+                    .cloned()
+                    .map(PgpPacket::SymmetricKeyEncryptedSessionKeyPacket),
+            )
+            .collect::<Vec<_>>();
+        packets.push(PgpPacket::IntegrityProtectedDataPacket(self.data.clone()));
+
+        write_pgp_packets(&packets)
+    }
+}
+
+/// Choose the symmetric algorithm to encrypt to all of `recipients`: the first algorithm in the
+/// preferences of the first recipient which every other recipient also prefers. AES-128 is
+/// used if there is no such algorithm, since every implementation must support it (RFC 9580
+/// section 9.3). Without recipients, AES-256 is used.
+pub fn preferred_symmetric_algorithm(recipients: &[PublicKey]) -> SymmetricAlgorithm {
+    if recipients.is_empty() {
+        return SymmetricAlgorithm::Aes256;
+    }
+
+    let preferences = recipients
+        .iter()
+        .map(|recipient| recipient.preferred_symmetric_algorithms())
+        .collect::<Vec<_>>();
+
+    preferences
+        .first()
+        .and_then(|first| {
+            first.iter().copied().find(|algorithm| {
+                preferences
+                    .iter()
+                    .all(|preference| preference.contains(algorithm))
+            })
+        })
+        .unwrap_or(SymmetricAlgorithm::Aes128)
+}
+
+/// The nonce of a chunk: the IV followed by the chunk index as an eight octet number.
+fn chunk_nonce(iv: &[u8], index: u64) -> Vec<u8> {
+    let mut nonce = iv.to_owned();
+    nonce.extend_from_slice(&index.to_be_bytes());
+
+    nonce
+}
+
+/// The key which encrypts the session key in a v6 symmetric-key encrypted session key packet:
+/// the key derived from the passphrase, passed through HKDF-SHA256 without a salt.
+fn aead_key_encryption_key(
+    s2k: &StringToKey,
+    passphrase: &str,
+    symmetric_algorithm: SymmetricAlgorithm,
+    aead_algorithm: AeadAlgorithm,
+) -> anyhow::Result<Vec<u8>> {
+    let key_size = symmetric_algorithm.key_size();
+    let s2k_key = s2k.derive_key(passphrase.as_bytes(), key_size)?;
+
+    hkdf_sha256(
+        None,
+        &s2k_key,
+        &aead_key_associated_data(symmetric_algorithm, aead_algorithm),
+        key_size,
+    )
+}
+
+/// The associated data of the session key in a v6 symmetric-key encrypted session key packet:
+/// the packet tag, the version and the algorithms. It is also the info of the key derivation.
+fn aead_key_associated_data(
+    symmetric_algorithm: SymmetricAlgorithm,
+    aead_algorithm: AeadAlgorithm,
+) -> [u8; 4] {
+    [
+        SYMMETRIC_KEY_ENCRYPTED_SESSION_KEY_TAG,
+        6,
+        symmetric_algorithm.id(),
+        aead_algorithm.id(),
+    ]
+}
+
+/// Derive a key of `size` octets with HKDF-SHA256, as described by RFC 5869.
+pub fn hkdf_sha256(
+    salt: Option<&[u8]>,
+    input_key: &[u8],
+    info: &[u8],
+    size: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let mut key = vec![0; size];
+    Hkdf::<Sha256>::new(salt, input_key)
+        .expand(info, &mut key)
+        .map_err(|_| anyhow!("invalid HKDF output size {}", size))?;
+
+    Ok(key)
+}
@@ -0,0 +1,151 @@
+use anyhow::anyhow;
+use digest::DynDigest;
+use ripemd::Ripemd160;
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512};
+use sha3::{Sha3_256, Sha3_512};
+use std::convert::TryFrom;
+
+/// The hash algorithms which can be used to compute a signature, as defined by the RFC in
+/// section 9.4 (and RFC 9580 section 9.5 for SHA3). MD5 is recognized, but rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Ripemd160,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha224,
+    Sha3_256,
+    Sha3_512,
+}
+
+impl HashAlgorithm {
+    pub fn id(&self) -> u8 {
+        match self {
+            HashAlgorithm::Sha1 => 2,
+            HashAlgorithm::Ripemd160 => 3,
+            HashAlgorithm::Sha256 => 8,
+            HashAlgorithm::Sha384 => 9,
+            HashAlgorithm::Sha512 => 10,
+            HashAlgorithm::Sha224 => 11,
+            HashAlgorithm::Sha3_256 => 12,
+            HashAlgorithm::Sha3_512 => 14,
+        }
+    }
+
+    /// The name of the hash algorithm as used in the "Hash" armor header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "SHA1",
+            HashAlgorithm::Ripemd160 => "RIPEMD160",
+            HashAlgorithm::Sha256 => "SHA256",
+            HashAlgorithm::Sha384 => "SHA384",
+            HashAlgorithm::Sha512 => "SHA512",
+            HashAlgorithm::Sha224 => "SHA224",
+            HashAlgorithm::Sha3_256 => "SHA3-256",
+            HashAlgorithm::Sha3_512 => "SHA3-512",
+        }
+    }
+
+    /// Create a new hasher for this algorithm.
+    pub fn hasher(&self) -> Box<dyn DynDigest> {
+        match self {
+            HashAlgorithm::Sha1 => Box::new(Sha1::default()),
+            HashAlgorithm::Ripemd160 => Box::new(Ripemd160::default()),
+            HashAlgorithm::Sha256 => Box::new(Sha256::default()),
+            HashAlgorithm::Sha384 => Box::new(Sha384::default()),
+            HashAlgorithm::Sha512 => Box::new(Sha512::default()),
+            HashAlgorithm::Sha224 => Box::new(Sha224::default()),
+            HashAlgorithm::Sha3_256 => Box::new(Sha3_256::default()),
+            HashAlgorithm::Sha3_512 => Box::new(Sha3_512::default()),
+        }
+    }
+
+    /// The ASN.1 DER encoded DigestInfo prefix used in PKCS#1 signatures, as listed by the RFC in
+    /// section 5.2.2.
+    pub fn digest_info_prefix(&self) -> &'static [u8] {
+        match self {
+            HashAlgorithm::Sha1 => &[
+                0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04,
+                0x14,
+            ],
+            HashAlgorithm::Ripemd160 => &[
+                0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x24, 0x03, 0x02, 0x01, 0x05, 0x00, 0x04,
+                0x14,
+            ],
+            HashAlgorithm::Sha256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ],
+            HashAlgorithm::Sha384 => &[
+                0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x02, 0x05, 0x00, 0x04, 0x30,
+            ],
+            HashAlgorithm::Sha512 => &[
+                0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x03, 0x05, 0x00, 0x04, 0x40,
+            ],
+            HashAlgorithm::Sha224 => &[
+                0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x04, 0x05, 0x00, 0x04, 0x1c,
+            ],
+            HashAlgorithm::Sha3_256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x08, 0x05, 0x00, 0x04, 0x20,
+            ],
+            HashAlgorithm::Sha3_512 => &[
+                0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x0a, 0x05, 0x00, 0x04, 0x40,
+            ],
+        }
+    }
+
+    /// The size of the salt in a v6 signature using this hash algorithm, as listed by RFC 9580 in
+    /// section 9.5. SHA1 and RIPEMD160 can't be used in v6 signatures.
+    pub fn salt_size(&self) -> Option<usize> {
+        match self {
+            HashAlgorithm::Sha1 | HashAlgorithm::Ripemd160 => None,
+            HashAlgorithm::Sha256 | HashAlgorithm::Sha224 | HashAlgorithm::Sha3_256 => Some(16),
+            HashAlgorithm::Sha384 => Some(24),
+            HashAlgorithm::Sha512 | HashAlgorithm::Sha3_512 => Some(32),
+        }
+    }
+
+    /// Look up a hash algorithm by the name used in the "Hash" armor header.
+    pub fn from_name(name: &str) -> anyhow::Result<HashAlgorithm> {
+        let algorithm = match name {
+            "SHA1" => HashAlgorithm::Sha1,
+            "RIPEMD160" => HashAlgorithm::Ripemd160,
+            "SHA256" => HashAlgorithm::Sha256,
+            "SHA384" => HashAlgorithm::Sha384,
+            "SHA512" => HashAlgorithm::Sha512,
+            "SHA224" => HashAlgorithm::Sha224,
+            "SHA3-256" => HashAlgorithm::Sha3_256,
+            "SHA3-512" => HashAlgorithm::Sha3_512,
+            "MD5" => return Err(anyhow!("hash algorithm MD5 is not supported")),
+            _ => return Err(anyhow!("unknown hash algorithm {}", name)),
+        };
+
+        Ok(algorithm)
+    }
+}
+
+impl TryFrom<u8> for HashAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(val: u8) -> anyhow::Result<Self> {
+        match val {
+            1 => Err(anyhow!("hash algorithm MD5 is not supported")),
+            2 => Ok(HashAlgorithm::Sha1),
+            3 => Ok(HashAlgorithm::Ripemd160),
+            8 => Ok(HashAlgorithm::Sha256),
+            9 => Ok(HashAlgorithm::Sha384),
+            10 => Ok(HashAlgorithm::Sha512),
+            11 => Ok(HashAlgorithm::Sha224),
+            12 => Ok(HashAlgorithm::Sha3_256),
+            14 => Ok(HashAlgorithm::Sha3_512),
+            _ => Err(anyhow!("unknown hash algorithm {}", val)),
+        }
+    }
+}
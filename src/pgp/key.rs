@@ -1,39 +1,172 @@
 use anyhow::anyhow;
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use ed25519_dalek::{Signature, VerifyingKey};
 use num::BigUint;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::convert::{TryFrom, TryInto};
 
-use crate::parsers::parse_ascii_armor_parts_all_consuming;
-use crate::pgp::{AsciiArmor, PgpPacket};
+use super::hash::HashAlgorithm;
+use super::signature::{
+    SignaturePacket, SIGNATURE_TYPE_CASUAL_CERTIFICATION, SIGNATURE_TYPE_GENERIC_CERTIFICATION,
+    SIGNATURE_TYPE_PERSONA_CERTIFICATION, SIGNATURE_TYPE_POSITIVE_CERTIFICATION,
+    SIGNATURE_TYPE_PRIMARY_KEY_BINDING, SIGNATURE_TYPE_SUBKEY_BINDING,
+};
+use super::symmetric::SymmetricAlgorithm;
+use crate::pgp::{write_mpi, AsciiArmor, AsciiArmorKind, PgpPacket, USER_ATTRIBUTE_TAG};
+use crate::rsa::verify_pkcs1;
 
+/// The public key algorithms, as defined by the RFC in section 9.1, RFC 6637 for ECDH and ECDSA,
+/// RFC 4880bis for EdDSA, and RFC 9580 for the native X25519 and Ed25519 algorithms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PublicKeyAlgorithm {
+    Rsa,
+    RsaEncryptOnly,
+    RsaSignOnly,
+    ElGamal,
+    Dsa,
+    Ecdh,
+    Ecdsa,
+    EdDsa,
+    X25519,
+    Ed25519,
+    /// An algorithm this implementation does not know, kept so that its packets can be written
+    /// back unchanged.
+    Unknown(u8),
+}
+
+/// The prefix of an EC point in native (compressed) encoding, as defined by RFC 4880bis section
+/// 13.2.
+pub const NATIVE_POINT_PREFIX: u8 = 0x40;
+
+/// Key flag marking a key which may certify other keys, as defined by the RFC in section 5.2.3.21.
+pub const KEY_FLAG_CERTIFY: u8 = 0x01;
+/// Key flag marking a key which may be used to sign data.
+pub const KEY_FLAG_SIGN: u8 = 0x02;
+/// Key flag marking a key which may be used to encrypt communications.
+pub const KEY_FLAG_ENCRYPT_COMMUNICATIONS: u8 = 0x04;
+/// Key flag marking a key which may be used to encrypt storage.
+pub const KEY_FLAG_ENCRYPT_STORAGE: u8 = 0x08;
+
+/// A certificate: the primary key, along with its user IDs and subkeys.
 #[derive(Debug)]
 pub struct PublicKey {
-    pub n: BigUint,
-    pub e: BigUint,
+    pub primary_key: PublicKeyPacket,
+    pub user_ids: Vec<UserId>,
+    pub subkeys: Vec<Subkey>,
+}
+
+/// A user ID, along with the certification signatures which follow it in the certificate.
+#[derive(Debug, Clone)]
+pub struct UserId {
+    pub user_id: String,
+    pub signatures: Vec<SignaturePacket>,
 }
 
+/// A subkey, along with the signatures which follow it in the certificate.
 #[derive(Debug)]
+pub struct Subkey {
+    pub key: PublicKeyPacket,
+    pub signatures: Vec<SignaturePacket>,
+}
+
+/// The algorithm specific part of a public key, as defined by the RFC in section 5.5.2. Keys of
+/// other algorithms can still be parsed, but not used.
+#[derive(Debug, Clone)]
+pub enum PublicKeyMaterial {
+    Rsa {
+        n: BigUint,
+        e: BigUint,
+    },
+    Dsa {
+        p: BigUint,
+        q: BigUint,
+        g: BigUint,
+        y: BigUint,
+    },
+    /// the point is kept in its SEC1 encoding.
+    Ecdsa {
+        curve: Curve,
+        point: Vec<u8>,
+    },
+    /// the public key is kept without its native point prefix.
+    EdDsa {
+        curve: Curve,
+        public_key: Vec<u8>,
+    },
+    /// an encryption-only ElGamal key.
+    ElGamal {
+        p: BigUint,
+        g: BigUint,
+        y: BigUint,
+    },
+    /// the point is kept as encoded in the packet: SEC1 for the NIST curves, and with the native
+    /// point prefix for Curve25519. The KDF parameters are the hash and symmetric algorithm IDs
+    /// used to derive and wrap the session key, as described by RFC 6637 section 7.
+    Ecdh {
+        curve: Curve,
+        point: Vec<u8>,
+        kdf_hash_algorithm: u8,
+        kdf_symmetric_algorithm: u8,
+    },
+    /// a native Ed25519 key, as defined by RFC 9580 section 5.5.5.10.
+    Ed25519 {
+        public_key: Vec<u8>,
+    },
+    /// a native X25519 key, which can only be used for encryption, as defined by RFC 9580 section
+    /// 5.5.5.8.
+    X25519 {
+        public_key: Vec<u8>,
+    },
+    Unknown,
+}
+
+/// The elliptic curves which are supported, identified in key packets by their OIDs as listed by
+/// RFC 4880bis section 9.2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    NistP256,
+    NistP384,
+    NistP521,
+    Ed25519,
+    Cv25519,
+}
+
+/// A user ID packet, as defined by the RFC in section 5.11.
+#[derive(Debug)]
+pub struct UserIdPacket {
+    pub user_id: String,
+}
+
+/// A public key or public subkey packet, as defined by the RFC in section 5.5.2. Both v4 and v6
+/// (RFC 9580 section 5.5.2.3) keys are supported.
+#[derive(Debug, Clone)]
 pub struct PublicKeyPacket {
-    pub n: BigUint,
-    pub e: BigUint,
+    pub version: u8,
+    pub creation_time: u32,
+    pub public_key_algorithm: PublicKeyAlgorithm,
+    pub key_material: PublicKeyMaterial,
+    /// the raw packet body, which is needed to compute the fingerprint and to hash key
+    /// signatures.
+    pub packet_data: Vec<u8>,
 }
 
 impl PublicKey {
     pub fn parse(input: &str) -> anyhow::Result<Self> {
-        let (_, parts) = parse_ascii_armor_parts_all_consuming(input)
-            .map_err(|_| anyhow!("could not parse ascii armor parts"))?;
-
-        let ascii_armor = AsciiArmor::from_parts(parts)?;
-        if !ascii_armor.verify() {
-            return Err(anyhow!(
-                "ascii armor failed to verify: checksum did not match"
-            ));
+        let ascii_armor = AsciiArmor::parse(input)?;
+        if ascii_armor.kind != AsciiArmorKind::PublicKey {
+            return Err(anyhow!("ascii armor did not contain a public key"));
         }
 
-        let packets = ascii_armor.into_pgp_packets()?;
+        // packets which are not understood, such as a marker packet, may precede the key.
+        let mut packets = ascii_armor
+            .to_pgp_packets()?
+            .into_iter()
+            .skip_while(|packet| matches!(packet, PgpPacket::Unknown { .. }));
 
-        // this is a bit hacky, I know my keys will have the key used for signing as
-        // the first packet, but this doesn't have to be the case.
-        let public_key_packet = match &packets[0] {
-            PgpPacket::PublicKeyPacket(p) => p,
+        let primary_key = match packets.next() {
+            Some(PgpPacket::PublicKeyPacket(p)) => p,
             _ => {
                 return Err(anyhow!(
                     "first packet from the ascii armor was not a public key packet."
@@ -41,9 +174,683 @@ impl PublicKey {
             }
         };
 
+        // each user ID and subkey is followed by its signatures, as described by the RFC in
+        // section 11.1. Signatures directly on the primary key or on a user attribute are
+        // skipped, as are other packets which are not understood, such as trust packets.
+        enum Component {
+            PrimaryKey,
+            UserId,
+            Subkey,
+            UserAttribute,
+        }
+        let mut last = Component::PrimaryKey;
+        let mut user_ids: Vec<UserId> = Vec::new();
+        let mut subkeys: Vec<Subkey> = Vec::new();
+        for packet in packets {
+            match packet {
+                PgpPacket::UserIdPacket(packet) => {
+                    user_ids.push(UserId {
+                        user_id: packet.user_id,
+                        signatures: Vec::new(),
+                    });
+                    last = Component::UserId;
+                }
+                PgpPacket::PublicSubkeyPacket(key) => {
+                    subkeys.push(Subkey {
+                        key,
+                        signatures: Vec::new(),
+                    });
+                    last = Component::Subkey;
+                }
+                PgpPacket::Unknown {
+                    tag: USER_ATTRIBUTE_TAG,
+                    ..
+                } => last = Component::UserAttribute,
+                PgpPacket::SignaturePacket(signature) => match last {
+                    Component::PrimaryKey | Component::UserAttribute => {}
+                    Component::UserId => user_ids
+                        .last_mut()
+                        .expect("a user id was seen")
+                        .signatures
+                        .push(signature),
+                    Component::Subkey => subkeys
+                        .last_mut()
+                        .expect("a subkey was seen")
+                        .signatures
+                        .push(signature),
+                },
+                _ => {}
+            }
+        }
+
         Ok(PublicKey {
-            n: public_key_packet.n.clone(),
-            e: public_key_packet.e.clone(),
+            primary_key,
+            user_ids,
+            subkeys,
+        })
+    }
+
+    /// The user IDs which are self-certified by the primary key. Other user IDs can't be trusted
+    /// to belong to the key holder.
+    pub fn verified_user_ids(&self) -> impl Iterator<Item = (&str, &SignaturePacket)> {
+        self.user_ids.iter().filter_map(move |user_id| {
+            user_id
+                .verify_self_certification(&self.primary_key)
+                .ok()
+                .map(|certification| (user_id.user_id.as_str(), certification))
+        })
+    }
+
+    /// The primary user ID: the verified user ID whose self-certification marks it as primary,
+    /// or the first verified user ID otherwise.
+    pub fn primary_user_id(&self) -> Option<&str> {
+        self.primary_user_id_certification()
+            .map(|(user_id, _)| user_id)
+    }
+
+    /// The primary user ID along with its self-certification, which carries the preferences of
+    /// the key holder.
+    fn primary_user_id_certification(&self) -> Option<(&str, &SignaturePacket)> {
+        let user_ids = self.verified_user_ids().collect::<Vec<_>>();
+
+        user_ids
+            .iter()
+            .find(|(_, certification)| certification.is_primary_user_id())
+            .or_else(|| user_ids.first())
+            .copied()
+    }
+
+    /// The primary key followed by the subkeys, regardless of whether the subkeys are bound.
+    pub fn keys(&self) -> impl Iterator<Item = &PublicKeyPacket> {
+        std::iter::once(&self.primary_key).chain(self.subkeys.iter().map(|subkey| &subkey.key))
+    }
+
+    /// The fingerprint of the primary key, which identifies the certificate.
+    pub fn fingerprint(&self) -> Vec<u8> {
+        self.primary_key.fingerprint()
+    }
+
+    /// The key ID of the primary key.
+    pub fn key_id(&self) -> u64 {
+        self.primary_key.key_id()
+    }
+
+    /// The symmetric algorithms the key holder prefers, most preferred first, from the
+    /// self-certification of the primary user ID. Empty if the key has no preferences.
+    pub fn preferred_symmetric_algorithms(&self) -> Vec<SymmetricAlgorithm> {
+        self.primary_user_id_certification()
+            .and_then(|(_, certification)| certification.preferred_symmetric_algorithms())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|algorithm| SymmetricAlgorithm::try_from(*algorithm).ok())
+            .collect()
+    }
+
+    /// Find the key which messages to this certificate should be encrypted to: the first subkey
+    /// which is bound to the primary key as an encryption key, or the primary key if its
+    /// self-certification allows encryption.
+    pub fn find_encryption_key(&self) -> anyhow::Result<&PublicKeyPacket> {
+        let subkey = self.subkeys.iter().find(|subkey| {
+            subkey
+                .verify_binding(&self.primary_key)
+                .is_ok_and(allows_encryption)
+        });
+        if let Some(subkey) = subkey {
+            return Ok(&subkey.key);
+        }
+
+        match self.primary_user_id_certification() {
+            Some((_, certification)) if allows_encryption(certification) => Ok(&self.primary_key),
+            _ => Err(anyhow!(
+                "key 0x{:016X} does not have a key which can be used for encryption",
+                self.key_id()
+            )),
+        }
+    }
+
+    /// Look up the primary key or a subkey by its fingerprint.
+    pub fn find_by_fingerprint(&self, fingerprint: &[u8]) -> Option<&PublicKeyPacket> {
+        self.keys()
+            .find(|key| key.fingerprint().as_slice() == fingerprint)
+    }
+
+    /// Look up the primary key or a subkey by its key ID.
+    pub fn find_by_key_id(&self, key_id: u64) -> Option<&PublicKeyPacket> {
+        self.keys().find(|key| key.key_id() == key_id)
+    }
+
+    /// Find the key which made the signature, using the issuer fingerprint if the signature has
+    /// one, and the issuer key ID otherwise. A subkey is only returned if it is bound to the
+    /// primary key as a signing key.
+    pub fn find_signing_key(
+        &self,
+        signature: &SignaturePacket,
+    ) -> anyhow::Result<&PublicKeyPacket> {
+        let key = match (signature.issuer_fingerprint(), signature.issuer()) {
+            (Some(fingerprint), _) => self.find_by_fingerprint(fingerprint).ok_or_else(|| {
+                anyhow!(
+                    "no matching key for the signature issuer fingerprint {}",
+                    format_fingerprint(fingerprint)
+                )
+            })?,
+            (None, Some(key_id)) => self.find_by_key_id(key_id).ok_or_else(|| {
+                anyhow!("no matching key for the signature issuer 0x{:016X}", key_id)
+            })?,
+            (None, None) => {
+                return Err(anyhow!("signature does not identify the key which made it"))
+            }
+        };
+
+        // a subkey can only be used if it is bound to the primary key as a signing key.
+        if let Some(subkey) = self
+            .subkeys
+            .iter()
+            .find(|subkey| std::ptr::eq(&subkey.key, key))
+        {
+            let binding = subkey.verify_binding(&self.primary_key)?;
+            if !allows_signing(binding) {
+                return Err(anyhow!("subkey is not bound as a signing key"));
+            }
+        }
+
+        Ok(key)
+    }
+}
+
+impl UserId {
+    /// Find a certification signature (0x10 to 0x13) over the user ID made by the primary key,
+    /// as defined by the RFC in section 5.2.4. Returns the certification, preferring the newest
+    /// one.
+    pub fn verify_self_certification(
+        &self,
+        primary_key: &PublicKeyPacket,
+    ) -> anyhow::Result<&SignaturePacket> {
+        let data = certification_data(primary_key, &self.user_id)?;
+
+        let mut certifications = self
+            .signatures
+            .iter()
+            .filter(|s| is_certification(s.signature_type) && s.is_issued_by(primary_key))
+            .collect::<Vec<_>>();
+        certifications.sort_by_key(|s| std::cmp::Reverse(s.creation_time()));
+
+        let mut last_error = anyhow!("user id does not have a self-certification");
+        for certification in certifications {
+            match certification.verify_with_key(primary_key, &data) {
+                Ok(true) => return Ok(certification),
+                Ok(false) => last_error = anyhow!("self-certification is invalid"),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(last_error.context(format!(
+            "user id \"{}\" is not self-certified",
+            self.user_id
+        )))
+    }
+}
+
+impl Subkey {
+    /// Find a subkey binding signature (0x18) made by the primary key, as defined by the RFC in
+    /// section 5.2.1. If the binding allows the subkey to sign, it must also contain a primary
+    /// key binding signature (0x19) made by the subkey, so that a signing subkey can't be claimed
+    /// by another primary key. Returns the binding signature, preferring the newest one.
+    pub fn verify_binding(
+        &self,
+        primary_key: &PublicKeyPacket,
+    ) -> anyhow::Result<&SignaturePacket> {
+        let data = subkey_binding_data(primary_key, &self.key);
+
+        let mut bindings = self
+            .signatures
+            .iter()
+            .filter(|s| s.signature_type == SIGNATURE_TYPE_SUBKEY_BINDING)
+            .collect::<Vec<_>>();
+        bindings.sort_by_key(|s| std::cmp::Reverse(s.creation_time()));
+
+        let mut last_error = anyhow!("subkey does not have a binding signature");
+        for binding in bindings {
+            match verify_subkey_binding(binding, primary_key, &self.key, &data) {
+                Ok(()) => return Ok(binding),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(last_error.context("subkey is not bound to the primary key"))
+    }
+}
+
+/// The data hashed by a certification of a user ID: the primary key, then the octet 0xB4, the
+/// four-octet length of the user ID and the user ID itself.
+pub fn certification_data(primary_key: &PublicKeyPacket, user_id: &str) -> anyhow::Result<Vec<u8>> {
+    let mut data = primary_key.hash_data();
+    data.push(0xb4);
+    data.write_u32::<BigEndian>(user_id.len().try_into()?)?;
+    data.extend_from_slice(user_id.as_bytes());
+
+    Ok(data)
+}
+
+/// The data hashed by subkey and primary key binding signatures: the primary key, then the
+/// subkey.
+pub fn subkey_binding_data(primary_key: &PublicKeyPacket, subkey: &PublicKeyPacket) -> Vec<u8> {
+    let mut data = primary_key.hash_data();
+    data.extend_from_slice(&subkey.hash_data());
+    data
+}
+
+fn verify_subkey_binding(
+    binding: &SignaturePacket,
+    primary_key: &PublicKeyPacket,
+    subkey: &PublicKeyPacket,
+    data: &[u8],
+) -> anyhow::Result<()> {
+    if !binding.verify_with_key(primary_key, data)? {
+        return Err(anyhow!("subkey binding signature is invalid"));
+    }
+
+    if allows_signing(binding) {
+        let back_signature = binding
+            .embedded_signature()
+            .filter(|s| s.signature_type == SIGNATURE_TYPE_PRIMARY_KEY_BINDING)
+            .ok_or_else(|| {
+                anyhow!("signing subkey does not have a primary key binding signature")
+            })?;
+        if !back_signature.verify_with_key(subkey, data)? {
+            return Err(anyhow!("primary key binding signature is invalid"));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_certification(signature_type: u8) -> bool {
+    matches!(
+        signature_type,
+        SIGNATURE_TYPE_GENERIC_CERTIFICATION
+            | SIGNATURE_TYPE_PERSONA_CERTIFICATION
+            | SIGNATURE_TYPE_CASUAL_CERTIFICATION
+            | SIGNATURE_TYPE_POSITIVE_CERTIFICATION
+    )
+}
+
+/// Whether a self-certification or binding signature allows the key to sign. Signatures without
+/// key flags predate them, so they don't restrict the key.
+pub fn allows_signing(signature: &SignaturePacket) -> bool {
+    match signature.key_flags() {
+        Some(flags) => flags
+            .first()
+            .is_some_and(|flags| flags & KEY_FLAG_SIGN != 0),
+        None => true,
+    }
+}
+
+/// Whether a self-certification or binding signature allows the key to encrypt. Unlike signing,
+/// a key without key flags isn't assumed to be an encryption key, as the primary key of such
+/// certificates usually only signs.
+pub fn allows_encryption(signature: &SignaturePacket) -> bool {
+    signature.key_flags().is_some_and(|flags| {
+        flags.first().is_some_and(|flags| {
+            flags & (KEY_FLAG_ENCRYPT_COMMUNICATIONS | KEY_FLAG_ENCRYPT_STORAGE) != 0
         })
+    })
+}
+
+impl PublicKeyAlgorithm {
+    pub fn id(&self) -> u8 {
+        match self {
+            PublicKeyAlgorithm::Rsa => 1,
+            PublicKeyAlgorithm::RsaEncryptOnly => 2,
+            PublicKeyAlgorithm::RsaSignOnly => 3,
+            PublicKeyAlgorithm::ElGamal => 16,
+            PublicKeyAlgorithm::Dsa => 17,
+            PublicKeyAlgorithm::Ecdh => 18,
+            PublicKeyAlgorithm::Ecdsa => 19,
+            PublicKeyAlgorithm::EdDsa => 22,
+            PublicKeyAlgorithm::X25519 => 25,
+            PublicKeyAlgorithm::Ed25519 => 27,
+            PublicKeyAlgorithm::Unknown(id) => *id,
+        }
+    }
+}
+
+impl From<u8> for PublicKeyAlgorithm {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => PublicKeyAlgorithm::Rsa,
+            2 => PublicKeyAlgorithm::RsaEncryptOnly,
+            3 => PublicKeyAlgorithm::RsaSignOnly,
+            16 => PublicKeyAlgorithm::ElGamal,
+            17 => PublicKeyAlgorithm::Dsa,
+            18 => PublicKeyAlgorithm::Ecdh,
+            19 => PublicKeyAlgorithm::Ecdsa,
+            22 => PublicKeyAlgorithm::EdDsa,
+            25 => PublicKeyAlgorithm::X25519,
+            27 => PublicKeyAlgorithm::Ed25519,
+            _ => PublicKeyAlgorithm::Unknown(val),
+        }
+    }
+}
+
+impl PublicKeyMaterial {
+    /// Serialize the key material, as it appears in a key packet.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        match self {
+            PublicKeyMaterial::Rsa { n, e } => {
+                write_mpi(&mut bytes, n);
+                write_mpi(&mut bytes, e);
+            }
+            PublicKeyMaterial::Dsa { p, q, g, y } => {
+                for value in [p, q, g, y].iter() {
+                    write_mpi(&mut bytes, value);
+                }
+            }
+            PublicKeyMaterial::ElGamal { p, g, y } => {
+                for value in [p, g, y].iter() {
+                    write_mpi(&mut bytes, value);
+                }
+            }
+            PublicKeyMaterial::Ecdsa { curve, point } => {
+                write_curve_oid(&mut bytes, *curve);
+                write_mpi(&mut bytes, &BigUint::from_bytes_be(point));
+            }
+            PublicKeyMaterial::EdDsa { curve, public_key } => {
+                write_curve_oid(&mut bytes, *curve);
+                let mut point = vec![NATIVE_POINT_PREFIX];
+                point.extend_from_slice(public_key);
+                write_mpi(&mut bytes, &BigUint::from_bytes_be(&point));
+            }
+            PublicKeyMaterial::Ecdh {
+                curve,
+                point,
+                kdf_hash_algorithm,
+                kdf_symmetric_algorithm,
+            } => {
+                write_curve_oid(&mut bytes, *curve);
+                write_mpi(&mut bytes, &BigUint::from_bytes_be(point));
+                bytes.extend_from_slice(&[
+                    0x03,
+                    0x01,
+                    *kdf_hash_algorithm,
+                    *kdf_symmetric_algorithm,
+                ]);
+            }
+            PublicKeyMaterial::Ed25519 { public_key }
+            | PublicKeyMaterial::X25519 { public_key } => bytes.extend_from_slice(public_key),
+            PublicKeyMaterial::Unknown => {
+                return Err(anyhow!("can't serialize a key of an unsupported algorithm"))
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Verify the algorithm specific signature values over a hash computed with
+    /// `hash_algorithm`, as described by the RFC in section 5.2.2.
+    pub fn verify(
+        &self,
+        hash_algorithm: HashAlgorithm,
+        hash: &[u8],
+        signature: &[BigUint],
+    ) -> anyhow::Result<bool> {
+        match (self, signature) {
+            (PublicKeyMaterial::Rsa { n, e }, [s]) => Ok(verify_pkcs1(
+                n,
+                e,
+                hash_algorithm.digest_info_prefix(),
+                hash,
+                s,
+            )),
+            (PublicKeyMaterial::Dsa { p, q, g, y }, [r, s]) => {
+                Ok(verify_dsa(p, q, g, y, hash, r, s))
+            }
+            (PublicKeyMaterial::Ecdsa { curve, point }, [r, s]) => match curve {
+                Curve::NistP256 => Ok(verify_ecdsa::<_, p256::ecdsa::Signature>(
+                    p256::ecdsa::VerifyingKey::from_sec1_bytes(point).ok(),
+                    hash,
+                    r,
+                    s,
+                    32,
+                )),
+                Curve::NistP384 => Ok(verify_ecdsa::<_, p384::ecdsa::Signature>(
+                    p384::ecdsa::VerifyingKey::from_sec1_bytes(point).ok(),
+                    hash,
+                    r,
+                    s,
+                    48,
+                )),
+                Curve::NistP521 => Ok(verify_ecdsa::<_, p521::ecdsa::Signature>(
+                    p521::ecdsa::VerifyingKey::from_sec1_bytes(point).ok(),
+                    hash,
+                    r,
+                    s,
+                    66,
+                )),
+                _ => Err(anyhow!("curve {:?} can't be used with ECDSA", curve)),
+            },
+            (PublicKeyMaterial::EdDsa { curve, public_key }, [r, s]) => match curve {
+                Curve::Ed25519 => Ok(verify_ed25519(public_key, hash, r, s)),
+                _ => Err(anyhow!("curve {:?} can't be used with EdDSA", curve)),
+            },
+            (PublicKeyMaterial::Ed25519 { public_key }, [r, s]) => {
+                Ok(verify_ed25519(public_key, hash, r, s))
+            }
+            (PublicKeyMaterial::ElGamal { .. }, _)
+            | (PublicKeyMaterial::Ecdh { .. }, _)
+            | (PublicKeyMaterial::X25519 { .. }, _) => {
+                Err(anyhow!("key can only be used for encryption"))
+            }
+            (PublicKeyMaterial::Unknown, _) => Err(anyhow!("unsupported public key algorithm")),
+            _ => Err(anyhow!(
+                "signature has the wrong number of values for the public key algorithm"
+            )),
+        }
+    }
+}
+
+impl Curve {
+    /// Look up a curve by the OID used in key packets (without the ASN.1 tag and length).
+    pub fn from_oid(oid: &[u8]) -> Option<Curve> {
+        match oid {
+            [0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07] => Some(Curve::NistP256),
+            [0x2b, 0x81, 0x04, 0x00, 0x22] => Some(Curve::NistP384),
+            [0x2b, 0x81, 0x04, 0x00, 0x23] => Some(Curve::NistP521),
+            [0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01] => Some(Curve::Ed25519),
+            [0x2b, 0x06, 0x01, 0x04, 0x01, 0x97, 0x55, 0x01, 0x05, 0x01] => Some(Curve::Cv25519),
+            _ => None,
+        }
+    }
+
+    /// The OID used in key packets (without the ASN.1 tag and length).
+    pub fn oid(&self) -> &'static [u8] {
+        match self {
+            Curve::NistP256 => &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07],
+            Curve::NistP384 => &[0x2b, 0x81, 0x04, 0x00, 0x22],
+            Curve::NistP521 => &[0x2b, 0x81, 0x04, 0x00, 0x23],
+            Curve::Ed25519 => &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01],
+            Curve::Cv25519 => &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x97, 0x55, 0x01, 0x05, 0x01],
+        }
     }
 }
+
+/// Write the curve OID of an ECC key, preceded by its length.
+fn write_curve_oid(bytes: &mut Vec<u8>, curve: Curve) {
+    let oid = curve.oid();
+    bytes.push(oid.len() as u8);
+    bytes.extend_from_slice(oid);
+}
+
+/// Encode the two signature values as fixed size octet strings, one after the other. The values
+/// are stored as MPIs, so their leading zeros have to be restored.
+fn concat_signature_values(r: &BigUint, s: &BigUint, size: usize) -> Option<Vec<u8>> {
+    let mut signature = vec![0_u8; size * 2];
+    for (value, out) in [r, s].iter().zip(signature.chunks_mut(size)) {
+        let bytes = value.to_bytes_be();
+        if bytes.len() > size {
+            return None;
+        }
+        out[size - bytes.len()..].copy_from_slice(&bytes);
+    }
+
+    Some(signature)
+}
+
+/// Ed25519 verification as defined by RFC 8032, with the hash as the message.
+fn verify_ed25519(public_key: &[u8], hash: &[u8], r: &BigUint, s: &BigUint) -> bool {
+    let public_key = match public_key.try_into().map(VerifyingKey::from_bytes) {
+        Ok(Ok(public_key)) => public_key,
+        _ => return false,
+    };
+    let signature = match concat_signature_values(r, s, 32)
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+    {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    public_key.verify_strict(hash, &signature).is_ok()
+}
+
+/// ECDSA verification as defined by FIPS 186-4 section 6.4, over a curve whose scalars are
+/// `size` octets. The hash is truncated to the size of the curve order by the verifier. `key` is
+/// `None` if the point couldn't be decoded.
+fn verify_ecdsa<K, S>(key: Option<K>, hash: &[u8], r: &BigUint, s: &BigUint, size: usize) -> bool
+where
+    K: PrehashVerifier<S>,
+    S: for<'a> TryFrom<&'a [u8]>,
+{
+    let key = match key {
+        Some(key) => key,
+        None => return false,
+    };
+    let signature = match concat_signature_values(r, s, size)
+        .and_then(|signature| S::try_from(signature.as_slice()).ok())
+    {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    key.verify_prehash(hash, &signature).is_ok()
+}
+
+/// DSA verification as defined by FIPS 186-4 section 4.7. The hash is truncated to the size of
+/// `q`, as required by the RFC in section 13.6.
+fn verify_dsa(
+    p: &BigUint,
+    q: &BigUint,
+    g: &BigUint,
+    y: &BigUint,
+    hash: &[u8],
+    r: &BigUint,
+    s: &BigUint,
+) -> bool {
+    let zero = BigUint::from(0_u8);
+    if *r == zero || r >= q || *s == zero || s >= q {
+        return false;
+    }
+    // the key comes from untrusted input, so check the domain parameters before using them.
+    if *p == zero || *q < BigUint::from(2_u8) || *g == zero || (p - 1_u8) % q != zero {
+        return false;
+    }
+
+    let mut z = BigUint::from_bytes_be(hash);
+    let hash_bits = hash.len() as u64 * 8;
+    if hash_bits > q.bits() {
+        z >>= (hash_bits - q.bits()) as usize;
+    }
+
+    // q is prime, so the inverse of s is s^(q - 2) mod q.
+    let w = s.modpow(&(q - 2_u8), q);
+    let u1 = (z * &w) % q;
+    let u2 = (r * &w) % q;
+    let v = (g.modpow(&u1, p) * y.modpow(&u2, p)) % p % q;
+
+    v == *r
+}
+
+impl PublicKeyPacket {
+    /// Create a v4 key packet.
+    pub fn new(
+        creation_time: u32,
+        public_key_algorithm: PublicKeyAlgorithm,
+        key_material: PublicKeyMaterial,
+    ) -> anyhow::Result<PublicKeyPacket> {
+        let mut packet_data = vec![4];
+        packet_data.write_u32::<BigEndian>(creation_time)?;
+        packet_data.push(public_key_algorithm.id());
+        packet_data.extend_from_slice(&key_material.to_bytes()?);
+        if u16::try_from(packet_data.len()).is_err() {
+            return Err(anyhow!("key packet is too long for a v4 key"));
+        }
+
+        Ok(PublicKeyPacket {
+            version: 4,
+            creation_time,
+            public_key_algorithm,
+            key_material,
+            packet_data,
+        })
+    }
+
+    /// The octet 0x99, the two-octet packet length and the packet body, which is how a key is
+    /// hashed for fingerprints and key signatures. v6 keys instead use the octet 0x9B and a
+    /// four-octet packet length. The parser rejects v4 key packets which are too long for a
+    /// two-octet length.
+    pub fn hash_data(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        if self.version == 6 {
+            data.push(0x9b);
+            let length = self
+                .packet_data
+                .len()
+                .try_into()
+                .expect("public key packet is too long");
+            data.write_u32::<BigEndian>(length)
+                .expect("writing to a vector can't fail");
+        } else {
+            data.push(0x99);
+            let length = self
+                .packet_data
+                .len()
+                .try_into()
+                .expect("public key packet is too long");
+            data.write_u16::<BigEndian>(length)
+                .expect("writing to a vector can't fail");
+        }
+        data.extend_from_slice(&self.packet_data);
+        data
+    }
+
+    /// The fingerprint, as defined by the RFC in section 12.2 for v4 keys, and by RFC 9580
+    /// section 5.5.4.3 for v6 keys.
+    pub fn fingerprint(&self) -> Vec<u8> {
+        if self.version == 6 {
+            Sha256::digest(self.hash_data()).to_vec()
+        } else {
+            Sha1::digest(self.hash_data()).to_vec()
+        }
+    }
+
+    /// The key ID: the low 64 bits of the fingerprint for v4 keys, and the high 64 bits for v6
+    /// keys.
+    pub fn key_id(&self) -> u64 {
+        let fingerprint = self.fingerprint();
+        if self.version == 6 {
+            BigEndian::read_u64(&fingerprint[..8])
+        } else {
+            BigEndian::read_u64(&fingerprint[fingerprint.len() - 8..])
+        }
+    }
+
+    /// The short key ID: the low 32 bits of the fingerprint. Short key IDs are easy to collide,
+    /// so they should only be used for display.
+    pub fn short_key_id(&self) -> u32 {
+        self.key_id() as u32
+    }
+}
+
+/// Format a fingerprint as uppercase hex, as it is usually displayed.
+pub fn format_fingerprint(fingerprint: &[u8]) -> String {
+    fingerprint.iter().map(|b| format!("{:02X}", b)).collect()
+}
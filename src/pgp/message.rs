@@ -0,0 +1,318 @@
+use crate::parsers::parse_pgp_packets;
+use crate::pgp::{read_pgp_packets, write_pgp_packets, AsciiArmorKind, PgpPacket};
+use crate::utils::{canonicalize_line_endings, unix_time_now};
+use anyhow::anyhow;
+use byteorder::{BigEndian, WriteBytesExt};
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+use std::convert::TryInto;
+use std::io::Read;
+
+use super::key::{PublicKey, PublicKeyAlgorithm};
+use super::secret_key::SecretKey;
+use super::signature::SignaturePacket;
+
+/// The most data a compressed data packet may decompress to, 1 GiB, so that a small packet can't
+/// exhaust memory.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 1 << 30;
+
+#[derive(Debug)]
+pub struct OnePassSignaturePacket {
+    pub version: u8,
+    pub signature_type: u8,
+    pub hash_algorithm: u8,
+    pub public_key_algorithm: PublicKeyAlgorithm,
+    /// the salt of the signature, only present in v6 one-pass signatures.
+    pub salt: Vec<u8>,
+    /// the fingerprint of the signing key, only present in v6 one-pass signatures.
+    pub fingerprint: Option<Vec<u8>>,
+    /// the key ID of the signing key. For v6 one-pass signatures, it is taken from the
+    /// fingerprint.
+    pub key_id: u64,
+    /// the RFC's "nested" flag. false means the next packet is another one-pass signature over
+    /// the same data.
+    pub last: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiteralDataPacket {
+    /// one of 'b' (binary), 't' (text) or 'u' (UTF-8 text).
+    pub format: u8,
+    pub filename: Vec<u8>,
+    pub date: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct CompressedDataPacket {
+    pub algorithm: u8,
+    pub data: Vec<u8>,
+}
+
+/// A message signed using one-pass signatures, as described by the RFC in section 11.3. The
+/// one-pass signatures come first, followed by the literal data, followed by the signatures in
+/// the reverse order of the one-pass signatures.
+#[derive(Debug)]
+pub struct SignedMessage {
+    literal: LiteralDataPacket,
+    /// the signatures, in the same order as the one-pass signatures which precede them.
+    signatures: Vec<SignaturePacket>,
+}
+
+impl OnePassSignaturePacket {
+    /// Create the v3 one-pass signature which announces a v4 signature.
+    pub fn new(signature: &SignaturePacket, last: bool) -> anyhow::Result<OnePassSignaturePacket> {
+        let key_id = signature
+            .issuer()
+            .ok_or_else(|| anyhow!("signature does not identify the key which made it"))?;
+
+        Ok(OnePassSignaturePacket {
+            version: 3,
+            signature_type: signature.signature_type,
+            hash_algorithm: signature.hash_algorithm,
+            public_key_algorithm: signature.public_key_algorithm,
+            salt: Vec::new(),
+            fingerprint: None,
+            key_id,
+            last,
+        })
+    }
+
+    /// Serialize the packet body.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = vec![
+            self.version,
+            self.signature_type,
+            self.hash_algorithm,
+            self.public_key_algorithm.id(),
+        ];
+        match &self.fingerprint {
+            Some(fingerprint) if self.version == 6 => {
+                bytes.push(self.salt.len().try_into()?);
+                bytes.extend_from_slice(&self.salt);
+                bytes.extend_from_slice(fingerprint);
+            }
+            _ => bytes.write_u64::<BigEndian>(self.key_id)?,
+        }
+        bytes.push(u8::from(self.last));
+
+        Ok(bytes)
+    }
+}
+
+impl LiteralDataPacket {
+    /// Serialize the packet body.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = vec![self.format, self.filename.len().try_into()?];
+        bytes.extend_from_slice(&self.filename);
+        bytes.write_u32::<BigEndian>(self.date)?;
+        bytes.extend_from_slice(&self.data);
+
+        Ok(bytes)
+    }
+}
+
+impl CompressedDataPacket {
+    /// Decompress the data, and parse the packets it contains. Algorithms are defined by the RFC
+    /// in section 9.3, BZip2 is not supported. The data may decompress to at most
+    /// `MAX_DECOMPRESSED_SIZE` octets.
+    pub fn decompress(&self) -> anyhow::Result<Vec<PgpPacket>> {
+        self.decompress_with_limit(MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Decompress the data like `decompress`, failing if it decompresses to more than `limit`
+    /// octets.
+    pub fn decompress_with_limit(&self, limit: u64) -> anyhow::Result<Vec<PgpPacket>> {
+        let mut data = Vec::new();
+        // one octet more than the limit is read, to tell data which is too large apart.
+        match self.algorithm {
+            0 => self
+                .data
+                .as_slice()
+                .take(limit + 1)
+                .read_to_end(&mut data)?,
+            1 => DeflateDecoder::new(self.data.as_slice())
+                .take(limit + 1)
+                .read_to_end(&mut data)?,
+            2 => ZlibDecoder::new(self.data.as_slice())
+                .take(limit + 1)
+                .read_to_end(&mut data)?,
+            algorithm => return Err(anyhow!("unsupported compression algorithm {}", algorithm)),
+        };
+        if data.len() as u64 > limit {
+            return Err(anyhow!(
+                "compressed data decompresses to more than {} octets",
+                limit
+            ));
+        }
+
+        let (_, mut packets) = parse_pgp_packets(&data)
+            .map_err(|_| anyhow!("could not parse compressed pgp packets"))?;
+        packets.retain(|packet| !packet.is_unknown());
+
+        Ok(packets)
+    }
+
+    /// Serialize the packet body. The data is written as it is, already compressed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.algorithm];
+        bytes.extend_from_slice(&self.data);
+
+        bytes
+    }
+}
+
+impl SignedMessage {
+    /// Sign `data` with a single one-pass signature. In text mode, the data is stored as text in
+    /// the literal data packet, with canonical line endings as described by the RFC in section
+    /// 5.9, and signed as a text document. Filenames longer than 255 octets are truncated.
+    pub fn create(
+        data: Vec<u8>,
+        filename: &[u8],
+        text: bool,
+        key: &SecretKey,
+    ) -> anyhow::Result<SignedMessage> {
+        let data = if text {
+            canonicalize_line_endings(&data)
+        } else {
+            data
+        };
+
+        let signature = key.sign_document(text, &data)?;
+        let literal = LiteralDataPacket {
+            format: if text { b't' } else { b'b' },
+            filename: filename[..filename.len().min(255)].to_owned(),
+            date: unix_time_now().try_into()?,
+            data,
+        };
+
+        Ok(SignedMessage {
+            literal,
+            signatures: vec![signature],
+        })
+    }
+
+    /// Parse a signed message, which may be either ascii armored or binary, and may be compressed.
+    pub fn parse(input: &[u8]) -> anyhow::Result<SignedMessage> {
+        let mut packets = read_pgp_packets(input, AsciiArmorKind::Message)?;
+        packets.retain(|packet| !packet.is_unknown());
+
+        if let [PgpPacket::CompressedDataPacket(compressed)] = packets.as_slice() {
+            packets = compressed.decompress()?;
+        }
+
+        let mut packets = packets.into_iter();
+        let mut one_pass_signatures = Vec::new();
+        let literal = loop {
+            match packets.next() {
+                Some(PgpPacket::OnePassSignaturePacket(p)) => one_pass_signatures.push(p),
+                Some(PgpPacket::LiteralDataPacket(p)) => break p,
+                _ => {
+                    return Err(anyhow!(
+                        "expected one-pass signature packets followed by a literal data packet"
+                    ))
+                }
+            }
+        };
+
+        let mut signatures = packets
+            .map(|packet| match packet {
+                PgpPacket::SignaturePacket(signature) => Ok(signature),
+                _ => Err(anyhow!(
+                    "expected only signature packets after the literal data packet"
+                )),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        match one_pass_signatures.last() {
+            None => return Err(anyhow!("message is not signed")),
+            Some(one_pass_signature) if !one_pass_signature.last => {
+                return Err(anyhow!(
+                    "one-pass signature packet before the literal data is not marked as the last"
+                ))
+            }
+            _ => {}
+        }
+        if one_pass_signatures.len() != signatures.len() {
+            return Err(anyhow!(
+                "found {} one-pass signatures, but {} signatures",
+                one_pass_signatures.len(),
+                signatures.len()
+            ));
+        }
+
+        // the signatures bracket the literal data, so the last signature corresponds to the first
+        // one-pass signature.
+        signatures.reverse();
+
+        for (one_pass_signature, signature) in one_pass_signatures.iter().zip(signatures.iter()) {
+            // a v3 one-pass signature goes with a v4 signature, and a v6 one-pass signature
+            // with a v6 signature made using the same salt.
+            match (one_pass_signature.version, signature.version) {
+                (3, 4) => {}
+                (6, 6) if one_pass_signature.salt == signature.salt => {}
+                (3, _) | (6, _) => {
+                    return Err(anyhow!(
+                        "one-pass signature packet does not match its signature packet"
+                    ))
+                }
+                (version, _) => {
+                    return Err(anyhow!(
+                        "unsupported one-pass signature version {}",
+                        version
+                    ))
+                }
+            }
+
+            if one_pass_signature.signature_type != signature.signature_type
+                || one_pass_signature.hash_algorithm != signature.hash_algorithm
+                || one_pass_signature.public_key_algorithm != signature.public_key_algorithm
+            {
+                return Err(anyhow!(
+                    "one-pass signature packet does not match its signature packet"
+                ));
+            }
+        }
+
+        Ok(SignedMessage {
+            literal,
+            signatures,
+        })
+    }
+
+    /// Serialize the message: the one-pass signatures, the literal data packet, then the
+    /// signatures in reverse order.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut packets = Vec::new();
+        for (i, signature) in self.signatures.iter().enumerate() {
+            let last = i + 1 == self.signatures.len();
+            packets.push(PgpPacket::OnePassSignaturePacket(
+                OnePassSignaturePacket::new(signature, last)?,
+            ));
+        }
+        packets.push(PgpPacket::LiteralDataPacket(self.literal.clone()));
+        for signature in self.signatures.iter().rev() {
+            packets.push(PgpPacket::SignaturePacket(signature.clone()));
+        }
+
+        write_pgp_packets(&packets)
+    }
+
+    /// The signed data recovered from the literal data packet.
+    pub fn literal_data(&self) -> &[u8] {
+        &self.literal.data
+    }
+
+    /// The signatures, in the order of the one-pass signatures.
+    pub fn signatures(&self) -> &[SignaturePacket] {
+        &self.signatures
+    }
+
+    /// Verify each signature over the literal data, returning the results in the order of the
+    /// one-pass signatures.
+    pub fn verify(&self, key: &PublicKey) -> anyhow::Result<Vec<bool>> {
+        self.signatures
+            .iter()
+            .map(|signature| signature.verify_document(key, &self.literal.data))
+            .collect()
+    }
+}
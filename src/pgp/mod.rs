@@ -1,10 +1,29 @@
-use crate::parsers::parse_pgp_packets;
+use crate::parsers::{parse_ascii_armor_parts_all_consuming, parse_pgp_packets};
+use crate::utils::convert_newlines;
 use anyhow::anyhow;
+use byteorder::{BigEndian, WriteBytesExt};
+use num::BigUint;
+use std::convert::TryInto;
+use std::fmt;
 
+pub mod aead;
+pub mod ecdh;
+pub mod encryption;
+pub mod hash;
 pub mod key;
+pub mod message;
+pub mod s2k;
+pub mod secret_key;
 pub mod signature;
+pub mod symmetric;
 
-use key::PublicKeyPacket;
+use encryption::{
+    IntegrityProtectedDataPacket, PublicKeyEncryptedSessionKeyPacket,
+    SymmetricKeyEncryptedSessionKeyPacket,
+};
+use key::{PublicKeyPacket, UserIdPacket};
+use message::{CompressedDataPacket, LiteralDataPacket, OnePassSignaturePacket};
+use secret_key::SecretKeyPacket;
 use signature::SignaturePacket;
 
 pub use key::PublicKey;
@@ -12,45 +31,102 @@ pub use key::PublicKey;
 const CRC24_INIT: u32 = 0xB704CE;
 const CRC24_POLY: u32 = 0x1864CFB;
 
-pub type AsciiArmorParts = (AsciiArmorKind, String, String);
+/// The number of base64 characters on each line of ascii armor.
+const ASCII_ARMOR_LINE_LENGTH: usize = 64;
+
+/// The tag of a user attribute packet, which starts a component of a key like a user ID does.
+pub const USER_ATTRIBUTE_TAG: u8 = 17;
+
+pub type AsciiArmorParts = (AsciiArmorKind, String, Option<String>);
 
 #[derive(Debug)]
 pub struct AsciiArmor {
     kind: AsciiArmorKind,
     data: Vec<u8>,
-    checksum: Vec<u8>,
+    /// the checksum is optional since RFC 9580.
+    checksum: Option<Vec<u8>>,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum AsciiArmorKind {
     Signature,
     PublicKey,
+    Message,
+    SecretKey,
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum PgpPacket {
     SignaturePacket(SignaturePacket),
     PublicKeyPacket(PublicKeyPacket),
-    // Ignored
-    UserIdPacket,
-    PublicSubkeyPacket,
+    OnePassSignaturePacket(OnePassSignaturePacket),
+    LiteralDataPacket(LiteralDataPacket),
+    CompressedDataPacket(CompressedDataPacket),
+    UserIdPacket(UserIdPacket),
+    PublicSubkeyPacket(PublicKeyPacket),
+    SecretKeyPacket(SecretKeyPacket),
+    SecretSubkeyPacket(SecretKeyPacket),
+    PublicKeyEncryptedSessionKeyPacket(PublicKeyEncryptedSessionKeyPacket),
+    SymmetricKeyEncryptedSessionKeyPacket(SymmetricKeyEncryptedSessionKeyPacket),
+    IntegrityProtectedDataPacket(IntegrityProtectedDataPacket),
+    /// a packet which is not understood, such as a marker, trust, user attribute or padding
+    /// packet. These are kept so that readers can skip them rather than failing.
+    Unknown {
+        tag: u8,
+        body: Vec<u8>,
+    },
 }
 
 #[derive(Debug)]
 pub enum PgpPacketTag {
     Signature,
+    OnePassSignature,
     PublicKey,
+    CompressedData,
+    LiteralData,
     UserId,
     PublicSubkey,
-    Ignored,
+    SecretKey,
+    SecretSubkey,
+    PublicKeyEncryptedSessionKey,
+    SymmetricKeyEncryptedSessionKey,
+    IntegrityProtectedData,
+    Unknown(u8),
 }
 
 impl AsciiArmor {
+    /// Create ascii armor around binary data, computing its checksum.
+    pub fn new(kind: AsciiArmorKind, data: Vec<u8>) -> AsciiArmor {
+        let checksum = crc24(&data).to_be_bytes()[1..].to_vec();
+
+        AsciiArmor {
+            kind,
+            data,
+            checksum: Some(checksum),
+        }
+    }
+
+    /// Parse a complete ascii armor block, verifying its checksum.
+    pub fn parse(input: &str) -> anyhow::Result<AsciiArmor> {
+        let (_, parts) = parse_ascii_armor_parts_all_consuming(input)
+            .map_err(|_| anyhow!("could not parse ascii armor parts"))?;
+
+        let ascii_armor = AsciiArmor::from_parts(parts)?;
+        if !ascii_armor.verify() {
+            return Err(anyhow!(
+                "ascii armor failed to verify: checksum did not match"
+            ));
+        }
+
+        Ok(ascii_armor)
+    }
+
     pub fn from_parts(parts: AsciiArmorParts) -> anyhow::Result<AsciiArmor> {
         let (kind, data, checksum) = parts;
 
         let data = base64::decode(&data)?;
-        let checksum = base64::decode(&checksum)?;
+        let checksum = checksum.map(base64::decode).transpose()?;
 
         Ok(AsciiArmor {
             kind,
@@ -59,16 +135,24 @@ impl AsciiArmor {
         })
     }
 
+    /// Verify the checksum, if there is one.
     pub fn verify(&self) -> bool {
+        let checksum = match &self.checksum {
+            Some(checksum) => checksum,
+            None => return true,
+        };
+        if checksum.len() != 3 {
+            return false;
+        }
+
         let checksum_computed = crc24(self.data.as_slice());
-        let checksum_stored = (self.checksum[0] as u32) << 16
-            | (self.checksum[1] as u32) << 8
-            | (self.checksum[2] as u32);
+        let checksum_stored =
+            (checksum[0] as u32) << 16 | (checksum[1] as u32) << 8 | (checksum[2] as u32);
 
         checksum_computed == checksum_stored
     }
 
-    pub fn into_pgp_packets(&self) -> anyhow::Result<Vec<PgpPacket>> {
+    pub fn to_pgp_packets(&self) -> anyhow::Result<Vec<PgpPacket>> {
         let (_, packets) =
             parse_pgp_packets(&self.data).map_err(|_| anyhow!("could not parse pgp packets"))?;
 
@@ -76,14 +160,165 @@ impl AsciiArmor {
     }
 }
 
+impl fmt::Display for AsciiArmor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.kind.name();
+        writeln!(f, "-----BEGIN PGP {}-----", name)?;
+        writeln!(f)?;
+
+        let data = base64::encode(&self.data);
+        for line in data.as_bytes().chunks(ASCII_ARMOR_LINE_LENGTH) {
+            writeln!(f, "{}", String::from_utf8_lossy(line))?;
+        }
+        if let Some(checksum) = &self.checksum {
+            writeln!(f, "={}", base64::encode(checksum))?;
+        }
+
+        writeln!(f, "-----END PGP {}-----", name)
+    }
+}
+
+impl AsciiArmorKind {
+    /// The name used in the header and footer lines of the ascii armor.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AsciiArmorKind::Signature => "SIGNATURE",
+            AsciiArmorKind::PublicKey => "PUBLIC KEY BLOCK",
+            AsciiArmorKind::Message => "MESSAGE",
+            AsciiArmorKind::SecretKey => "PRIVATE KEY BLOCK",
+        }
+    }
+}
+
+impl PgpPacket {
+    /// Whether the packet is one which is not understood. Outside of keys, these are skipped
+    /// wherever they appear.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, PgpPacket::Unknown { .. })
+    }
+
+    /// The packet tag, as defined by the RFC in section 4.3.
+    fn tag(&self) -> u8 {
+        match self {
+            PgpPacket::PublicKeyEncryptedSessionKeyPacket(_) => 1,
+            PgpPacket::SignaturePacket(_) => 2,
+            PgpPacket::SymmetricKeyEncryptedSessionKeyPacket(_) => 3,
+            PgpPacket::OnePassSignaturePacket(_) => 4,
+            PgpPacket::SecretKeyPacket(_) => 5,
+            PgpPacket::PublicKeyPacket(_) => 6,
+            PgpPacket::SecretSubkeyPacket(_) => 7,
+            PgpPacket::CompressedDataPacket(_) => 8,
+            PgpPacket::LiteralDataPacket(_) => 11,
+            PgpPacket::UserIdPacket(_) => 13,
+            PgpPacket::PublicSubkeyPacket(_) => 14,
+            PgpPacket::IntegrityProtectedDataPacket(_) => 18,
+            PgpPacket::Unknown { tag, .. } => *tag,
+        }
+    }
+
+    /// Serialize the packet body.
+    fn body(&self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            PgpPacket::SignaturePacket(signature) => signature.to_bytes(),
+            PgpPacket::PublicKeyPacket(key) | PgpPacket::PublicSubkeyPacket(key) => {
+                Ok(key.packet_data.clone())
+            }
+            PgpPacket::SecretKeyPacket(key) | PgpPacket::SecretSubkeyPacket(key) => key.to_bytes(),
+            PgpPacket::UserIdPacket(user_id) => Ok(user_id.user_id.as_bytes().to_vec()),
+            PgpPacket::OnePassSignaturePacket(one_pass_signature) => one_pass_signature.to_bytes(),
+            PgpPacket::LiteralDataPacket(literal) => literal.to_bytes(),
+            PgpPacket::CompressedDataPacket(compressed) => Ok(compressed.to_bytes()),
+            PgpPacket::PublicKeyEncryptedSessionKeyPacket(session_key) => session_key.to_bytes(),
+            PgpPacket::SymmetricKeyEncryptedSessionKeyPacket(session_key) => session_key.to_bytes(),
+            PgpPacket::IntegrityProtectedDataPacket(data) => Ok(data.to_bytes()),
+            PgpPacket::Unknown { body, .. } => Ok(body.clone()),
+        }
+    }
+
+    /// Serialize the packet, using a new format header with a full body length, as defined by
+    /// the RFC in section 4.2.2.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let body = self.body()?;
+
+        let mut bytes = vec![0xc0 | self.tag()];
+        match body.len() {
+            length @ 0..=191 => bytes.push(length as u8),
+            length @ 192..=8383 => {
+                let length = length - 192;
+                bytes.push((length >> 8) as u8 + 192);
+                bytes.push(length as u8);
+            }
+            length => {
+                bytes.push(0xff);
+                bytes.write_u32::<BigEndian>(length.try_into()?)?;
+            }
+        }
+        bytes.extend_from_slice(&body);
+
+        Ok(bytes)
+    }
+}
+
+/// Serialize a sequence of packets.
+pub fn write_pgp_packets(packets: &[PgpPacket]) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for packet in packets {
+        bytes.extend_from_slice(&packet.to_bytes()?);
+    }
+
+    Ok(bytes)
+}
+
+/// Serialize a multi-precision integer (MPI) as defined by the RFC in section 3.2.
+pub fn write_mpi(bytes: &mut Vec<u8>, value: &BigUint) {
+    let bits = value.bits() as u16;
+    bytes
+        .write_u16::<BigEndian>(bits)
+        .expect("writing to a vector can't fail");
+    if bits > 0 {
+        bytes.extend_from_slice(&value.to_bytes_be());
+    }
+}
+
+/// Read packets from input which may be either ascii armored (in which case the armor must be of
+/// the expected kind) or binary.
+pub fn read_pgp_packets(input: &[u8], kind: AsciiArmorKind) -> anyhow::Result<Vec<PgpPacket>> {
+    if input.starts_with(b"-----BEGIN PGP ") {
+        let input = convert_newlines(std::str::from_utf8(input)?);
+        let ascii_armor = AsciiArmor::parse(&input)?;
+        if ascii_armor.kind != kind {
+            return Err(anyhow!(
+                "expected ascii armor of kind {:?}, found {:?}",
+                kind,
+                ascii_armor.kind
+            ));
+        }
+
+        ascii_armor.to_pgp_packets()
+    } else {
+        let (_, packets) =
+            parse_pgp_packets(input).map_err(|_| anyhow!("could not parse pgp packets"))?;
+
+        Ok(packets)
+    }
+}
+
 impl From<u8> for PgpPacketTag {
     fn from(val: u8) -> Self {
         match val {
+            1 => PgpPacketTag::PublicKeyEncryptedSessionKey,
             2 => PgpPacketTag::Signature,
+            3 => PgpPacketTag::SymmetricKeyEncryptedSessionKey,
+            4 => PgpPacketTag::OnePassSignature,
+            5 => PgpPacketTag::SecretKey,
             6 => PgpPacketTag::PublicKey,
+            7 => PgpPacketTag::SecretSubkey,
+            8 => PgpPacketTag::CompressedData,
+            11 => PgpPacketTag::LiteralData,
             13 => PgpPacketTag::UserId,
             14 => PgpPacketTag::PublicSubkey,
-            _ => PgpPacketTag::Ignored,
+            18 => PgpPacketTag::IntegrityProtectedData,
+            tag => PgpPacketTag::Unknown(tag),
         }
     }
 }
@@ -0,0 +1,228 @@
+use anyhow::anyhow;
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use super::hash::HashAlgorithm;
+
+/// The coded iteration count used when protecting keys, which hashes 16 MiB of data.
+pub const DEFAULT_S2K_COUNT: u8 = 0xe0;
+
+/// The Argon2 passes, lanes and coded memory size used when protecting keys: the second
+/// recommendation of RFC 9580 section 3.7.1.4, which uses 64 MiB of memory.
+const DEFAULT_ARGON2_PARAMETERS: (u8, u8, u8) = (3, 4, 16);
+
+/// The largest coded Argon2 memory size which is accepted, 2 GiB.
+pub const MAX_ARGON2_ENCODED_M: u8 = 21;
+
+/// The S2K type of GnuPG's extensions, which is in the private or experimental range.
+pub const GNU_S2K_TYPE: u8 = 101;
+
+/// The marker which follows the hash algorithm of a GnuPG S2K extension.
+pub const GNU_S2K_MARKER: &[u8] = b"GNU";
+
+/// The GnuPG S2K extension mode of keys whose secret key material was left out.
+pub const GNU_DUMMY_S2K_MODE: u8 = 1;
+
+/// A string-to-key specifier, which turns a passphrase into a symmetric key, as defined by the
+/// RFC in section 3.7.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringToKey {
+    Simple {
+        hash_algorithm: HashAlgorithm,
+    },
+    Salted {
+        hash_algorithm: HashAlgorithm,
+        salt: [u8; 8],
+    },
+    /// the count is kept in its coded, one octet form.
+    IteratedSalted {
+        hash_algorithm: HashAlgorithm,
+        salt: [u8; 8],
+        count: u8,
+    },
+    /// Argon2id, as defined by RFC 9580 section 3.7.1.4. The memory size is 2^encoded_m KiB.
+    Argon2 {
+        salt: [u8; 16],
+        t: u8,
+        p: u8,
+        encoded_m: u8,
+    },
+}
+
+impl StringToKey {
+    /// Create an iterated and salted specifier with a random salt, which is what should be used
+    /// to protect new keys.
+    pub fn new_iterated_salted(hash_algorithm: HashAlgorithm) -> StringToKey {
+        StringToKey::IteratedSalted {
+            hash_algorithm,
+            salt: rand::random(),
+            count: DEFAULT_S2K_COUNT,
+        }
+    }
+
+    /// Create an Argon2 specifier with a random salt, which is what should be used to protect
+    /// new keys with AEAD.
+    pub fn new_argon2() -> StringToKey {
+        let (t, p, encoded_m) = DEFAULT_ARGON2_PARAMETERS;
+
+        StringToKey::Argon2 {
+            salt: rand::random(),
+            t,
+            p,
+            encoded_m,
+        }
+    }
+
+    /// Derive a key of `key_size` octets from the passphrase. If the hash is too small for the
+    /// key, more hashes are computed, each preloaded with one more zero octet than the last, and
+    /// their results are concatenated. Argon2 produces a key of any size directly.
+    pub fn derive_key(&self, passphrase: &[u8], key_size: usize) -> anyhow::Result<Vec<u8>> {
+        let (hash_algorithm, salt, count) = match self {
+            StringToKey::Simple { hash_algorithm } => (*hash_algorithm, &[][..], 0),
+            StringToKey::Salted {
+                hash_algorithm,
+                salt,
+            } => (*hash_algorithm, &salt[..], 0),
+            StringToKey::IteratedSalted {
+                hash_algorithm,
+                salt,
+                count,
+            } => (*hash_algorithm, &salt[..], decode_count(*count)),
+            StringToKey::Argon2 {
+                salt,
+                t,
+                p,
+                encoded_m,
+            } => return derive_argon2_key(passphrase, salt, *t, *p, *encoded_m, key_size),
+        };
+
+        let mut data = salt.to_vec();
+        data.extend_from_slice(passphrase);
+
+        // the salt and passphrase are repeated until `count` octets have been hashed, but they
+        // are always hashed at least once.
+        let count = count.max(data.len());
+        // hashing the repetitions in larger chunks is much faster than one at a time.
+        let data = data.repeat(1 + 4096 / data.len().max(1));
+
+        let mut key = Vec::new();
+        let mut preload = 0;
+        while key.len() < key_size {
+            let mut hasher = hash_algorithm.hasher();
+            hasher.update(&vec![0; preload]);
+
+            let mut remaining = count;
+            while remaining > 0 {
+                let chunk = remaining.min(data.len());
+                hasher.update(&data[..chunk]);
+                remaining -= chunk;
+            }
+
+            key.extend_from_slice(&hasher.finalize());
+            preload += 1;
+        }
+        key.truncate(key_size);
+
+        Ok(key)
+    }
+
+    /// Check that a key can be derived with the specifier's parameters, without deriving it.
+    pub fn check_parameters(&self) -> anyhow::Result<()> {
+        match self {
+            StringToKey::Argon2 { p, encoded_m, .. } => check_argon2_memory_size(*p, *encoded_m),
+            _ => Ok(()),
+        }
+    }
+
+    /// Serialize the specifier, as it appears in secret key and symmetric-key encrypted session
+    /// key packets.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            StringToKey::Simple { hash_algorithm } => vec![0, hash_algorithm.id()],
+            StringToKey::Salted {
+                hash_algorithm,
+                salt,
+            } => {
+                let mut bytes = vec![1, hash_algorithm.id()];
+                bytes.extend_from_slice(salt);
+                bytes
+            }
+            StringToKey::IteratedSalted {
+                hash_algorithm,
+                salt,
+                count,
+            } => {
+                let mut bytes = vec![3, hash_algorithm.id()];
+                bytes.extend_from_slice(salt);
+                bytes.push(*count);
+                bytes
+            }
+            StringToKey::Argon2 {
+                salt,
+                t,
+                p,
+                encoded_m,
+            } => {
+                let mut bytes = vec![4];
+                bytes.extend_from_slice(salt);
+                bytes.extend_from_slice(&[*t, *p, *encoded_m]);
+                bytes
+            }
+        }
+    }
+}
+
+/// Derive a key with Argon2id version 0x13, without a secret or associated data.
+fn derive_argon2_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    t: u8,
+    p: u8,
+    encoded_m: u8,
+    key_size: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let invalid = |_| {
+        anyhow!(
+            "invalid Argon2 parameters t={} p={} m=2^{} KiB",
+            t,
+            p,
+            encoded_m
+        )
+    };
+    check_argon2_memory_size(p, encoded_m)?;
+    let params =
+        Params::new(1 << encoded_m, t.into(), p.into(), Some(key_size)).map_err(invalid)?;
+
+    let mut key = vec![0; key_size];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, salt, &mut key)
+        .map_err(invalid)?;
+
+    Ok(key)
+}
+
+/// Check the coded Argon2 memory size. It comes from the message or key, so it is limited to
+/// avoid running out of memory. RFC 9580 section 3.7.1.4 requires at least 8 KiB for each lane.
+fn check_argon2_memory_size(p: u8, encoded_m: u8) -> anyhow::Result<()> {
+    if encoded_m > MAX_ARGON2_ENCODED_M {
+        return Err(anyhow!(
+            "Argon2 memory size 2^{} KiB is too large, at most 2^{} KiB is allowed",
+            encoded_m,
+            MAX_ARGON2_ENCODED_M
+        ));
+    }
+    let lanes_log2 = u32::from(p).next_power_of_two().trailing_zeros();
+    if u32::from(encoded_m) < 3 + lanes_log2 {
+        return Err(anyhow!(
+            "Argon2 memory size 2^{} KiB is too small for {} lanes",
+            encoded_m,
+            p
+        ));
+    }
+
+    Ok(())
+}
+
+/// Decode the one octet iteration count into the number of octets to hash.
+fn decode_count(count: u8) -> usize {
+    (16 + (count as usize & 15)) << ((count >> 4) + 6)
+}
@@ -0,0 +1,972 @@
+use anyhow::anyhow;
+use byteorder::{BigEndian, WriteBytesExt};
+use ed25519_dalek::{Signer, SigningKey};
+use hkdf::Hkdf;
+use num::BigUint;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::convert::TryInto;
+use std::fmt;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use super::aead::AeadAlgorithm;
+use super::ecdh::{ecdh_decrypt, x25519_decrypt};
+use super::encryption::EncryptedSessionKey;
+use super::hash::HashAlgorithm;
+use super::key::{
+    allows_signing, certification_data, subkey_binding_data, Curve, PublicKey, PublicKeyAlgorithm,
+    PublicKeyMaterial, PublicKeyPacket, Subkey, UserId, UserIdPacket, KEY_FLAG_CERTIFY,
+    KEY_FLAG_ENCRYPT_COMMUNICATIONS, KEY_FLAG_ENCRYPT_STORAGE, KEY_FLAG_SIGN, NATIVE_POINT_PREFIX,
+};
+use super::s2k::{StringToKey, GNU_DUMMY_S2K_MODE, GNU_S2K_MARKER, GNU_S2K_TYPE};
+use super::signature::{
+    SignaturePacket, SignatureSubPacket, SubPacket, SIGNATURE_TYPE_BINARY,
+    SIGNATURE_TYPE_POSITIVE_CERTIFICATION, SIGNATURE_TYPE_SUBKEY_BINDING, SIGNATURE_TYPE_TEXT,
+};
+use super::symmetric::SymmetricAlgorithm;
+use super::{
+    read_pgp_packets, write_mpi, write_pgp_packets, AsciiArmor, AsciiArmorKind, PgpPacket,
+    USER_ATTRIBUTE_TAG,
+};
+use crate::parsers::parse_secret_key_material_all_consuming;
+use crate::rsa::{decrypt_pkcs1, sign_pkcs1, RsaPrivateKey};
+use crate::utils::{canonicalize_line_endings, random_bytes, sum16, unix_time_now};
+
+/// The RSA key sizes which can be generated.
+pub const RSA_KEY_SIZES: [u64; 3] = [2048, 3072, 4096];
+
+/// The symmetric algorithm used to protect secret keys with a passphrase.
+const PROTECTION_SYMMETRIC_ALGORITHM: SymmetricAlgorithm = SymmetricAlgorithm::Aes256;
+
+/// The packet tags of secret key and secret subkey packets, which AEAD protection binds the key
+/// material to.
+const SECRET_KEY_PACKET_TAG: u8 = 5;
+const SECRET_SUBKEY_PACKET_TAG: u8 = 7;
+
+/// The hash algorithm used for self-signatures and for deriving the protection key.
+const DEFAULT_HASH_ALGORITHM: HashAlgorithm = HashAlgorithm::Sha256;
+
+/// The kinds of key which can be generated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyType {
+    /// an RSA primary key of the given size in bits, which can certify and sign.
+    Rsa(u64),
+    /// an Ed25519 primary key, which can certify and sign, with a Curve25519 encryption subkey.
+    Ed25519,
+}
+
+/// A secret key: the primary key, along with its user IDs and subkeys, as described by the RFC in
+/// section 11.2.
+#[derive(Debug)]
+pub struct SecretKey {
+    pub primary_key: SecretKeyPacket,
+    pub user_ids: Vec<UserId>,
+    pub subkeys: Vec<SecretSubkey>,
+}
+
+/// A secret subkey, along with the signatures which follow it.
+#[derive(Debug)]
+pub struct SecretSubkey {
+    pub key: SecretKeyPacket,
+    pub signatures: Vec<SignaturePacket>,
+}
+
+/// The algorithm specific secret part of a key, as defined by the RFC in section 5.5.3.
+#[derive(Debug, Clone)]
+pub enum SecretKeyMaterial {
+    /// p is the smaller prime, and u is the inverse of p mod q.
+    Rsa {
+        d: BigUint,
+        p: BigUint,
+        q: BigUint,
+        u: BigUint,
+    },
+    /// the 32 octet secret seed of an Ed25519 key.
+    EdDsa { seed: Vec<u8> },
+    /// the secret scalar of an ECDH key. For Curve25519 it is clamped, and stored in big-endian
+    /// order, the reverse of its native encoding.
+    Ecdh { secret: Vec<u8> },
+    /// the 32 octet secret of a native Ed25519 key, as defined by RFC 9580 section 5.5.5.10.
+    Ed25519 { secret: Vec<u8> },
+    /// the 32 octet secret of a native X25519 key, as defined by RFC 9580 section 5.5.5.8.
+    X25519 { secret: Vec<u8> },
+    /// the material of keys whose algorithm isn't supported, kept as is so that the key can still
+    /// be written out.
+    Unknown { data: Vec<u8> },
+}
+
+/// How the secret key material is stored in the packet, as indicated by its S2K usage octet.
+#[derive(Debug, Clone)]
+pub enum SecretKeyProtection {
+    /// S2K usage 0: the key material is stored in the clear, followed by a two octet checksum.
+    Unprotected,
+    /// S2K usage 254 or 255: the key material and its checksum are encrypted in CFB mode, with a
+    /// key derived from a passphrase.
+    Cfb {
+        symmetric_algorithm: SymmetricAlgorithm,
+        s2k: StringToKey,
+        iv: Vec<u8>,
+        encrypted_data: Vec<u8>,
+        checksum: SecretKeyChecksum,
+    },
+    /// S2K usage 253: the key material is encrypted and authenticated with an AEAD algorithm,
+    /// with a key derived from a passphrase, as described by RFC 9580 section 5.5.3. There is no
+    /// checksum, since the tag already detects a wrong passphrase.
+    Aead {
+        symmetric_algorithm: SymmetricAlgorithm,
+        aead_algorithm: AeadAlgorithm,
+        s2k: StringToKey,
+        nonce: Vec<u8>,
+        encrypted_data: Vec<u8>,
+    },
+    /// S2K usage 254 or 255 with the GNU dummy S2K: the packet holds no secret key material at
+    /// all, as in keys exported by `gpg --export-secret-subkeys`. The algorithm octets are only
+    /// kept to write the packet back.
+    GnuDummy {
+        s2k_usage: u8,
+        symmetric_algorithm: u8,
+        hash_algorithm: u8,
+    },
+}
+
+/// The checksum which follows encrypted key material, and which detects a wrong passphrase.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecretKeyChecksum {
+    /// S2K usage 254: the SHA-1 hash of the key material.
+    Sha1,
+    /// S2K usage 255: the deprecated two octet sum of the key material.
+    Sum16,
+}
+
+/// A secret key or secret subkey packet, as defined by the RFC in section 5.5.3.
+#[derive(Debug, Clone)]
+pub struct SecretKeyPacket {
+    pub public_key: PublicKeyPacket,
+    pub protection: SecretKeyProtection,
+    /// the secret key material, which is `None` while the key is protected and locked.
+    pub secret_key_material: Option<SecretKeyMaterial>,
+}
+
+impl SecretKey {
+    /// Parse a secret key, which may be either ascii armored or binary.
+    pub fn parse(input: &[u8]) -> anyhow::Result<SecretKey> {
+        // packets which are not understood, such as a marker packet, may precede the key.
+        let mut packets = read_pgp_packets(input, AsciiArmorKind::SecretKey)?
+            .into_iter()
+            .skip_while(|packet| matches!(packet, PgpPacket::Unknown { .. }));
+
+        let primary_key = match packets.next() {
+            Some(PgpPacket::SecretKeyPacket(p)) => p,
+            _ => return Err(anyhow!("first packet was not a secret key packet")),
+        };
+
+        // each user ID and subkey is followed by its signatures, as described by the RFC in
+        // section 11.2. Signatures directly on the primary key or on a user attribute are
+        // skipped, as are other packets which are not understood, such as trust packets.
+        enum Component {
+            PrimaryKey,
+            UserId,
+            Subkey,
+            UserAttribute,
+        }
+        let mut last = Component::PrimaryKey;
+        let mut user_ids: Vec<UserId> = Vec::new();
+        let mut subkeys: Vec<SecretSubkey> = Vec::new();
+        for packet in packets {
+            match packet {
+                PgpPacket::UserIdPacket(packet) => {
+                    user_ids.push(UserId {
+                        user_id: packet.user_id,
+                        signatures: Vec::new(),
+                    });
+                    last = Component::UserId;
+                }
+                PgpPacket::SecretSubkeyPacket(key) => {
+                    subkeys.push(SecretSubkey {
+                        key,
+                        signatures: Vec::new(),
+                    });
+                    last = Component::Subkey;
+                }
+                PgpPacket::Unknown {
+                    tag: USER_ATTRIBUTE_TAG,
+                    ..
+                } => last = Component::UserAttribute,
+                PgpPacket::SignaturePacket(signature) => match last {
+                    Component::PrimaryKey | Component::UserAttribute => {}
+                    Component::UserId => user_ids
+                        .last_mut()
+                        .expect("a user id was seen")
+                        .signatures
+                        .push(signature),
+                    Component::Subkey => subkeys
+                        .last_mut()
+                        .expect("a subkey was seen")
+                        .signatures
+                        .push(signature),
+                },
+                _ => {}
+            }
+        }
+
+        Ok(SecretKey {
+            primary_key,
+            user_ids,
+            subkeys,
+        })
+    }
+
+    /// Generate a key of the given type, and self-certify the user ID with it.
+    pub fn generate(key_type: KeyType, user_id: &str) -> anyhow::Result<SecretKey> {
+        match key_type {
+            KeyType::Rsa(bits) => SecretKey::generate_rsa(bits, user_id),
+            KeyType::Ed25519 => SecretKey::generate_ed25519(user_id),
+        }
+    }
+
+    /// Generate an RSA primary key of `bits` bits, which can certify and sign, and self-certify
+    /// the user ID with it.
+    pub fn generate_rsa(bits: u64, user_id: &str) -> anyhow::Result<SecretKey> {
+        if !RSA_KEY_SIZES.contains(&bits) {
+            return Err(anyhow!(
+                "unsupported RSA key size {}, expected one of {:?}",
+                bits,
+                RSA_KEY_SIZES
+            ));
+        }
+
+        let rsa = RsaPrivateKey::generate(bits);
+        let public_key = PublicKeyPacket::new(
+            unix_time_now().try_into()?,
+            PublicKeyAlgorithm::Rsa,
+            PublicKeyMaterial::Rsa { n: rsa.n, e: rsa.e },
+        )?;
+        let primary_key = SecretKeyPacket::new(
+            public_key,
+            SecretKeyMaterial::Rsa {
+                d: rsa.d,
+                p: rsa.p,
+                q: rsa.q,
+                u: rsa.u,
+            },
+        );
+
+        let mut secret_key = SecretKey {
+            primary_key,
+            user_ids: Vec::new(),
+            subkeys: Vec::new(),
+        };
+        secret_key.add_user_id(user_id, KEY_FLAG_CERTIFY | KEY_FLAG_SIGN)?;
+
+        Ok(secret_key)
+    }
+
+    /// Generate an Ed25519 primary key, which can certify and sign, and self-certify the user ID
+    /// with it. A Curve25519 ECDH subkey is added for encryption. This is the shape of key
+    /// created by default by modern versions of GnuPG.
+    pub fn generate_ed25519(user_id: &str) -> anyhow::Result<SecretKey> {
+        let creation_time = unix_time_now().try_into()?;
+
+        let seed: [u8; 32] = rand::random();
+        let public_key = PublicKeyPacket::new(
+            creation_time,
+            PublicKeyAlgorithm::EdDsa,
+            PublicKeyMaterial::EdDsa {
+                curve: Curve::Ed25519,
+                public_key: SigningKey::from_bytes(&seed)
+                    .verifying_key()
+                    .to_bytes()
+                    .to_vec(),
+            },
+        )?;
+        let primary_key = SecretKeyPacket::new(
+            public_key,
+            SecretKeyMaterial::EdDsa {
+                seed: seed.to_vec(),
+            },
+        );
+
+        let mut secret_key = SecretKey {
+            primary_key,
+            user_ids: Vec::new(),
+            subkeys: Vec::new(),
+        };
+        secret_key.add_user_id(user_id, KEY_FLAG_CERTIFY | KEY_FLAG_SIGN)?;
+
+        // the scalar is clamped as described by RFC 7748 section 5.
+        let mut scalar: [u8; 32] = rand::random();
+        scalar[0] &= 0xf8;
+        scalar[31] &= 0x7f;
+        scalar[31] |= 0x40;
+        let mut point = vec![NATIVE_POINT_PREFIX];
+        point.extend_from_slice(X25519PublicKey::from(&StaticSecret::from(scalar)).as_bytes());
+        scalar.reverse();
+
+        let subkey = PublicKeyPacket::new(
+            creation_time,
+            PublicKeyAlgorithm::Ecdh,
+            PublicKeyMaterial::Ecdh {
+                curve: Curve::Cv25519,
+                point,
+                kdf_hash_algorithm: HashAlgorithm::Sha256.id(),
+                kdf_symmetric_algorithm: SymmetricAlgorithm::Aes128.id(),
+            },
+        )?;
+        secret_key.add_subkey(
+            SecretKeyPacket::new(
+                subkey,
+                SecretKeyMaterial::Ecdh {
+                    secret: scalar.to_vec(),
+                },
+            ),
+            KEY_FLAG_ENCRYPT_COMMUNICATIONS | KEY_FLAG_ENCRYPT_STORAGE,
+        )?;
+
+        Ok(secret_key)
+    }
+
+    /// Add a user ID, self-certified with the primary key. The certification also carries the
+    /// key flags of the primary key, and the algorithm preferences and features of the key.
+    fn add_user_id(&mut self, user_id: &str, key_flags: u8) -> anyhow::Result<()> {
+        let hashed_subpackets = vec![
+            SubPacket::new(SignatureSubPacket::KeyFlags(vec![key_flags])),
+            SubPacket::new(SignatureSubPacket::PreferredSymmetricAlgorithms(vec![
+                SymmetricAlgorithm::Aes256.id(),
+                SymmetricAlgorithm::Aes192.id(),
+                SymmetricAlgorithm::Aes128.id(),
+            ])),
+            SubPacket::new(SignatureSubPacket::PreferredHashAlgorithms(vec![
+                HashAlgorithm::Sha512.id(),
+                HashAlgorithm::Sha384.id(),
+                HashAlgorithm::Sha256.id(),
+            ])),
+            // ZLIB, ZIP and uncompressed.
+            SubPacket::new(SignatureSubPacket::PreferredCompressionAlgorithms(vec![
+                2, 1, 0,
+            ])),
+            // the key supports modification detection (SEIPD v1).
+            SubPacket::new(SignatureSubPacket::Features(vec![0x01])),
+            SubPacket::new(SignatureSubPacket::PrimaryUserId(self.user_ids.is_empty())),
+        ];
+
+        let data = certification_data(&self.primary_key.public_key, user_id)?;
+        let certification = SignaturePacket::create(
+            &self.primary_key,
+            SIGNATURE_TYPE_POSITIVE_CERTIFICATION,
+            DEFAULT_HASH_ALGORITHM,
+            hashed_subpackets,
+            &data,
+        )?;
+
+        self.user_ids.push(UserId {
+            user_id: user_id.to_owned(),
+            signatures: vec![certification],
+        });
+
+        Ok(())
+    }
+
+    /// Add a subkey, bound to the primary key by a subkey binding signature carrying its key
+    /// flags. Subkeys which can sign would also need a primary key binding signature, so only
+    /// encryption subkeys are supported.
+    fn add_subkey(&mut self, key: SecretKeyPacket, key_flags: u8) -> anyhow::Result<()> {
+        if key_flags & KEY_FLAG_SIGN != 0 {
+            return Err(anyhow!("signing subkeys are not supported"));
+        }
+
+        let data = subkey_binding_data(&self.primary_key.public_key, &key.public_key);
+        let binding = SignaturePacket::create(
+            &self.primary_key,
+            SIGNATURE_TYPE_SUBKEY_BINDING,
+            DEFAULT_HASH_ALGORITHM,
+            vec![SubPacket::new(SignatureSubPacket::KeyFlags(vec![
+                key_flags,
+            ]))],
+            &data,
+        )?;
+
+        self.subkeys.push(SecretSubkey {
+            key,
+            signatures: vec![binding],
+        });
+
+        Ok(())
+    }
+
+    /// Unlock the primary key and all subkeys with a passphrase.
+    pub fn unlock(&mut self, passphrase: &str) -> anyhow::Result<()> {
+        self.primary_key.unlock(passphrase, SECRET_KEY_PACKET_TAG)?;
+        for subkey in self.subkeys.iter_mut() {
+            subkey.key.unlock(passphrase, SECRET_SUBKEY_PACKET_TAG)?;
+        }
+
+        Ok(())
+    }
+
+    /// Protect the primary key and all subkeys with a passphrase.
+    pub fn protect(&mut self, passphrase: &str) -> anyhow::Result<()> {
+        self.primary_key.protect(passphrase)?;
+        for subkey in self.subkeys.iter_mut() {
+            subkey.key.protect(passphrase)?;
+        }
+
+        Ok(())
+    }
+
+    /// Protect the primary key and all subkeys with a passphrase, using AEAD.
+    pub fn protect_aead(
+        &mut self,
+        passphrase: &str,
+        aead_algorithm: AeadAlgorithm,
+    ) -> anyhow::Result<()> {
+        self.primary_key
+            .protect_aead(passphrase, aead_algorithm, SECRET_KEY_PACKET_TAG)?;
+        for subkey in self.subkeys.iter_mut() {
+            subkey
+                .key
+                .protect_aead(passphrase, aead_algorithm, SECRET_SUBKEY_PACKET_TAG)?;
+        }
+
+        Ok(())
+    }
+
+    /// The primary key, followed by the subkeys.
+    pub fn key_packets(&self) -> impl Iterator<Item = &SecretKeyPacket> {
+        std::iter::once(&self.primary_key).chain(self.subkeys.iter().map(|subkey| &subkey.key))
+    }
+
+    /// The fingerprint of the primary key.
+    pub fn fingerprint(&self) -> Vec<u8> {
+        self.primary_key.public_key.fingerprint()
+    }
+
+    /// The certificate of the key, without any of the secret parts.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            primary_key: self.primary_key.public_key.clone(),
+            user_ids: self.user_ids.clone(),
+            subkeys: self
+                .subkeys
+                .iter()
+                .map(|subkey| Subkey {
+                    key: subkey.key.public_key.clone(),
+                    signatures: subkey.signatures.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// The key used to make signatures: the primary key if a self-certification allows it to
+    /// sign, otherwise the first subkey which is bound as a signing key. Keys without secret key
+    /// material are passed over.
+    pub fn signing_key(&self) -> anyhow::Result<&SecretKeyPacket> {
+        let public_key = self.public_key();
+
+        if self.primary_key.has_secret_material()
+            && public_key
+                .verified_user_ids()
+                .any(|(_, certification)| allows_signing(certification))
+        {
+            return Ok(&self.primary_key);
+        }
+
+        public_key
+            .subkeys
+            .iter()
+            .zip(self.subkeys.iter())
+            .find(|(subkey, secret_subkey)| {
+                secret_subkey.key.has_secret_material()
+                    && subkey
+                        .verify_binding(&public_key.primary_key)
+                        .is_ok_and(allows_signing)
+            })
+            .map(|(_, secret_subkey)| &secret_subkey.key)
+            .ok_or_else(|| anyhow!("secret key has no key which can sign"))
+    }
+
+    /// Sign a document: as a binary document signature, or in text mode as a text document
+    /// signature over the document with its line endings canonicalized.
+    pub fn sign_document(&self, text: bool, data: &[u8]) -> anyhow::Result<SignaturePacket> {
+        if text {
+            self.sign(SIGNATURE_TYPE_TEXT, &canonicalize_line_endings(data))
+        } else {
+            self.sign(SIGNATURE_TYPE_BINARY, data)
+        }
+    }
+
+    /// Sign `data` with the signing key, as a signature of the given type.
+    pub fn sign(&self, signature_type: u8, data: &[u8]) -> anyhow::Result<SignaturePacket> {
+        SignaturePacket::create(
+            self.signing_key()?,
+            signature_type,
+            DEFAULT_HASH_ALGORITHM,
+            Vec::new(),
+            data,
+        )
+    }
+
+    /// Export the public parts of the key, as an ascii armored public key block.
+    pub fn public_key_armor(&self) -> anyhow::Result<AsciiArmor> {
+        let packets = self.packets(
+            |key| PgpPacket::PublicKeyPacket(key.public_key.clone()),
+            |key| PgpPacket::PublicSubkeyPacket(key.public_key.clone()),
+        );
+
+        Ok(AsciiArmor::new(
+            AsciiArmorKind::PublicKey,
+            write_pgp_packets(&packets)?,
+        ))
+    }
+
+    /// Export the key, as an ascii armored private key block.
+    pub fn secret_key_armor(&self) -> anyhow::Result<AsciiArmor> {
+        let packets = self.packets(
+            |key| PgpPacket::SecretKeyPacket(key.clone()),
+            |key| PgpPacket::SecretSubkeyPacket(key.clone()),
+        );
+
+        Ok(AsciiArmor::new(
+            AsciiArmorKind::SecretKey,
+            write_pgp_packets(&packets)?,
+        ))
+    }
+
+    /// The packets of the key in the order described by the RFC in section 11.1, with the
+    /// primary key and subkeys turned into packets by the given functions.
+    fn packets(
+        &self,
+        primary_key_packet: impl Fn(&SecretKeyPacket) -> PgpPacket,
+        subkey_packet: impl Fn(&SecretKeyPacket) -> PgpPacket,
+    ) -> Vec<PgpPacket> {
+        let mut packets = vec![primary_key_packet(&self.primary_key)];
+        for user_id in self.user_ids.iter() {
+            packets.push(PgpPacket::UserIdPacket(UserIdPacket {
+                user_id: user_id.user_id.clone(),
+            }));
+            for signature in user_id.signatures.iter() {
+                packets.push(PgpPacket::SignaturePacket(signature.clone()));
+            }
+        }
+        for subkey in self.subkeys.iter() {
+            packets.push(subkey_packet(&subkey.key));
+            for signature in subkey.signatures.iter() {
+                packets.push(PgpPacket::SignaturePacket(signature.clone()));
+            }
+        }
+
+        packets
+    }
+}
+
+impl SecretKeyPacket {
+    /// Create an unprotected secret key packet.
+    pub fn new(public_key: PublicKeyPacket, secret_key_material: SecretKeyMaterial) -> Self {
+        SecretKeyPacket {
+            public_key,
+            protection: SecretKeyProtection::Unprotected,
+            secret_key_material: Some(secret_key_material),
+        }
+    }
+
+    /// Protect the secret key material with a passphrase, using an iterated and salted S2K and
+    /// a SHA-1 checksum, as described by the RFC in section 5.5.3.
+    pub fn protect(&mut self, passphrase: &str) -> anyhow::Result<()> {
+        let material = self.unlocked_material()?;
+
+        let mut data = material.to_bytes();
+        let checksum = Sha1::digest(&data);
+        data.extend_from_slice(&checksum);
+
+        let symmetric_algorithm = PROTECTION_SYMMETRIC_ALGORITHM;
+        let s2k = StringToKey::new_iterated_salted(DEFAULT_HASH_ALGORITHM);
+        let key = s2k.derive_key(passphrase.as_bytes(), symmetric_algorithm.key_size())?;
+        let iv = random_bytes(symmetric_algorithm.block_size());
+        symmetric_algorithm.cfb_encrypt(&key, &iv, &mut data)?;
+
+        self.protection = SecretKeyProtection::Cfb {
+            symmetric_algorithm,
+            s2k,
+            iv,
+            encrypted_data: data,
+            checksum: SecretKeyChecksum::Sha1,
+        };
+
+        Ok(())
+    }
+
+    /// Protect the secret key material with a passphrase, using an Argon2 S2K and the given AEAD
+    /// algorithm, as described by RFC 9580 section 5.5.3. `packet_tag` is the tag of the packet
+    /// the key is written in, which is authenticated along with the public key.
+    pub fn protect_aead(
+        &mut self,
+        passphrase: &str,
+        aead_algorithm: AeadAlgorithm,
+        packet_tag: u8,
+    ) -> anyhow::Result<()> {
+        let data = self.unlocked_material()?.to_bytes();
+
+        let symmetric_algorithm = PROTECTION_SYMMETRIC_ALGORITHM;
+        let s2k = StringToKey::new_argon2();
+        let nonce = random_bytes(aead_algorithm.nonce_size());
+        let key = self.aead_key(
+            &s2k,
+            passphrase,
+            symmetric_algorithm,
+            aead_algorithm,
+            packet_tag,
+        )?;
+        let encrypted_data = aead_algorithm.encrypt(
+            symmetric_algorithm,
+            &key,
+            &nonce,
+            &self.aead_associated_data(packet_tag),
+            &data,
+        )?;
+
+        self.protection = SecretKeyProtection::Aead {
+            symmetric_algorithm,
+            aead_algorithm,
+            s2k,
+            nonce,
+            encrypted_data,
+        };
+
+        Ok(())
+    }
+
+    /// Decrypt the secret key material with a passphrase, checking its checksum or tag.
+    /// `packet_tag` is the tag of the packet the key was read from. The key stays protected when
+    /// it is written out. Unprotected keys don't need unlocking, and keys without secret key
+    /// material can't be unlocked.
+    pub fn unlock(&mut self, passphrase: &str, packet_tag: u8) -> anyhow::Result<()> {
+        let (symmetric_algorithm, s2k, iv, encrypted_data, checksum) = match &self.protection {
+            SecretKeyProtection::Unprotected | SecretKeyProtection::GnuDummy { .. } => {
+                return Ok(())
+            }
+            SecretKeyProtection::Cfb {
+                symmetric_algorithm,
+                s2k,
+                iv,
+                encrypted_data,
+                checksum,
+            } => (symmetric_algorithm, s2k, iv, encrypted_data, checksum),
+            SecretKeyProtection::Aead {
+                symmetric_algorithm,
+                aead_algorithm,
+                s2k,
+                nonce,
+                encrypted_data,
+            } => {
+                let key = self.aead_key(
+                    s2k,
+                    passphrase,
+                    *symmetric_algorithm,
+                    *aead_algorithm,
+                    packet_tag,
+                )?;
+                let data = aead_algorithm
+                    .decrypt(
+                        *symmetric_algorithm,
+                        &key,
+                        nonce,
+                        &self.aead_associated_data(packet_tag),
+                        encrypted_data,
+                    )
+                    .map_err(|_| {
+                        anyhow!("could not unlock the secret key: the passphrase is wrong")
+                    })?;
+
+                return self.set_material(&data);
+            }
+        };
+
+        let key = s2k.derive_key(passphrase.as_bytes(), symmetric_algorithm.key_size())?;
+        let mut data = encrypted_data.clone();
+        symmetric_algorithm.cfb_decrypt(&key, iv, &mut data)?;
+
+        let checksum_size = match checksum {
+            SecretKeyChecksum::Sha1 => 20,
+            SecretKeyChecksum::Sum16 => 2,
+        };
+        if data.len() < checksum_size {
+            return Err(anyhow!("encrypted secret key material is too short"));
+        }
+        let (material_data, stored_checksum) = data.split_at(data.len() - checksum_size);
+        let valid = match checksum {
+            SecretKeyChecksum::Sha1 => Sha1::digest(material_data).as_slice() == stored_checksum,
+            SecretKeyChecksum::Sum16 => sum16(material_data).to_be_bytes() == stored_checksum,
+        };
+        if !valid {
+            return Err(anyhow!(
+                "could not unlock the secret key: the passphrase is wrong"
+            ));
+        }
+
+        self.set_material(material_data)
+    }
+
+    /// Parse decrypted key material, which unlocks the key.
+    fn set_material(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let (_, material) =
+            parse_secret_key_material_all_consuming(data, &self.public_key.key_material)
+                .map_err(|_| anyhow!("could not parse the decrypted secret key material"))?;
+        self.secret_key_material = Some(material);
+
+        Ok(())
+    }
+
+    /// The key which encrypts AEAD protected key material: the S2K output, expanded with
+    /// HKDF-SHA256 using the packet header, the key version and the algorithms as info.
+    fn aead_key(
+        &self,
+        s2k: &StringToKey,
+        passphrase: &str,
+        symmetric_algorithm: SymmetricAlgorithm,
+        aead_algorithm: AeadAlgorithm,
+        packet_tag: u8,
+    ) -> anyhow::Result<Vec<u8>> {
+        let key_size = symmetric_algorithm.key_size();
+        let s2k_key = s2k.derive_key(passphrase.as_bytes(), key_size)?;
+        let info = [
+            0xc0 | packet_tag,
+            self.public_key.version,
+            symmetric_algorithm.id(),
+            aead_algorithm.id(),
+        ];
+
+        let mut key = vec![0; key_size];
+        Hkdf::<Sha256>::new(None, &s2k_key)
+            .expand(&info, &mut key)
+            .map_err(|_| anyhow!("invalid HKDF output size {}", key_size))?;
+
+        Ok(key)
+    }
+
+    /// The data authenticated along with AEAD protected key material: the packet header,
+    /// followed by the public key.
+    fn aead_associated_data(&self, packet_tag: u8) -> Vec<u8> {
+        let mut data = vec![0xc0 | packet_tag];
+        data.extend_from_slice(&self.public_key.packet_data);
+
+        data
+    }
+
+    /// Serialize the secret key packet body: the public key, followed by the (possibly
+    /// encrypted) secret key material.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = self.public_key.packet_data.clone();
+
+        match &self.protection {
+            SecretKeyProtection::Unprotected => {
+                let data = self.unlocked_material()?.to_bytes();
+
+                bytes.push(0);
+                bytes.extend_from_slice(&data);
+                // v6 keys leave out the checksum, as described by RFC 9580 section 5.5.3.
+                if self.public_key.version != 6 {
+                    bytes.write_u16::<BigEndian>(sum16(&data))?;
+                }
+            }
+            SecretKeyProtection::Cfb {
+                symmetric_algorithm,
+                s2k,
+                iv,
+                encrypted_data,
+                checksum,
+            } => {
+                bytes.push(match checksum {
+                    SecretKeyChecksum::Sha1 => 254,
+                    SecretKeyChecksum::Sum16 => 255,
+                });
+
+                let s2k = s2k.to_bytes();
+                // v6 keys give the length of the parameters, and of the S2K specifier.
+                if self.public_key.version == 6 {
+                    bytes.push((2 + s2k.len() + iv.len()).try_into()?);
+                    bytes.push(symmetric_algorithm.id());
+                    bytes.push(s2k.len().try_into()?);
+                } else {
+                    bytes.push(symmetric_algorithm.id());
+                }
+                bytes.extend_from_slice(&s2k);
+                bytes.extend_from_slice(iv);
+                bytes.extend_from_slice(encrypted_data);
+            }
+            SecretKeyProtection::Aead {
+                symmetric_algorithm,
+                aead_algorithm,
+                s2k,
+                nonce,
+                encrypted_data,
+            } => {
+                bytes.push(253);
+
+                let s2k = s2k.to_bytes();
+                if self.public_key.version == 6 {
+                    bytes.push((3 + s2k.len() + nonce.len()).try_into()?);
+                    bytes.push(symmetric_algorithm.id());
+                    bytes.push(aead_algorithm.id());
+                    bytes.push(s2k.len().try_into()?);
+                } else {
+                    bytes.push(symmetric_algorithm.id());
+                    bytes.push(aead_algorithm.id());
+                }
+                bytes.extend_from_slice(&s2k);
+                bytes.extend_from_slice(nonce);
+                bytes.extend_from_slice(encrypted_data);
+            }
+            SecretKeyProtection::GnuDummy {
+                s2k_usage,
+                symmetric_algorithm,
+                hash_algorithm,
+            } => {
+                bytes.extend_from_slice(&[
+                    *s2k_usage,
+                    *symmetric_algorithm,
+                    GNU_S2K_TYPE,
+                    *hash_algorithm,
+                ]);
+                bytes.extend_from_slice(GNU_S2K_MARKER);
+                bytes.push(GNU_DUMMY_S2K_MODE);
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Sign a hash computed with `hash_algorithm`, returning the algorithm specific signature
+    /// values.
+    pub fn sign(&self, hash_algorithm: HashAlgorithm, hash: &[u8]) -> anyhow::Result<Vec<BigUint>> {
+        match (&self.public_key.key_material, self.unlocked_material()?) {
+            (PublicKeyMaterial::Rsa { n, .. }, SecretKeyMaterial::Rsa { d, .. }) => {
+                Ok(vec![sign_pkcs1(
+                    n,
+                    d,
+                    hash_algorithm.digest_info_prefix(),
+                    hash,
+                )?])
+            }
+            (
+                PublicKeyMaterial::EdDsa {
+                    curve: Curve::Ed25519,
+                    ..
+                },
+                SecretKeyMaterial::EdDsa { seed },
+            )
+            | (PublicKeyMaterial::Ed25519 { .. }, SecretKeyMaterial::Ed25519 { secret: seed }) => {
+                let seed = seed
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow!("invalid Ed25519 secret key size {}", seed.len()))?;
+                let signature = SigningKey::from_bytes(seed).sign(hash).to_bytes();
+
+                Ok(vec![
+                    BigUint::from_bytes_be(&signature[..32]),
+                    BigUint::from_bytes_be(&signature[32..]),
+                ])
+            }
+            (_, SecretKeyMaterial::Unknown { .. }) => Err(anyhow!(
+                "signing with public key algorithm {:?} is not supported",
+                self.public_key.public_key_algorithm
+            )),
+            _ => Err(anyhow!(
+                "secret key material does not match the public key algorithm"
+            )),
+        }
+    }
+
+    /// Decrypt an encrypted session key, returning the decrypted and decoded value. An RSA
+    /// encrypted value which isn't correctly padded decrypts to `session_key_length` random
+    /// octets instead, so that it fails later on just like a wrong session key, rather than
+    /// telling an attacker that the padding was wrong (Bleichenbacher's attack).
+    pub fn decrypt(
+        &self,
+        encrypted_session_key: &EncryptedSessionKey,
+        session_key_length: usize,
+    ) -> anyhow::Result<Vec<u8>> {
+        match (
+            &self.public_key.key_material,
+            self.unlocked_material()?,
+            encrypted_session_key,
+        ) {
+            (
+                PublicKeyMaterial::Rsa { n, .. },
+                SecretKeyMaterial::Rsa { d, .. },
+                EncryptedSessionKey::Rsa { value },
+            ) => Ok(decrypt_pkcs1(n, d, value).unwrap_or_else(|| random_bytes(session_key_length))),
+            (
+                PublicKeyMaterial::Ecdh { .. },
+                SecretKeyMaterial::Ecdh { secret },
+                EncryptedSessionKey::Ecdh {
+                    ephemeral_point,
+                    wrapped_key,
+                },
+            ) => ecdh_decrypt(&self.public_key, secret, ephemeral_point, wrapped_key),
+            (
+                PublicKeyMaterial::X25519 { public_key },
+                SecretKeyMaterial::X25519 { secret },
+                EncryptedSessionKey::X25519 {
+                    ephemeral,
+                    wrapped_key,
+                    ..
+                },
+            ) => x25519_decrypt(public_key, secret, ephemeral, wrapped_key),
+            (_, _, EncryptedSessionKey::Unknown { .. }) => Err(anyhow!(
+                "decrypting with public key algorithm {:?} is not supported",
+                self.public_key.public_key_algorithm
+            )),
+            _ => Err(anyhow!(
+                "encrypted session key does not match the public key algorithm"
+            )),
+        }
+    }
+
+    /// Whether the packet holds secret key material, which is only missing from GNU dummy keys.
+    pub fn has_secret_material(&self) -> bool {
+        !matches!(self.protection, SecretKeyProtection::GnuDummy { .. })
+    }
+
+    fn unlocked_material(&self) -> anyhow::Result<&SecretKeyMaterial> {
+        if !self.has_secret_material() {
+            return Err(anyhow!(
+                "secret key 0x{:016X} has no secret key material",
+                self.public_key.key_id()
+            ));
+        }
+
+        self.secret_key_material
+            .as_ref()
+            .ok_or_else(|| anyhow!("secret key is locked, a passphrase is needed to unlock it"))
+    }
+}
+
+impl SecretKeyMaterial {
+    /// Serialize the key material, without a checksum.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            SecretKeyMaterial::Rsa { d, p, q, u } => {
+                for value in [d, p, q, u].iter() {
+                    write_mpi(&mut bytes, value);
+                }
+            }
+            SecretKeyMaterial::EdDsa { seed: secret } | SecretKeyMaterial::Ecdh { secret } => {
+                write_mpi(&mut bytes, &BigUint::from_bytes_be(secret))
+            }
+            SecretKeyMaterial::Ed25519 { secret }
+            | SecretKeyMaterial::X25519 { secret }
+            | SecretKeyMaterial::Unknown { data: secret } => bytes.extend_from_slice(secret),
+        }
+
+        bytes
+    }
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyType::Rsa(bits) => write!(f, "a {} bit RSA key", bits),
+            KeyType::Ed25519 => write!(f, "an Ed25519 key with a Curve25519 encryption subkey"),
+        }
+    }
+}
@@ -1,57 +1,227 @@
-use crate::parsers::{parse_cleartext_signature_parts, parse_pkcs1};
-use crate::pgp::{AsciiArmor, PgpPacket};
+use crate::parsers::parse_cleartext_signature_parts;
+use crate::pgp::{read_pgp_packets, AsciiArmor, AsciiArmorKind, PgpPacket};
+use crate::utils::{canonicalize_line_endings, convert_newlines, unix_time_now};
 use anyhow::anyhow;
 use byteorder::{BigEndian, WriteBytesExt};
 use num::BigUint;
-use regex::Regex;
-use sha2::{Digest, Sha256};
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
-use super::key::PublicKey;
-use super::AsciiArmorParts;
+use super::hash::HashAlgorithm;
+use super::key::{PublicKey, PublicKeyAlgorithm, PublicKeyPacket};
+use super::secret_key::{SecretKey, SecretKeyPacket};
+use super::{write_mpi, AsciiArmorParts};
 
-pub type CleartextSignatureParts = (String, String, AsciiArmorParts);
+pub type CleartextSignatureParts = (Option<String>, String, AsciiArmorParts);
+
+/// Signature of a binary document, as defined by the RFC in section 5.2.1.
+pub const SIGNATURE_TYPE_BINARY: u8 = 0x00;
+/// Signature of a canonical text document, as defined by the RFC in section 5.2.1.
+pub const SIGNATURE_TYPE_TEXT: u8 = 0x01;
+/// Generic certification of a user ID and public key.
+pub const SIGNATURE_TYPE_GENERIC_CERTIFICATION: u8 = 0x10;
+/// Persona certification of a user ID and public key.
+pub const SIGNATURE_TYPE_PERSONA_CERTIFICATION: u8 = 0x11;
+/// Casual certification of a user ID and public key.
+pub const SIGNATURE_TYPE_CASUAL_CERTIFICATION: u8 = 0x12;
+/// Positive certification of a user ID and public key.
+pub const SIGNATURE_TYPE_POSITIVE_CERTIFICATION: u8 = 0x13;
+/// Subkey binding signature, made by the primary key over a subkey.
+pub const SIGNATURE_TYPE_SUBKEY_BINDING: u8 = 0x18;
+/// Primary key binding signature, made by a signing subkey over its primary key.
+pub const SIGNATURE_TYPE_PRIMARY_KEY_BINDING: u8 = 0x19;
 
 #[derive(Debug)]
 pub struct CleartextSignature {
-    hash: String,
     cleartext: String,
     signature: SignaturePacket,
 }
 
+/// A signature stored separately from the document it signs.
 #[derive(Debug)]
-pub struct PgpSignature {}
+pub struct DetachedSignature {
+    signature: SignaturePacket,
+}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SignaturePacket {
     pub version: u8,
     pub signature_type: u8,
-    pub public_key_algorithm: u8,
+    pub public_key_algorithm: PublicKeyAlgorithm,
     pub hash_algorithm: u8,
+    /// the raw hashed subpacket data, which is needed to compute the hash.
     pub hashed_subpacket_data: Vec<u8>,
-    pub unhashed_subpacket_data: Vec<u8>,
+    pub hashed_subpackets: Vec<SubPacket>,
+    pub unhashed_subpackets: Vec<SubPacket>,
     /// holds the left 16 bits of the signed hash value.
     pub signed_hash_value_head: u16,
+    /// the salt which is hashed before the signed data, only present in v6 signatures.
+    pub salt: Vec<u8>,
 
     pub signature: Vec<BigUint>,
 }
 
-#[derive(Debug)]
-enum SignatureType {}
+/// A signature subpacket, as defined by the RFC in section 5.2.3.1. Subpackets which aren't
+/// understood are kept as `Unknown`.
+#[derive(Debug, Clone)]
+pub enum SignatureSubPacket {
+    SignatureCreationTime(u32),
+    SignatureExpirationTime(u32),
+    KeyExpirationTime(u32),
+    PreferredSymmetricAlgorithms(Vec<u8>),
+    Issuer(u64),
+    NotationData {
+        human_readable: bool,
+        name: String,
+        value: Vec<u8>,
+    },
+    PreferredHashAlgorithms(Vec<u8>),
+    PreferredCompressionAlgorithms(Vec<u8>),
+    PrimaryUserId(bool),
+    PolicyUri(String),
+    KeyFlags(Vec<u8>),
+    SignersUserId(String),
+    ReasonForRevocation {
+        code: u8,
+        reason: String,
+    },
+    Features(Vec<u8>),
+    EmbeddedSignature(Box<SignaturePacket>),
+    IssuerFingerprint {
+        version: u8,
+        fingerprint: Vec<u8>,
+    },
+    Unknown {
+        subpacket_type: u8,
+        data: Vec<u8>,
+    },
+}
 
-#[derive(Debug)]
-enum PublicKeyAlgorithm {}
+/// A signature subpacket, along with its critical bit. If a critical subpacket is not understood,
+/// the signature must be considered invalid.
+#[derive(Debug, Clone)]
+pub struct SubPacket {
+    pub critical: bool,
+    pub subpacket: SignatureSubPacket,
+}
 
-#[derive(Debug)]
-enum HashAlgorithm {}
+impl SubPacket {
+    /// A subpacket which isn't critical.
+    pub fn new(subpacket: SignatureSubPacket) -> SubPacket {
+        SubPacket {
+            critical: false,
+            subpacket,
+        }
+    }
 
-#[derive(Debug)]
-pub enum SignatureSubPacket {}
+    /// Serialize the subpacket, with its length and type, as defined by the RFC in section
+    /// 5.2.3.1.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut type_octet = self.subpacket.subpacket_type();
+        if self.critical {
+            type_octet |= 0x80;
+        }
+
+        let mut body = vec![type_octet];
+        body.extend_from_slice(&self.subpacket.to_bytes()?);
+
+        let mut bytes = Vec::new();
+        match body.len() {
+            length @ 0..=191 => bytes.push(length as u8),
+            length @ 192..=8383 => {
+                let length = length - 192;
+                bytes.push((length >> 8) as u8 + 192);
+                bytes.push(length as u8);
+            }
+            length => {
+                bytes.push(0xff);
+                bytes.write_u32::<BigEndian>(length.try_into()?)?;
+            }
+        }
+        bytes.extend_from_slice(&body);
+
+        Ok(bytes)
+    }
+}
+
+impl SignatureSubPacket {
+    /// The subpacket type, as listed by the RFC in section 5.2.3.1.
+    fn subpacket_type(&self) -> u8 {
+        match self {
+            SignatureSubPacket::SignatureCreationTime(_) => 2,
+            SignatureSubPacket::SignatureExpirationTime(_) => 3,
+            SignatureSubPacket::KeyExpirationTime(_) => 9,
+            SignatureSubPacket::PreferredSymmetricAlgorithms(_) => 11,
+            SignatureSubPacket::Issuer(_) => 16,
+            SignatureSubPacket::NotationData { .. } => 20,
+            SignatureSubPacket::PreferredHashAlgorithms(_) => 21,
+            SignatureSubPacket::PreferredCompressionAlgorithms(_) => 22,
+            SignatureSubPacket::PrimaryUserId(_) => 25,
+            SignatureSubPacket::PolicyUri(_) => 26,
+            SignatureSubPacket::KeyFlags(_) => 27,
+            SignatureSubPacket::SignersUserId(_) => 28,
+            SignatureSubPacket::ReasonForRevocation { .. } => 29,
+            SignatureSubPacket::Features(_) => 30,
+            SignatureSubPacket::EmbeddedSignature(_) => 32,
+            SignatureSubPacket::IssuerFingerprint { .. } => 33,
+            SignatureSubPacket::Unknown { subpacket_type, .. } => *subpacket_type,
+        }
+    }
+
+    /// Serialize the subpacket body.
+    fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        match self {
+            SignatureSubPacket::SignatureCreationTime(time)
+            | SignatureSubPacket::SignatureExpirationTime(time)
+            | SignatureSubPacket::KeyExpirationTime(time) => bytes.write_u32::<BigEndian>(*time)?,
+            SignatureSubPacket::Issuer(key_id) => bytes.write_u64::<BigEndian>(*key_id)?,
+            SignatureSubPacket::NotationData {
+                human_readable,
+                name,
+                value,
+            } => {
+                let flags = if *human_readable { 0x8000_0000 } else { 0 };
+                bytes.write_u32::<BigEndian>(flags)?;
+                bytes.write_u16::<BigEndian>(name.len().try_into()?)?;
+                bytes.write_u16::<BigEndian>(value.len().try_into()?)?;
+                bytes.extend_from_slice(name.as_bytes());
+                bytes.extend_from_slice(value);
+            }
+            SignatureSubPacket::PreferredSymmetricAlgorithms(data)
+            | SignatureSubPacket::PreferredHashAlgorithms(data)
+            | SignatureSubPacket::PreferredCompressionAlgorithms(data)
+            | SignatureSubPacket::KeyFlags(data)
+            | SignatureSubPacket::Features(data)
+            | SignatureSubPacket::Unknown { data, .. } => bytes.extend_from_slice(data),
+            SignatureSubPacket::PrimaryUserId(primary) => bytes.push(*primary as u8),
+            SignatureSubPacket::PolicyUri(text) | SignatureSubPacket::SignersUserId(text) => {
+                bytes.extend_from_slice(text.as_bytes())
+            }
+            SignatureSubPacket::ReasonForRevocation { code, reason } => {
+                bytes.push(*code);
+                bytes.extend_from_slice(reason.as_bytes());
+            }
+            SignatureSubPacket::EmbeddedSignature(signature) => {
+                bytes.extend_from_slice(&signature.to_bytes()?)
+            }
+            SignatureSubPacket::IssuerFingerprint {
+                version,
+                fingerprint,
+            } => {
+                bytes.push(*version);
+                bytes.extend_from_slice(fingerprint);
+            }
+        }
+
+        Ok(bytes)
+    }
+}
 
 impl CleartextSignature {
     pub fn parse(input: &str) -> anyhow::Result<CleartextSignature> {
-        let (_, (hash, cleartext, ascii_armor_parts)) = parse_cleartext_signature_parts(input)
-            .map_err(|_| anyhow!("failed to parse parts of cleartext signature"))?;
+        let (_, (hash_header, cleartext, ascii_armor_parts)) =
+            parse_cleartext_signature_parts(input)
+                .map_err(|_| anyhow!("failed to parse parts of cleartext signature"))?;
 
         let ascii_armor = AsciiArmor::from_parts(ascii_armor_parts)?;
 
@@ -61,59 +231,472 @@ impl CleartextSignature {
             ));
         }
 
-        let mut packets = ascii_armor.into_pgp_packets()?;
+        if ascii_armor.kind != AsciiArmorKind::Signature {
+            return Err(anyhow!("ascii armor did not contain a signature"));
+        }
 
-        if let PgpPacket::SignaturePacket(signature) = packets.pop().unwrap() {
-            Ok(CleartextSignature {
-                hash,
-                cleartext,
-                signature,
-            })
+        let mut packets = ascii_armor.to_pgp_packets()?;
+        packets.retain(|packet| !packet.is_unknown());
+
+        let signature = match packets.pop() {
+            Some(PgpPacket::SignaturePacket(signature)) => signature,
+            _ => return Err(anyhow!("did not find a signature packet")),
+        };
+
+        // the "Hash" armor header lists the hash algorithms used by the signatures, so the
+        // algorithm from the signature packet must be one of them. Names which aren't known may
+        // belong to other signatures, and are ignored.
+        if let Some(hash_header) = hash_header {
+            let hash = HashAlgorithm::try_from(signature.hash_algorithm)?;
+            let header_algorithms = hash_header
+                .split(',')
+                .filter_map(|name| HashAlgorithm::from_name(name.trim()).ok())
+                .collect::<Vec<_>>();
+            if !header_algorithms.contains(&hash) {
+                return Err(anyhow!(
+                    "hash armor header \"{}\" does not match the signature hash algorithm {}",
+                    hash_header,
+                    hash.name()
+                ));
+            }
+        }
+
+        Ok(CleartextSignature {
+            cleartext,
+            signature,
+        })
+    }
+
+    /// Sign text as a cleartext signature, as described by the RFC in section 7. Trailing
+    /// whitespace is removed from each line, and the line endings are canonicalized to CRLF for
+    /// the signature. The line ending after the last line is not part of the signed text.
+    pub fn create(text: &str, key: &SecretKey) -> anyhow::Result<CleartextSignature> {
+        let text = convert_newlines(text);
+        let text = text.strip_suffix('\n').unwrap_or(&text);
+        let cleartext = text
+            .split('\n')
+            .map(|line| line.trim_end_matches([' ', '\t', '\r']))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let signature = key.sign_document(true, cleartext.as_bytes())?;
+
+        Ok(CleartextSignature {
+            cleartext,
+            signature,
+        })
+    }
+
+    /// Write the cleartext signature document: the dash-escaped text, followed by the ascii
+    /// armored signature.
+    pub fn to_document(&self) -> anyhow::Result<String> {
+        let hash = HashAlgorithm::try_from(self.signature.hash_algorithm)?;
+
+        let mut document = format!(
+            "-----BEGIN PGP SIGNED MESSAGE-----\nHash: {}\n\n",
+            hash.name()
+        );
+        for line in self.cleartext.split('\n') {
+            // lines starting with a dash are escaped, so they can't be confused with armor lines.
+            if line.starts_with('-') {
+                document.push_str("- ");
+            }
+            document.push_str(line);
+            document.push('\n');
+        }
+
+        let signature = PgpPacket::SignaturePacket(self.signature.clone()).to_bytes()?;
+        document.push_str(&AsciiArmor::new(AsciiArmorKind::Signature, signature).to_string());
+
+        Ok(document)
+    }
+
+    pub fn signature(&self) -> &SignaturePacket {
+        &self.signature
+    }
+
+    pub fn verify(&self, key: &PublicKey) -> anyhow::Result<bool> {
+        // the msg is canonicalized by replacing newlines with CRLF.
+        let cleartext = canonicalize_line_endings(self.cleartext.as_bytes());
+
+        self.signature.verify(key, &cleartext)
+    }
+}
+
+impl DetachedSignature {
+    /// Sign a document, in text mode as a text document.
+    pub fn create(data: &[u8], text: bool, key: &SecretKey) -> anyhow::Result<DetachedSignature> {
+        Ok(DetachedSignature {
+            signature: key.sign_document(text, data)?,
+        })
+    }
+
+    /// Serialize the signature packet.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        PgpPacket::SignaturePacket(self.signature.clone()).to_bytes()
+    }
+
+    /// Parse a detached signature, which may be either ascii armored or binary.
+    pub fn parse(input: &[u8]) -> anyhow::Result<DetachedSignature> {
+        let mut packets = read_pgp_packets(input, AsciiArmorKind::Signature)?;
+        packets.retain(|packet| !packet.is_unknown());
+
+        if let Some(PgpPacket::SignaturePacket(signature)) = packets.pop() {
+            Ok(DetachedSignature { signature })
         } else {
             Err(anyhow!("did not find a signature packet"))
         }
     }
 
-    pub fn verify(&self, key: &PublicKey) -> anyhow::Result<bool> {
-        let mut hasher = Sha256::new();
+    pub fn signature(&self) -> &SignaturePacket {
+        &self.signature
+    }
 
-        // 1. write the msg, canonicalized by replacing newlines with CRLF.
-        let r = Regex::new(r"\r\n")?;
-        let replaced = r.replace_all(self.cleartext.as_str(), "\n");
-        let r = Regex::new(r"\n")?;
-        let replaced = r.replace_all(replaced.as_ref(), "\r\n");
-        hasher.update(replaced.as_ref());
+    /// Verify the signature over the contents of the signed file.
+    pub fn verify(&self, key: &PublicKey, data: &[u8]) -> anyhow::Result<bool> {
+        self.signature.verify_document(key, data)
+    }
+}
+
+impl SignaturePacket {
+    /// All subpackets, hashed first. Unhashed subpackets aren't covered by the signature, so they
+    /// should only be used for advisory information (such as the issuer).
+    pub fn subpackets(&self) -> impl Iterator<Item = &SignatureSubPacket> {
+        self.hashed_subpackets
+            .iter()
+            .chain(self.unhashed_subpackets.iter())
+            .map(|s| &s.subpacket)
+    }
+
+    /// The time the signature was created, from the hashed subpackets.
+    pub fn creation_time(&self) -> Option<u32> {
+        self.hashed_subpackets
+            .iter()
+            .find_map(|s| match s.subpacket {
+                SignatureSubPacket::SignatureCreationTime(time) => Some(time),
+                _ => None,
+            })
+    }
+
+    /// The number of seconds after its creation that the signature expires, from the hashed
+    /// subpackets.
+    pub fn expiration_time(&self) -> Option<u32> {
+        self.hashed_subpackets
+            .iter()
+            .find_map(|s| match s.subpacket {
+                SignatureSubPacket::SignatureExpirationTime(time) => Some(time),
+                _ => None,
+            })
+    }
+
+    /// The key ID of the key which made the signature.
+    pub fn issuer(&self) -> Option<u64> {
+        self.subpackets().find_map(|s| match s {
+            SignatureSubPacket::Issuer(key_id) => Some(*key_id),
+            _ => None,
+        })
+    }
+
+    /// The fingerprint of the key which made the signature.
+    pub fn issuer_fingerprint(&self) -> Option<&[u8]> {
+        self.subpackets().find_map(|s| match s {
+            SignatureSubPacket::IssuerFingerprint { fingerprint, .. } => {
+                Some(fingerprint.as_slice())
+            }
+            _ => None,
+        })
+    }
+
+    /// Whether the signature was made by `key`, according to its issuer subpackets.
+    pub fn is_issued_by(&self, key: &PublicKeyPacket) -> bool {
+        match (self.issuer_fingerprint(), self.issuer()) {
+            (Some(fingerprint), _) => key.fingerprint().as_slice() == fingerprint,
+            (None, Some(key_id)) => key.key_id() == key_id,
+            (None, None) => false,
+        }
+    }
+
+    /// Whether a self-certification marks its user ID as the primary one, from the hashed
+    /// subpackets.
+    pub fn is_primary_user_id(&self) -> bool {
+        self.hashed_subpackets
+            .iter()
+            .any(|s| matches!(s.subpacket, SignatureSubPacket::PrimaryUserId(true)))
+    }
+
+    /// The key flags, from the hashed subpackets.
+    pub fn key_flags(&self) -> Option<&[u8]> {
+        self.hashed_subpackets
+            .iter()
+            .find_map(|s| match &s.subpacket {
+                SignatureSubPacket::KeyFlags(flags) => Some(flags.as_slice()),
+                _ => None,
+            })
+    }
+
+    /// The symmetric algorithms the key holder prefers, most preferred first, from the hashed
+    /// subpackets.
+    pub fn preferred_symmetric_algorithms(&self) -> Option<&[u8]> {
+        self.hashed_subpackets
+            .iter()
+            .find_map(|s| match &s.subpacket {
+                SignatureSubPacket::PreferredSymmetricAlgorithms(algorithms) => {
+                    Some(algorithms.as_slice())
+                }
+                _ => None,
+            })
+    }
+
+    /// A signature embedded in this one, such as the primary key binding signature of a signing
+    /// subkey.
+    pub fn embedded_signature(&self) -> Option<&SignaturePacket> {
+        self.subpackets().find_map(|s| match s {
+            SignatureSubPacket::EmbeddedSignature(signature) => Some(signature.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Check the parts of the signature which don't depend on the signed data. The creation time
+    /// must be present, the signature must not have expired, and all critical subpackets must be
+    /// understood (no notations are understood).
+    fn check_subpackets(&self) -> anyhow::Result<()> {
+        let all_subpackets = self
+            .hashed_subpackets
+            .iter()
+            .chain(self.unhashed_subpackets.iter());
+        for s in all_subpackets {
+            match s.subpacket {
+                SignatureSubPacket::Unknown { subpacket_type, .. } if s.critical => {
+                    return Err(anyhow!(
+                        "signature has an unknown critical subpacket of type {}",
+                        subpacket_type
+                    ));
+                }
+                SignatureSubPacket::NotationData { ref name, .. } if s.critical => {
+                    return Err(anyhow!(
+                        "signature has an unknown critical notation {}",
+                        name
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        let creation_time = self
+            .creation_time()
+            .ok_or_else(|| anyhow!("signature does not have a creation time"))?;
+
+        if let Some(expiration_time) = self.expiration_time() {
+            let expires_at = u64::from(creation_time) + u64::from(expiration_time);
+            if expiration_time != 0 && expires_at <= unix_time_now() {
+                return Err(anyhow!("signature has expired"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify a document signature. Binary document signatures are computed over the document
+    /// as-is, text document signatures over the document with its line endings canonicalized.
+    pub fn verify_document(&self, key: &PublicKey, data: &[u8]) -> anyhow::Result<bool> {
+        match self.signature_type {
+            SIGNATURE_TYPE_BINARY => self.verify(key, data),
+            SIGNATURE_TYPE_TEXT => self.verify(key, &canonicalize_line_endings(data)),
+            signature_type => Err(anyhow!(
+                "signature type {:#04x} is not a document signature",
+                signature_type
+            )),
+        }
+    }
+
+    /// Verify the signature over `data`, which must already be in the form required by the
+    /// signature type, using the key from the certificate which made the signature.
+    pub fn verify(&self, key: &PublicKey, data: &[u8]) -> anyhow::Result<bool> {
+        let signing_key = key.find_signing_key(self)?;
+        self.verify_with_key(signing_key, data)
+    }
+
+    /// Verify the signature over `data` with a specific key.
+    pub fn verify_with_key(&self, key: &PublicKeyPacket, data: &[u8]) -> anyhow::Result<bool> {
+        self.check_subpackets()?;
+
+        if self.public_key_algorithm != key.public_key_algorithm {
+            return Err(anyhow!(
+                "signature public key algorithm {:?} does not match the key algorithm {:?}",
+                self.public_key_algorithm,
+                key.public_key_algorithm
+            ));
+        }
+
+        if self.version != key.version {
+            return Err(anyhow!(
+                "version {} signature cannot be made by a version {} key",
+                self.version,
+                key.version
+            ));
+        }
+
+        let hash_algorithm = HashAlgorithm::try_from(self.hash_algorithm)?;
+        let hash = self.compute_hash(data)?;
+
+        key.key_material
+            .verify(hash_algorithm, &hash, &self.signature)
+    }
+
+    /// Compute the hash of `data` and the signature's own fields, as described by the RFC in
+    /// section 5.2.4.
+    fn compute_hash(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let hash_algorithm = HashAlgorithm::try_from(self.hash_algorithm)?;
+        let mut hasher = hash_algorithm.hasher();
+
+        // v6 signatures hash a salt of a size fixed by the hash algorithm before the data, and
+        // use a 4 octet length for the hashed subpacket data.
+        let length_size = match self.version {
+            4 => 2,
+            6 => {
+                if Some(self.salt.len()) != hash_algorithm.salt_size() {
+                    return Err(anyhow!(
+                        "signature salt size {} does not match the hash algorithm {}",
+                        self.salt.len(),
+                        hash_algorithm.name()
+                    ));
+                }
+                hasher.update(&self.salt);
+                4
+            }
+            version => return Err(anyhow!("unsupported signature version {}", version)),
+        };
+
+        // 1. write the signed data.
+        hasher.update(data);
 
         // 2. write the initial bytes of the signature packet.
         hasher.update(&[
-            self.signature.version,
-            self.signature.signature_type,
-            self.signature.public_key_algorithm,
-            self.signature.hash_algorithm,
+            self.version,
+            self.signature_type,
+            self.public_key_algorithm.id(),
+            self.hash_algorithm,
         ]);
 
         let mut buf = Vec::new();
-        let length = self.signature.hashed_subpacket_data.len().try_into()?;
-        buf.write_u16::<BigEndian>(length)?;
-        hasher.update(buf);
-        hasher.update(self.signature.hashed_subpacket_data.clone());
+        let length = self.hashed_subpacket_data.len();
+        if length_size == 2 {
+            buf.write_u16::<BigEndian>(length.try_into()?)?;
+        } else {
+            buf.write_u32::<BigEndian>(length.try_into()?)?;
+        }
+        hasher.update(&buf);
+        hasher.update(&self.hashed_subpacket_data);
 
-        // 3. finally, write the v4 hash trailer.
-        hasher.update(&[0x04_u8, 0xff]);
+        // 3. finally, write the hash trailer, which counts the bytes hashed in step 2.
+        hasher.update(&[self.version, 0xff]);
         let mut buf = Vec::new();
-        let mut length = self.signature.hashed_subpacket_data.len().try_into()?;
-        length += 6;
+        let length: u32 = (4 + length_size + length).try_into()?;
         buf.write_u32::<BigEndian>(length)?;
-        hasher.update(buf);
+        hasher.update(&buf);
+
+        Ok(hasher.finalize().to_vec())
+    }
+
+    /// Create a v4 signature over `data` with a secret key. Creation time and issuer fingerprint
+    /// subpackets are added to `hashed_subpackets`, and an issuer subpacket is left unhashed.
+    pub fn create(
+        key: &SecretKeyPacket,
+        signature_type: u8,
+        hash_algorithm: HashAlgorithm,
+        hashed_subpackets: Vec<SubPacket>,
+        data: &[u8],
+    ) -> anyhow::Result<SignaturePacket> {
+        let public_key = &key.public_key;
+        // v6 keys must make v6 signatures, which can't be created yet.
+        if public_key.version != 4 {
+            return Err(anyhow!(
+                "signing with v{} keys is not supported",
+                public_key.version
+            ));
+        }
+
+        let mut all_hashed_subpackets = vec![
+            SubPacket::new(SignatureSubPacket::SignatureCreationTime(
+                unix_time_now().try_into()?,
+            )),
+            SubPacket::new(SignatureSubPacket::IssuerFingerprint {
+                version: public_key.version,
+                fingerprint: public_key.fingerprint(),
+            }),
+        ];
+        all_hashed_subpackets.extend(hashed_subpackets);
+
+        let mut hashed_subpacket_data = Vec::new();
+        for subpacket in all_hashed_subpackets.iter() {
+            hashed_subpacket_data.extend_from_slice(&subpacket.to_bytes()?);
+        }
+
+        let mut signature = SignaturePacket {
+            version: 4,
+            signature_type,
+            public_key_algorithm: public_key.public_key_algorithm,
+            hash_algorithm: hash_algorithm.id(),
+            hashed_subpacket_data,
+            hashed_subpackets: all_hashed_subpackets,
+            unhashed_subpackets: vec![SubPacket::new(SignatureSubPacket::Issuer(
+                public_key.key_id(),
+            ))],
+            signed_hash_value_head: 0,
+            salt: Vec::new(),
+            signature: Vec::new(),
+        };
 
-        let hash = hasher.finalize();
-        let computed = BigUint::from_bytes_be(&hash);
+        let hash = signature.compute_hash(data)?;
+        signature.signed_hash_value_head = u16::from_be_bytes([hash[0], hash[1]]);
+        signature.signature = key.sign(hash_algorithm, &hash)?;
 
-        let signature = self.signature.signature[0]
-            .modpow(&key.e, &key.n)
-            .to_bytes_be();
-        let (_, decoded) = parse_pkcs1(&signature).map_err(|_| anyhow!("Failed to parse pkcs1"))?;
+        Ok(signature)
+    }
+
+    /// Serialize the signature packet body.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = vec![
+            self.version,
+            self.signature_type,
+            self.public_key_algorithm.id(),
+            self.hash_algorithm,
+        ];
+
+        let mut unhashed_subpacket_data = Vec::new();
+        for subpacket in self.unhashed_subpackets.iter() {
+            unhashed_subpacket_data.extend_from_slice(&subpacket.to_bytes()?);
+        }
+
+        for subpacket_data in [&self.hashed_subpacket_data, &unhashed_subpacket_data].iter() {
+            if self.version == 6 {
+                bytes.write_u32::<BigEndian>(subpacket_data.len().try_into()?)?;
+            } else {
+                bytes.write_u16::<BigEndian>(subpacket_data.len().try_into()?)?;
+            }
+            bytes.extend_from_slice(subpacket_data);
+        }
+
+        bytes.write_u16::<BigEndian>(self.signed_hash_value_head)?;
+
+        if self.version == 6 {
+            bytes.push(self.salt.len().try_into()?);
+            bytes.extend_from_slice(&self.salt);
+        }
+
+        if self.public_key_algorithm == PublicKeyAlgorithm::Ed25519 {
+            // native Ed25519 signatures are written as 64 raw octets.
+            for value in self.signature.iter() {
+                let value = value.to_bytes_be();
+                bytes.resize(bytes.len() + 32 - value.len(), 0);
+                bytes.extend_from_slice(&value);
+            }
+        } else {
+            for value in self.signature.iter() {
+                write_mpi(&mut bytes, value);
+            }
+        }
 
-        Ok(decoded == computed)
+        Ok(bytes)
     }
 }
@@ -0,0 +1,134 @@
+use aes::cipher::{AsyncStreamCipher, KeyIvInit};
+use aes::{Aes128, Aes192, Aes256};
+use aes_kw::{KekAes128, KekAes192, KekAes256};
+use anyhow::anyhow;
+use std::convert::TryFrom;
+use std::fmt;
+
+type Aes128CfbEnc = cfb_mode::Encryptor<Aes128>;
+type Aes192CfbEnc = cfb_mode::Encryptor<Aes192>;
+type Aes256CfbEnc = cfb_mode::Encryptor<Aes256>;
+type Aes128CfbDec = cfb_mode::Decryptor<Aes128>;
+type Aes192CfbDec = cfb_mode::Decryptor<Aes192>;
+type Aes256CfbDec = cfb_mode::Decryptor<Aes256>;
+
+/// The symmetric key algorithms which are supported, as defined by the RFC in section 9.2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymmetricAlgorithm {
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl SymmetricAlgorithm {
+    pub fn id(&self) -> u8 {
+        match self {
+            SymmetricAlgorithm::Aes128 => 7,
+            SymmetricAlgorithm::Aes192 => 8,
+            SymmetricAlgorithm::Aes256 => 9,
+        }
+    }
+
+    /// The size of a key, in octets.
+    pub fn key_size(&self) -> usize {
+        match self {
+            SymmetricAlgorithm::Aes128 => 16,
+            SymmetricAlgorithm::Aes192 => 24,
+            SymmetricAlgorithm::Aes256 => 32,
+        }
+    }
+
+    /// The size of a block, in octets.
+    pub fn block_size(&self) -> usize {
+        16
+    }
+
+    /// Encrypt `data` in place using CFB mode, as described by the RFC in section 13.9 (without
+    /// the OpenPGP specific resynchronization).
+    pub fn cfb_encrypt(&self, key: &[u8], iv: &[u8], data: &mut [u8]) -> anyhow::Result<()> {
+        let invalid = |_| anyhow!("invalid key or IV size for {:?}", self);
+        match self {
+            SymmetricAlgorithm::Aes128 => Aes128CfbEnc::new_from_slices(key, iv)
+                .map_err(invalid)?
+                .encrypt(data),
+            SymmetricAlgorithm::Aes192 => Aes192CfbEnc::new_from_slices(key, iv)
+                .map_err(invalid)?
+                .encrypt(data),
+            SymmetricAlgorithm::Aes256 => Aes256CfbEnc::new_from_slices(key, iv)
+                .map_err(invalid)?
+                .encrypt(data),
+        }
+
+        Ok(())
+    }
+
+    /// Decrypt `data` in place using CFB mode.
+    pub fn cfb_decrypt(&self, key: &[u8], iv: &[u8], data: &mut [u8]) -> anyhow::Result<()> {
+        let invalid = |_| anyhow!("invalid key or IV size for {:?}", self);
+        match self {
+            SymmetricAlgorithm::Aes128 => Aes128CfbDec::new_from_slices(key, iv)
+                .map_err(invalid)?
+                .decrypt(data),
+            SymmetricAlgorithm::Aes192 => Aes192CfbDec::new_from_slices(key, iv)
+                .map_err(invalid)?
+                .decrypt(data),
+            SymmetricAlgorithm::Aes256 => Aes256CfbDec::new_from_slices(key, iv)
+                .map_err(invalid)?
+                .decrypt(data),
+        }
+
+        Ok(())
+    }
+
+    /// Wrap `data`, whose size must be a multiple of 8 octets, with the AES key wrap algorithm of
+    /// RFC 3394.
+    pub fn key_wrap(&self, key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let invalid = |_| anyhow!("invalid key size for {:?}", self);
+        match self {
+            SymmetricAlgorithm::Aes128 => KekAes128::try_from(key).map_err(invalid)?.wrap_vec(data),
+            SymmetricAlgorithm::Aes192 => KekAes192::try_from(key).map_err(invalid)?.wrap_vec(data),
+            SymmetricAlgorithm::Aes256 => KekAes256::try_from(key).map_err(invalid)?.wrap_vec(data),
+        }
+        .map_err(|err| anyhow!("could not wrap the key: {}", err))
+    }
+
+    /// Unwrap `data` with the AES key wrap algorithm, checking its integrity.
+    pub fn key_unwrap(&self, key: &[u8], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let invalid = |_| anyhow!("invalid key size for {:?}", self);
+        match self {
+            SymmetricAlgorithm::Aes128 => {
+                KekAes128::try_from(key).map_err(invalid)?.unwrap_vec(data)
+            }
+            SymmetricAlgorithm::Aes192 => {
+                KekAes192::try_from(key).map_err(invalid)?.unwrap_vec(data)
+            }
+            SymmetricAlgorithm::Aes256 => {
+                KekAes256::try_from(key).map_err(invalid)?.unwrap_vec(data)
+            }
+        }
+        .map_err(|err| anyhow!("could not unwrap the key: {}", err))
+    }
+}
+
+impl TryFrom<u8> for SymmetricAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(val: u8) -> anyhow::Result<Self> {
+        match val {
+            7 => Ok(SymmetricAlgorithm::Aes128),
+            8 => Ok(SymmetricAlgorithm::Aes192),
+            9 => Ok(SymmetricAlgorithm::Aes256),
+            _ => Err(anyhow!("unsupported symmetric algorithm {}", val)),
+        }
+    }
+}
+
+impl fmt::Display for SymmetricAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymmetricAlgorithm::Aes128 => write!(f, "AES-128"),
+            SymmetricAlgorithm::Aes192 => write!(f, "AES-192"),
+            SymmetricAlgorithm::Aes256 => write!(f, "AES-256"),
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use anyhow::anyhow;
+use num::bigint::{BigInt, RandBigInt, ToBigInt};
+use num::{BigUint, Integer, One, Signed, Zero};
+use rayon::iter::{repeat, ParallelIterator};
+use subtle::ConstantTimeEq;
+
+/// The public exponent used for generated keys.
+const PUBLIC_EXPONENT: u32 = 65537;
+
+/// Rounds of Miller-Rabin used when testing primality. 20 rounds puts the chance of a composite
+/// being accepted well below 2^-80, even before accounting for the density of primes.
+const MILLER_RABIN_ROUNDS: usize = 20;
+
+const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+/// An RSA private key, along with the values OpenPGP stores to speed up private key operations.
+#[derive(Debug)]
+pub struct RsaPrivateKey {
+    pub n: BigUint,
+    pub e: BigUint,
+    pub d: BigUint,
+    /// the smaller of the two primes.
+    pub p: BigUint,
+    pub q: BigUint,
+    /// the inverse of p mod q.
+    pub u: BigUint,
+}
+
+impl RsaPrivateKey {
+    /// Generate a key whose modulus is `bits` bits long.
+    pub fn generate(bits: u64) -> RsaPrivateKey {
+        let e = BigUint::from(PUBLIC_EXPONENT);
+
+        loop {
+            let p = generate_prime(bits / 2);
+            let q = generate_prime(bits - bits / 2);
+            if p == q {
+                continue;
+            }
+            let (p, q) = if p < q { (p, q) } else { (q, p) };
+
+            let totient = carmichaels_totient_function(&p, &q);
+            let d = match mod_inverse(&e, &totient) {
+                Some(d) => d,
+                None => continue,
+            };
+            let u = mod_inverse(&p, &q).expect("distinct primes are coprime");
+
+            return RsaPrivateKey {
+                n: &p * &q,
+                e,
+                d,
+                p,
+                q,
+                u,
+            };
+        }
+    }
+}
+
+/// Sign a hash using PKCS#1 v1.5 (RFC 3447 section 8.2), where `digest_info_prefix` identifies
+/// the hash algorithm. Fails if the modulus is too small to hold the encoded hash.
+pub fn sign_pkcs1(
+    n: &BigUint,
+    d: &BigUint,
+    digest_info_prefix: &[u8],
+    hash: &[u8],
+) -> anyhow::Result<BigUint> {
+    let k = (n.bits() as usize).div_ceil(8);
+    let encoded = encode_pkcs1_signature(k, digest_info_prefix, hash)
+        .ok_or_else(|| anyhow!("RSA key is too small for this hash"))?;
+
+    Ok(BigUint::from_bytes_be(&encoded).modpow(d, n))
+}
+
+/// Verify a PKCS#1 v1.5 signature over a hash (RFC 3447 section 8.2.2), where
+/// `digest_info_prefix` identifies the hash algorithm. The expected encoding is built and compared
+/// with the recovered one in constant time, rather than parsing the recovered encoding.
+pub fn verify_pkcs1(
+    n: &BigUint,
+    e: &BigUint,
+    digest_info_prefix: &[u8],
+    hash: &[u8],
+    signature: &BigUint,
+) -> bool {
+    if n.is_zero() || signature >= n {
+        return false;
+    }
+
+    let k = (n.bits() as usize).div_ceil(8);
+    let expected = match encode_pkcs1_signature(k, digest_info_prefix, hash) {
+        Some(expected) => expected,
+        None => return false,
+    };
+
+    let recovered = signature.modpow(e, n).to_bytes_be();
+    if recovered.len() > k {
+        return false;
+    }
+    let mut encoded = vec![0; k - recovered.len()];
+    encoded.extend_from_slice(&recovered);
+
+    encoded.ct_eq(&expected).into()
+}
+
+/// Encrypt a message using PKCS#1 v1.5 (RFC 3447 section 7.2), returning `None` if the message
+/// is too long for the modulus.
+pub fn encrypt_pkcs1(n: &BigUint, e: &BigUint, message: &[u8]) -> Option<BigUint> {
+    let k = (n.bits() as usize).div_ceil(8);
+    let padding_length = k
+        .checked_sub(message.len() + 3)
+        .filter(|length| *length >= 8)?;
+
+    let mut encoded = vec![0x00, 0x02];
+    encoded.extend(
+        std::iter::repeat_with(rand::random::<u8>)
+            .filter(|b| *b != 0)
+            .take(padding_length),
+    );
+    encoded.push(0x00);
+    encoded.extend_from_slice(message);
+
+    Some(BigUint::from_bytes_be(&encoded).modpow(e, n))
+}
+
+/// Decrypt a message encrypted using PKCS#1 v1.5 (RFC 3447 section 7.2), returning `None` if the
+/// decrypted value isn't correctly padded.
+pub fn decrypt_pkcs1(n: &BigUint, d: &BigUint, ciphertext: &BigUint) -> Option<Vec<u8>> {
+    if ciphertext >= n {
+        return None;
+    }
+
+    let k = (n.bits() as usize).div_ceil(8);
+    let decrypted = ciphertext.modpow(d, n).to_bytes_be();
+    let mut encoded = vec![0; k.checked_sub(decrypted.len())?];
+    encoded.extend_from_slice(&decrypted);
+
+    // the message is 0x00 0x02, then at least 8 octets of non-zero padding, then 0x00.
+    if !encoded.starts_with(&[0x00, 0x02]) {
+        return None;
+    }
+    let padding_length = encoded[2..].iter().position(|b| *b == 0)?;
+    if padding_length < 8 {
+        return None;
+    }
+
+    Some(encoded[2 + padding_length + 1..].to_owned())
+}
+
+/// Encode a hash as a `k` octet EMSA-PKCS1-v1_5 block (RFC 3447 section 9.2): 0x00 0x01, at
+/// least 8 octets of 0xff, 0x00, then the DigestInfo. Returns `None` if `k` is smaller than the
+/// DigestInfo plus 11 octets.
+fn encode_pkcs1_signature(k: usize, digest_info_prefix: &[u8], hash: &[u8]) -> Option<Vec<u8>> {
+    let t_len = digest_info_prefix.len() + hash.len();
+    let padding_length = k.checked_sub(t_len + 3).filter(|length| *length >= 8)?;
+
+    let mut encoded = vec![0x00, 0x01];
+    encoded.resize(2 + padding_length, 0xff);
+    encoded.push(0x00);
+    encoded.extend_from_slice(digest_info_prefix);
+    encoded.extend_from_slice(hash);
+
+    Some(encoded)
+}
+
+/// Generate a random prime of `bits` bits. The top two bits are set, so that the product of two
+/// such primes has exactly the sum of their sizes. Candidates are tested in parallel.
+fn generate_prime(bits: u64) -> BigUint {
+    let top_bits = BigUint::from(3_u32) << (bits - 2) as usize;
+
+    repeat(())
+        .map(|_| {
+            let mut rng = rand::thread_rng();
+            rng.gen_biguint(bits) | &top_bits | BigUint::one()
+        })
+        .find_any(is_probable_prime)
+        .expect("the iterator is infinite")
+}
+
+/// Test whether `n` is prime: first by trial division with the first few primes, then with the
+/// Fermat test, and finally with Miller-Rabin.
+fn is_probable_prime(n: &BigUint) -> bool {
+    for small_prime in SMALL_PRIMES.iter() {
+        let small_prime = BigUint::from(*small_prime);
+        if *n == small_prime {
+            return true;
+        }
+        if (n % &small_prime).is_zero() {
+            return false;
+        }
+    }
+    if *n < BigUint::from(2_u32) {
+        return false;
+    }
+
+    let one = BigUint::one();
+    let n_minus_one = n - &one;
+    if BigUint::from(2_u32).modpow(&n_minus_one, n) != one {
+        return false;
+    }
+
+    miller_rabin(n, MILLER_RABIN_ROUNDS)
+}
+
+/// The Miller-Rabin probabilistic primality test, with `rounds` random bases. `n` must be odd and
+/// greater than 3.
+fn miller_rabin(n: &BigUint, rounds: usize) -> bool {
+    let one = BigUint::one();
+    let two = BigUint::from(2_u32);
+    let n_minus_one = n - &one;
+
+    // write n - 1 as 2^s * d, with d odd.
+    let s = n_minus_one.trailing_zeros().expect("n - 1 is not zero");
+    let d = &n_minus_one >> s as usize;
+
+    let mut rng = rand::thread_rng();
+    'witness: for _ in 0..rounds {
+        let a = rng.gen_biguint_range(&two, &n_minus_one);
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+
+        return false;
+    }
+
+    true
+}
+
+/// Carmichael's totient function of n = pq, lcm(p - 1, q - 1).
+fn carmichaels_totient_function(p: &BigUint, q: &BigUint) -> BigUint {
+    let one = BigUint::one();
+
+    (p - &one).lcm(&(q - &one))
+}
+
+/// The inverse of `a` mod `m`, if `a` and `m` are coprime.
+fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let a = a.to_bigint().unwrap();
+    let m = m.to_bigint().unwrap();
+
+    let extended = a.extended_gcd(&m);
+    if !extended.gcd.is_one() {
+        return None;
+    }
+
+    let x: BigInt = extended.x.mod_floor(&m);
+    debug_assert!(!x.is_negative());
+
+    x.to_biguint()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_probable_prime() {
+        let primes: [u32; 5] = [2, 97, 7919, 104_729, 2_147_483_647];
+        for p in primes.iter() {
+            assert!(is_probable_prime(&BigUint::from(*p)));
+        }
+
+        // 561 and 41041 are Carmichael numbers, which fool the Fermat test for coprime bases.
+        let composites: [u32; 5] = [1, 561, 7917, 41041, 2_147_483_649];
+        for c in composites.iter() {
+            assert!(!is_probable_prime(&BigUint::from(*c)));
+        }
+
+        // 2^127 - 1 is a Mersenne prime.
+        let mersenne = (BigUint::one() << 127_usize) - BigUint::one();
+        assert!(is_probable_prime(&mersenne));
+        assert!(!is_probable_prime(&(&mersenne + BigUint::from(2_u32))));
+    }
+
+    #[test]
+    fn test_carmichaels_totient_function() {
+        let p = BigUint::from(61_u32);
+        let q = BigUint::from(53_u32);
+
+        assert_eq!(carmichaels_totient_function(&p, &q), BigUint::from(780_u32));
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        let e = BigUint::from(17_u32);
+        let m = BigUint::from(780_u32);
+
+        assert_eq!(mod_inverse(&e, &m), Some(BigUint::from(413_u32)));
+        assert_eq!(mod_inverse(&BigUint::from(6_u32), &m), None);
+    }
+
+    #[test]
+    fn test_decrypt_pkcs1() {
+        let key = RsaPrivateKey::generate(512);
+
+        let mut encoded = vec![0x00, 0x02];
+        encoded.extend_from_slice(&[0x5a; 50]);
+        encoded.push(0x00);
+        encoded.extend_from_slice(b"session key");
+        let ciphertext = BigUint::from_bytes_be(&encoded).modpow(&key.e, &key.n);
+        assert_eq!(
+            decrypt_pkcs1(&key.n, &key.d, &ciphertext),
+            Some(b"session key".to_vec())
+        );
+
+        // padding which is too short.
+        let mut encoded = vec![0x00, 0x02];
+        encoded.extend_from_slice(&[0x5a; 4]);
+        encoded.push(0x00);
+        encoded.extend_from_slice(&[0x11; 57]);
+        let ciphertext = BigUint::from_bytes_be(&encoded).modpow(&key.e, &key.n);
+        assert_eq!(decrypt_pkcs1(&key.n, &key.d, &ciphertext), None);
+
+        assert_eq!(decrypt_pkcs1(&key.n, &key.d, &key.n), None);
+    }
+
+    #[test]
+    fn test_verify_pkcs1() {
+        let key = RsaPrivateKey::generate(512);
+        // the DigestInfo prefix of SHA-256.
+        let prefix = [
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x01, 0x05, 0x00, 0x04, 0x20,
+        ];
+        let hash = [0x42; 32];
+
+        let signature = sign_pkcs1(&key.n, &key.d, &prefix, &hash).unwrap();
+        assert!(verify_pkcs1(&key.n, &key.e, &prefix, &hash, &signature));
+        assert!(!verify_pkcs1(
+            &key.n,
+            &key.e,
+            &prefix,
+            &[0x43; 32],
+            &signature
+        ));
+        assert!(!verify_pkcs1(
+            &key.n,
+            &key.e,
+            &prefix,
+            &hash,
+            &(&signature + &key.n)
+        ));
+        assert!(!verify_pkcs1(
+            &BigUint::zero(),
+            &key.e,
+            &prefix,
+            &hash,
+            &signature
+        ));
+
+        // an encoding without any 0xff padding octets, shifted to the end of the block.
+        let mut encoded = vec![0x00; 10];
+        encoded.extend_from_slice(&[0x01, 0x00]);
+        encoded.extend_from_slice(&prefix);
+        encoded.extend_from_slice(&hash);
+        let signature = BigUint::from_bytes_be(&encoded).modpow(&key.d, &key.n);
+        assert!(!verify_pkcs1(&key.n, &key.e, &prefix, &hash, &signature));
+    }
+
+    #[test]
+    fn test_sign_pkcs1_key_too_small() {
+        let key = RsaPrivateKey::generate(512);
+        // the DigestInfo prefix of SHA-512, which with the hash leaves no room for padding in a
+        // 64 octet block.
+        let prefix = [
+            0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x03, 0x05, 0x00, 0x04, 0x40,
+        ];
+
+        assert!(sign_pkcs1(&key.n, &key.d, &prefix, &[0x42; 64]).is_err());
+        assert!(sign_pkcs1(&key.n, &key.d, &prefix, &[0x42; 34]).is_ok());
+        assert!(sign_pkcs1(&key.n, &key.d, &prefix, &[0x42; 35]).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_pkcs1() {
+        let key = RsaPrivateKey::generate(512);
+
+        let ciphertext = encrypt_pkcs1(&key.n, &key.e, b"session key").unwrap();
+        assert_eq!(
+            decrypt_pkcs1(&key.n, &key.d, &ciphertext),
+            Some(b"session key".to_vec())
+        );
+
+        // the message must leave room for 8 octets of padding.
+        assert!(encrypt_pkcs1(&key.n, &key.e, &[0x11; 53]).is_some());
+        assert!(encrypt_pkcs1(&key.n, &key.e, &[0x11; 54]).is_none());
+    }
+
+    #[test]
+    fn test_generate() {
+        let key = RsaPrivateKey::generate(512);
+
+        assert_eq!(key.n.bits(), 512);
+        assert_eq!(&key.p * &key.q, key.n);
+        assert!((&key.p * &key.u % &key.q).is_one());
+
+        let message = BigUint::from(42_u32);
+        assert_eq!(
+            message.modpow(&key.e, &key.n).modpow(&key.d, &key.n),
+            message
+        );
+    }
+}
@@ -1,9 +1,46 @@
+use regex::bytes::Regex as BytesRegex;
 use regex::Regex;
 use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn read_to_string_convert_newlines(filename: &str) -> anyhow::Result<String> {
-    let re = Regex::new(r"\r\n")?;
     let data = fs::read_to_string(filename)?;
 
-    Ok(re.replace_all(&data, "\n").to_owned().to_string())
+    Ok(convert_newlines(&data))
+}
+
+/// Convert CRLF line endings to LF.
+pub fn convert_newlines(data: &str) -> String {
+    let re = Regex::new(r"\r\n").unwrap();
+
+    re.replace_all(data, "\n").to_string()
+}
+
+/// Canonicalize line endings to CRLF. Done in two steps, first turning all CRLF into LF, then all
+/// LF into CRLF, so that mixed line endings are handled correctly.
+pub fn canonicalize_line_endings(data: &[u8]) -> Vec<u8> {
+    let crlf = BytesRegex::new(r"\r\n").unwrap();
+    let replaced = crlf.replace_all(data, &b"\n"[..]);
+    let lf = BytesRegex::new(r"\n").unwrap();
+
+    lf.replace_all(&replaced, &b"\r\n"[..]).into_owned()
+}
+
+/// The current time, as seconds since the unix epoch.
+pub fn unix_time_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// The two octet checksum used by OpenPGP: the sum of all octets, modulo 65536.
+pub fn sum16(data: &[u8]) -> u16 {
+    data.iter()
+        .fold(0_u16, |sum, byte| sum.wrapping_add(u16::from(*byte)))
+}
+
+/// `size` random octets.
+pub fn random_bytes(size: usize) -> Vec<u8> {
+    (0..size).map(|_| rand::random()).collect()
 }
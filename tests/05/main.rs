@@ -0,0 +1,48 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+#[test]
+fn test_05() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/05/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/05/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_05_unknown_packets() {
+    // the signature is preceded by a marker packet and followed by a padding packet, and the
+    // key has a photo ID. Packets which are not understood are skipped.
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/05/msg.txt.sig")
+        .arg("./tests/05/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/05/photo-public.key")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert!(stdout.contains("Good signature from Photo Test <photo@pgp-rs.example>"));
+}
+
+#[test]
+fn test_05_armor_headers() {
+    // the same key as public.key, with "Comment" armor headers as written by Sequoia.
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/05/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/05/public-comment.key")
+        .assert()
+        .success();
+}
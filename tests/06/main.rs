@@ -0,0 +1,54 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+#[test]
+fn test_06_sha512() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/06/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/06/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_06_sha1() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/06/msg-sha1.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/06/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_06_wrong_hash_header() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/06/msg-wrong-hash.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/06/public.key")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_06_unknown_hash_in_header() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/06/msg-unknown-hash.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/06/public.key")
+        .assert()
+        .success();
+}
@@ -0,0 +1,58 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+#[test]
+fn test_07_binary_armored() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/07/archive.bin.asc")
+        .arg("./tests/07/archive.bin")
+        .arg("--publicKey")
+        .arg("./tests/07/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_07_binary_unarmored() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/07/archive.bin.sig")
+        .arg("./tests/07/archive.bin")
+        .arg("--publicKey")
+        .arg("./tests/07/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_07_text() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/07/notes.txt.asc")
+        .arg("./tests/07/notes.txt")
+        .arg("--publicKey")
+        .arg("./tests/07/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_07_wrong_file() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/07/archive.bin.sig")
+        .arg("./tests/07/notes.txt")
+        .arg("--publicKey")
+        .arg("./tests/07/public.key")
+        .assert()
+        .failure();
+}
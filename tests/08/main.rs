@@ -0,0 +1,87 @@
+use assert_cmd::prelude::*;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use pgp_rs::pgp::message::CompressedDataPacket;
+use std::io::Write;
+use std::process::Command;
+
+const MSG: &str = "Now is the winter of our discontent\n\
+                   Made glorious summer by this sun of York;\n";
+
+#[test]
+fn test_08_compressed() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/08/msg.txt.gpg")
+        .arg("--publicKey")
+        .arg("./tests/08/public.key")
+        .assert()
+        .success()
+        .stdout(MSG);
+}
+
+#[test]
+fn test_08_armored() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/08/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/08/public.key")
+        .assert()
+        .success()
+        .stdout(MSG);
+}
+
+#[test]
+fn test_08_nested() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/08/msg-nested.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/08/public.key")
+        .assert()
+        .success()
+        .stdout(MSG)
+        .stderr(
+            "File read.\n\
+             Good signature from pgp-rs test <rsa@pgp-rs.example>\n\
+             Good signature from pgp-rs test <rsa@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_08_tampered() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/08/msg-tampered.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/08/public.key")
+        .assert()
+        .failure()
+        .stdout("");
+}
+
+#[test]
+fn test_08_decompression_limit() {
+    // 1 MiB of zeros deflates to about a kilobyte.
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&vec![0; 1 << 20]).unwrap();
+    let compressed = CompressedDataPacket {
+        algorithm: 1,
+        data: encoder.finish().unwrap(),
+    };
+
+    let error = compressed.decompress_with_limit(64 * 1024).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "compressed data decompresses to more than 65536 octets"
+    );
+}
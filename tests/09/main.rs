@@ -0,0 +1,41 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+#[test]
+fn test_09_notation() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/09/msg-notation.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/09/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_09_critical_notation() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/09/msg-critical.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/09/public.key")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_09_expired() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/09/msg-expired.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/09/public.key")
+        .assert()
+        .failure();
+}
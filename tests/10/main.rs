@@ -0,0 +1,91 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+const MSG: &str = "Signed by a subkey, verified by the certificate.\n";
+
+#[test]
+fn test_10_cleartext() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/10/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/10/public.key")
+        .assert()
+        .success()
+        .stdout("File read. Checksum is valid.\nGood signature from pgp-rs subkey test <subkey@pgp-rs.example>\n");
+}
+
+#[test]
+fn test_10_detached() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/10/msg.txt.sig")
+        .arg("./tests/10/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/10/public.key")
+        .assert()
+        .success()
+        .stdout(
+            "Signature read.\nGood signature from pgp-rs subkey test <subkey@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_10_signed() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/10/msg-signed.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/10/public.key")
+        .assert()
+        .success()
+        .stdout(MSG);
+}
+
+#[test]
+fn test_10_no_matching_key() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/10/msg.txt.sig")
+        .arg("./tests/10/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/09/public.key")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_10_invalid_subkey_binding() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/10/msg.txt.sig")
+        .arg("./tests/10/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/10/public-unbound.key")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_10_invalid_back_signature() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/10/msg.txt.sig")
+        .arg("./tests/10/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/10/public-bad-back-signature.key")
+        .assert()
+        .failure();
+}
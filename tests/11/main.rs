@@ -0,0 +1,53 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+#[test]
+fn test_11_primary_user_id() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/11/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/11/public.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from Alice Liddell <alice@wonderland.example>\n",
+        );
+}
+
+#[test]
+fn test_11_invalid_primary_user_id() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/11/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/11/public-bad-primary-uid.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from Alice <alice@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_11_no_self_certification() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/11/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/11/public-no-self-certification.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from key 0xE0D071F2E77FDC79\n",
+        );
+}
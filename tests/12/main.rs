@@ -0,0 +1,89 @@
+use assert_cmd::prelude::*;
+use num::BigUint;
+use pgp_rs::pgp::hash::HashAlgorithm;
+use pgp_rs::pgp::key::PublicKeyMaterial;
+use std::process::Command;
+
+#[test]
+fn test_12_cleartext() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/12/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/12/public.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from pgp-rs dsa test <dsa@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_12_truncated_hash() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/12/msg-sha512.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/12/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_12_detached() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/12/msg.txt.sig")
+        .arg("./tests/12/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/12/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_12_tampered() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/12/msg-tampered.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/12/public.key")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_12_invalid_domain_parameters() {
+    let hash = [0x42; 32];
+    let signature = [BigUint::from(1_u8), BigUint::from(1_u8)];
+    let key = |p: u32, q: u32, g: u32| PublicKeyMaterial::Dsa {
+        p: BigUint::from(p),
+        q: BigUint::from(q),
+        g: BigUint::from(g),
+        y: BigUint::from(2_u8),
+    };
+
+    // a zero p, a q below 2, a zero g, and a q which doesn't divide p - 1.
+    for material in [key(0, 11, 2), key(23, 1, 2), key(23, 11, 0), key(23, 7, 2)].iter() {
+        assert!(!material
+            .verify(HashAlgorithm::Sha256, &hash, &signature)
+            .unwrap());
+    }
+
+    let rsa = PublicKeyMaterial::Rsa {
+        n: BigUint::from(0_u8),
+        e: BigUint::from(65537_u32),
+    };
+    assert!(!rsa
+        .verify(HashAlgorithm::Sha256, &hash, &signature[..1])
+        .unwrap());
+}
@@ -0,0 +1,62 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+const MSG: &str = "All the world's a stage.\n";
+
+#[test]
+fn test_13_cleartext() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/13/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/13/public.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from pgp-rs ed25519 test <ed25519@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_13_detached_subkey() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/13/msg.txt.sig")
+        .arg("./tests/13/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/13/public.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_13_signed() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/13/msg-signed.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/13/public.key")
+        .assert()
+        .success()
+        .stdout(MSG);
+}
+
+#[test]
+fn test_13_tampered() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/13/msg-tampered.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/13/public.key")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,80 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+#[test]
+fn test_14_nistp256() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/14/msg-nistp256.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/14/nistp256.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from pgp-rs nistp256 test <nistp256@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_14_nistp384() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/14/msg-nistp384.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/14/nistp384.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from pgp-rs nistp384 test <nistp384@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_14_nistp521() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/14/msg-nistp521.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/14/nistp521.key")
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+             Good signature from pgp-rs nistp521 test <nistp521@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_14_truncated_hash() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg("./tests/14/msg-nistp256-sha512.txt.sig")
+        .arg("./tests/14/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/14/nistp256.key")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_14_tampered() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/14/msg-nistp384-tampered.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/14/nistp384.key")
+        .assert()
+        .failure();
+}
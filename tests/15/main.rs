@@ -0,0 +1,45 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+const MSG: &str = "What we need from the grocery store:\n\n- tofu\n- vegetables\n- noodles\n";
+
+#[test]
+fn test_15_cleartext() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/15/msg.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/15/public.key")
+        .assert()
+        .success()
+        .stdout("File read. Checksum is valid.\nGood signature from key 0xCB186C4F0609A697\n");
+}
+
+#[test]
+fn test_15_signed() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/15/msg-signed.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/15/public.key")
+        .assert()
+        .success()
+        .stdout(MSG);
+}
+
+#[test]
+fn test_15_tampered() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg("./tests/15/msg-tampered.txt.asc")
+        .arg("--publicKey")
+        .arg("./tests/15/public.key")
+        .assert()
+        .failure();
+}
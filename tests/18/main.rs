@@ -0,0 +1,81 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use std::fs;
+use std::process::Command;
+
+const SIGNED_TEXT: &str = "Hello world\n\
+                           - -----BEGIN PGP SIGNATURE-----\n\
+                           - - already dashed\n\
+                           trailing tab\n";
+
+/// Sign the message with the given secret key, check the signed document, and verify it with the
+/// matching public key.
+fn sign_and_verify(key: &str) {
+    let temp = TempDir::new("pgp-rs-18-");
+    let signed_path = temp.path(&format!("{}-msg.txt.asc", key));
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .arg("--clear")
+        .arg("-s")
+        .arg("./tests/18/msg.txt")
+        .arg("--secretKey")
+        .arg(format!("./tests/18/{}-secret.key", key))
+        .arg("-o")
+        .arg(&signed_path)
+        .assert()
+        .success();
+
+    let signed = fs::read_to_string(&signed_path).unwrap();
+    assert!(signed.starts_with(&format!(
+        "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\n{}-----BEGIN PGP SIGNATURE-----\n",
+        SIGNED_TEXT
+    )));
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg(&signed_path)
+        .arg("--publicKey")
+        .arg(format!("./tests/18/{}-public.key", key))
+        .assert()
+        .success()
+        .stdout(
+            "File read. Checksum is valid.\n\
+         Good signature from pgp-rs fixture <fixture@pgp-rs.example>\n",
+        );
+}
+
+#[test]
+fn test_18_sign_clear_ed25519() {
+    sign_and_verify("ed25519");
+}
+
+#[test]
+fn test_18_sign_clear_rsa() {
+    sign_and_verify("rsa");
+}
+
+#[test]
+fn test_18_sign_clear_to_stdout() {
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .arg("--clear")
+        .arg("-s")
+        .arg("./tests/18/msg.txt")
+        .arg("--secretKey")
+        .arg("./tests/18/ed25519-secret.key")
+        .assert()
+        .success()
+        .stderr("Signed with key 0x90AB9FB91E8731A0.\n");
+
+    let signed = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert!(signed.starts_with("-----BEGIN PGP SIGNED MESSAGE-----\n"));
+    assert!(signed.ends_with("-----END PGP SIGNATURE-----\n"));
+}
@@ -0,0 +1,123 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use std::fs;
+use std::process::Command;
+
+const MSG: &str = "What we need from the grocery store:\n\n- tofu\n- vegetables\n- noodles\n";
+const GOOD_SIGNATURE: &str = "Good signature from pgp-rs fixture <fixture@pgp-rs.example>\n";
+
+/// Sign the message with the ed25519 key from the cleartext signing tests, with the given extra
+/// arguments, returning what was written.
+fn sign(name: &str, args: &[&str]) -> Vec<u8> {
+    let temp = TempDir::new("pgp-rs-19-");
+    let output_path = temp.path(name);
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .args(args)
+        .arg("-s")
+        .arg("./tests/19/msg.txt")
+        .arg("--secretKey")
+        .arg("./tests/18/ed25519-secret.key")
+        .arg("-o")
+        .arg(&output_path)
+        .assert()
+        .success()
+        .stderr("Signed with key 0x90AB9FB91E8731A0.\n");
+
+    fs::read(&output_path).unwrap()
+}
+
+/// Verify a detached signature over the message.
+fn verify_detached(name: &str, signature: &[u8]) {
+    let temp = TempDir::new("pgp-rs-19-");
+    let signature_path = temp.path(name);
+    fs::write(&signature_path, signature).unwrap();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("--detached")
+        .arg(&signature_path)
+        .arg("./tests/19/msg.txt")
+        .arg("--publicKey")
+        .arg("./tests/18/ed25519-public.key")
+        .assert()
+        .success()
+        .stdout(format!("Signature read.\n{}", GOOD_SIGNATURE));
+}
+
+/// Verify a signed message, returning the signed data.
+fn verify_message(name: &str, message: &[u8]) -> Vec<u8> {
+    let temp = TempDir::new("pgp-rs-19-");
+    let message_path = temp.path(name);
+    fs::write(&message_path, message).unwrap();
+
+    let verified = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg(&message_path)
+        .arg("--publicKey")
+        .arg("./tests/18/ed25519-public.key")
+        .assert()
+        .success()
+        .stderr(format!("File read.\n{}", GOOD_SIGNATURE));
+
+    verified.get_output().stdout.clone()
+}
+
+#[test]
+fn test_19_sign_detached() {
+    let signature = sign("detached.sig", &["--detach"]);
+    assert!(!signature.starts_with(b"-----BEGIN"));
+
+    verify_detached("detached.sig", &signature);
+}
+
+#[test]
+fn test_19_sign_detached_armored_text() {
+    let signature = sign("detached-text.asc", &["--detach", "--text", "--armor"]);
+    assert!(signature.starts_with(b"-----BEGIN PGP SIGNATURE-----\n\n"));
+
+    verify_detached("detached-text.asc", &signature);
+}
+
+#[test]
+fn test_19_sign_message() {
+    let message = sign("msg.txt.gpg", &[]);
+    assert!(!message.starts_with(b"-----BEGIN"));
+
+    assert_eq!(verify_message("msg.txt.gpg", &message), MSG.as_bytes());
+}
+
+#[test]
+fn test_19_sign_message_armored_text() {
+    let message = sign("msg-text.txt.asc", &["--text", "-a"]);
+    assert!(message.starts_with(b"-----BEGIN PGP MESSAGE-----\n\n"));
+
+    // text is stored with canonical line endings.
+    assert_eq!(
+        verify_message("msg-text.txt.asc", &message),
+        MSG.replace('\n', "\r\n").as_bytes()
+    );
+}
+
+#[test]
+fn test_19_clear_and_detach() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .arg("--clear")
+        .arg("--detach")
+        .arg("-s")
+        .arg("./tests/19/msg.txt")
+        .arg("--secretKey")
+        .arg("./tests/18/ed25519-secret.key")
+        .assert()
+        .failure();
+}
@@ -0,0 +1,193 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use pgp_rs::pgp::key::{Curve, PublicKeyMaterial};
+use pgp_rs::pgp::secret_key::{SecretKey, SecretKeyMaterial, SecretKeyProtection};
+use std::fs;
+use std::process::Command;
+
+/// The passphrase which protects the keys, which were exported by `gpg --export-secret-keys`.
+/// `ed25519-secret-subkeys.key` is the same key exported by `gpg --export-secret-subkeys`, which
+/// leaves out the secret key material of the primary key.
+const PASSPHRASE: &str = "secret";
+
+#[test]
+fn test_20_unlock() {
+    let input = fs::read("./tests/20/ed25519-secret.key").unwrap();
+    let mut key = SecretKey::parse(&input).unwrap();
+
+    assert!(key.primary_key.secret_key_material.is_none());
+    assert_eq!(key.subkeys.len(), 1);
+    assert!(key.unlock("wrong").is_err());
+
+    key.unlock(PASSPHRASE).unwrap();
+    assert!(matches!(
+        key.primary_key.secret_key_material,
+        Some(SecretKeyMaterial::EdDsa { .. })
+    ));
+    let subkey = &key.subkeys[0].key;
+    assert!(matches!(
+        subkey.public_key.key_material,
+        PublicKeyMaterial::Ecdh {
+            curve: Curve::Cv25519,
+            ..
+        }
+    ));
+    assert!(matches!(
+        subkey.secret_key_material,
+        Some(SecretKeyMaterial::Ecdh { .. })
+    ));
+}
+
+/// Sign the message with a protected key, and verify it with the matching public key.
+fn sign_and_verify(key: &str, key_id: &str) {
+    let temp = TempDir::new("pgp-rs-20-");
+    let signed_path = temp.path(&format!("{}-msg.txt.asc", key));
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .arg("--clear")
+        .arg("-s")
+        .arg("./tests/20/msg.txt")
+        .arg("--secretKey")
+        .arg(format!("./tests/20/{}-secret.key", key))
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("-o")
+        .arg(&signed_path)
+        .assert()
+        .success()
+        .stderr(format!("Signed with key 0x{}.\n", key_id));
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("verify")
+        .arg("-s")
+        .arg(&signed_path)
+        .arg("--publicKey")
+        .arg(format!("./tests/20/{}-public.key", key))
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_20_sign_with_gpg_ed25519_key() {
+    sign_and_verify("ed25519", "4A66397FDD0328C5");
+}
+
+#[test]
+fn test_20_sign_with_gpg_rsa_key() {
+    sign_and_verify("rsa", "7188CAB84AFAFE67");
+}
+
+#[test]
+fn test_20_sign_without_passphrase() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .arg("--clear")
+        .arg("-s")
+        .arg("./tests/20/msg.txt")
+        .arg("--secretKey")
+        .arg("./tests/20/ed25519-secret.key")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_20_sign_with_wrong_passphrase() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .arg("--clear")
+        .arg("-s")
+        .arg("./tests/20/msg.txt")
+        .arg("--secretKey")
+        .arg("./tests/20/ed25519-secret.key")
+        .arg("--passphrase")
+        .arg("wrong")
+        .assert()
+        .failure()
+        .stderr("Error: could not unlock the secret key: the passphrase is wrong\n");
+}
+
+#[test]
+fn test_20_gnu_dummy_primary_key() {
+    let input = fs::read("./tests/20/ed25519-secret-subkeys.key").unwrap();
+    let mut key = SecretKey::parse(&input).unwrap();
+
+    assert!(matches!(
+        key.primary_key.protection,
+        SecretKeyProtection::GnuDummy { .. }
+    ));
+    assert!(!key.primary_key.has_secret_material());
+
+    key.unlock(PASSPHRASE).unwrap();
+    assert!(key.primary_key.secret_key_material.is_none());
+    assert!(matches!(
+        key.subkeys[0].key.secret_key_material,
+        Some(SecretKeyMaterial::Ecdh { .. })
+    ));
+    assert!(key.sign_document(false, b"message").is_err());
+
+    // the stub is written back as it was read.
+    let armor = key.secret_key_armor().unwrap().to_string();
+    let key = SecretKey::parse(armor.as_bytes()).unwrap();
+    assert!(matches!(
+        key.primary_key.protection,
+        SecretKeyProtection::GnuDummy { .. }
+    ));
+}
+
+#[test]
+fn test_20_decrypt_with_gnu_dummy_primary_key() {
+    let temp = TempDir::new("pgp-rs-20-");
+    let encrypted_path = temp.path("msg.txt.gpg");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/20/msg.txt")
+        .arg("--recipient")
+        .arg("./tests/20/ed25519-public.key")
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success();
+
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&encrypted_path)
+        .arg("--secretKey")
+        .arg("./tests/20/ed25519-secret-subkeys.key")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success();
+
+    let expected = fs::read("./tests/20/msg.txt").unwrap();
+    assert_eq!(assert.get_output().stdout, expected);
+}
+
+#[test]
+fn test_20_sign_with_gnu_dummy_primary_key() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("sign")
+        .arg("--clear")
+        .arg("-s")
+        .arg("./tests/20/msg.txt")
+        .arg("--secretKey")
+        .arg("./tests/20/ed25519-secret-subkeys.key")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr("Error: secret key has no key which can sign\n");
+}
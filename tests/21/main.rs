@@ -0,0 +1,204 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use pgp_rs::pgp::aead::AeadAlgorithm;
+use pgp_rs::pgp::s2k::StringToKey;
+use pgp_rs::pgp::secret_key::{SecretKey, SecretKeyMaterial, SecretKeyProtection};
+use pgp_rs::pgp::signature::SIGNATURE_TYPE_BINARY;
+use pgp_rs::pgp::PublicKey;
+use std::fs;
+use std::process::Command;
+
+const USER_ID: &str = "pgp-rs generated <generated@pgp-rs.example>";
+const PASSPHRASE: &str = "correct horse battery staple";
+
+fn assert_aead_protected(protection: &SecretKeyProtection, expected: AeadAlgorithm) {
+    match protection {
+        SecretKeyProtection::Aead {
+            aead_algorithm,
+            s2k,
+            nonce,
+            ..
+        } => {
+            assert_eq!(*aead_algorithm, expected);
+            assert!(matches!(s2k, StringToKey::Argon2 { .. }));
+            assert_eq!(nonce.len(), expected.nonce_size());
+        }
+        _ => panic!("expected aead protection"),
+    }
+}
+
+#[test]
+fn test_21_generate_aead_protected_key() {
+    let temp = TempDir::new("pgp-rs-21-");
+
+    for (name, aead_algorithm) in [
+        ("ocb", AeadAlgorithm::Ocb),
+        ("eax", AeadAlgorithm::Eax),
+        ("gcm", AeadAlgorithm::Gcm),
+    ]
+    .iter()
+    {
+        let public_key_path = temp.path(&format!("{}-public.pgp", name));
+        let secret_key_path = temp.path(&format!("{}-secret.pgp", name));
+        let signature_path = temp.path(&format!("{}-signature.sig", name));
+
+        Command::cargo_bin("pgp-rs")
+            .unwrap()
+            .arg("generate-key")
+            .arg("--userId")
+            .arg(USER_ID)
+            .arg("--algorithm")
+            .arg("ed25519")
+            .arg("--passphrase")
+            .arg(PASSPHRASE)
+            .arg("--aead")
+            .arg(name)
+            .arg("--publicKey")
+            .arg(&public_key_path)
+            .arg("--secretKey")
+            .arg(&secret_key_path)
+            .assert()
+            .success();
+
+        let mut key = SecretKey::parse(&fs::read(&secret_key_path).unwrap()).unwrap();
+        assert!(key.primary_key.secret_key_material.is_none());
+        assert_aead_protected(&key.primary_key.protection, *aead_algorithm);
+        assert_eq!(key.subkeys.len(), 1);
+        assert_aead_protected(&key.subkeys[0].key.protection, *aead_algorithm);
+
+        assert!(key.unlock("wrong").is_err());
+        key.unlock(PASSPHRASE).unwrap();
+        assert!(matches!(
+            key.subkeys[0].key.secret_key_material,
+            Some(SecretKeyMaterial::Ecdh { .. })
+        ));
+
+        Command::cargo_bin("pgp-rs")
+            .unwrap()
+            .arg("sign")
+            .arg("--source")
+            .arg("./tests/21/msg.txt")
+            .arg("--secretKey")
+            .arg(&secret_key_path)
+            .arg("--passphrase")
+            .arg(PASSPHRASE)
+            .arg("--detach")
+            .arg("--output")
+            .arg(&signature_path)
+            .assert()
+            .success();
+
+        Command::cargo_bin("pgp-rs")
+            .unwrap()
+            .arg("verify")
+            .arg("--publicKey")
+            .arg(&public_key_path)
+            .arg("--detached")
+            .arg(&signature_path)
+            .arg("./tests/21/msg.txt")
+            .assert()
+            .success();
+    }
+}
+
+#[test]
+fn test_21_reprotect_with_aead() {
+    let input = fs::read("./tests/20/rsa-secret.key").unwrap();
+    let mut key = SecretKey::parse(&input).unwrap();
+    key.unlock("secret").unwrap();
+    key.protect_aead(PASSPHRASE, AeadAlgorithm::Ocb).unwrap();
+
+    let exported = key.secret_key_armor().unwrap().to_string();
+    let mut key = SecretKey::parse(exported.as_bytes()).unwrap();
+    assert_aead_protected(&key.primary_key.protection, AeadAlgorithm::Ocb);
+    assert!(key.sign(SIGNATURE_TYPE_BINARY, b"data").is_err());
+
+    assert!(key.unlock("secret").is_err());
+    key.unlock(PASSPHRASE).unwrap();
+    let signature = key.sign(SIGNATURE_TYPE_BINARY, b"data").unwrap();
+
+    let public_key = fs::read_to_string("./tests/20/rsa-public.key").unwrap();
+    let public_key = PublicKey::parse(&public_key).unwrap();
+    assert!(signature.verify(&public_key, b"data").unwrap());
+}
+
+#[test]
+fn test_21_argon2_parameter_limits() {
+    let s2k = |p, encoded_m| StringToKey::Argon2 {
+        salt: [0; 16],
+        t: 1,
+        p,
+        encoded_m,
+    };
+
+    // 2^30 KiB is 1 TiB, which is refused before any memory is allocated.
+    let error = s2k(1, 30).derive_key(b"passphrase", 32).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Argon2 memory size 2^30 KiB is too large, at most 2^21 KiB is allowed"
+    );
+
+    // each of 4 lanes needs at least 8 KiB.
+    let error = s2k(4, 4).derive_key(b"passphrase", 32).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Argon2 memory size 2^4 KiB is too small for 4 lanes"
+    );
+    assert_eq!(s2k(4, 5).derive_key(b"passphrase", 32).unwrap().len(), 32);
+}
+
+#[test]
+fn test_21_argon2_memory_size_from_message() {
+    let temp = TempDir::new("pgp-rs-21-");
+    let encrypted_path = temp.path("msg.txt.gpg");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/21/msg.txt")
+        .arg("--symmetric")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("--aead")
+        .arg("ocb")
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success();
+
+    // the coded memory size of the Argon2 specifier in the symmetric-key encrypted session key
+    // packet. It follows the packet header, 5 octets of fields, and the specifier's type, salt,
+    // passes and lanes.
+    let mut encrypted = fs::read(&encrypted_path).unwrap();
+    assert_eq!(encrypted[26], 16);
+    encrypted[26] = 30;
+    fs::write(&encrypted_path, &encrypted).unwrap();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&encrypted_path)
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr("Error: Argon2 memory size 2^30 KiB is too large, at most 2^21 KiB is allowed\n");
+}
+
+#[test]
+fn test_21_aead_without_passphrase() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("generate-key")
+        .arg("--userId")
+        .arg(USER_ID)
+        .arg("--aead")
+        .arg("ocb")
+        .assert()
+        .failure();
+}
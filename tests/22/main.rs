@@ -0,0 +1,123 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use std::fs;
+use std::process::Command;
+
+/// The passphrase which protects the key, which was exported by `gpg --export-secret-keys`. The
+/// messages were encrypted to its RSA subkey by `gpg --encrypt`.
+const PASSPHRASE: &str = "secret";
+
+#[test]
+fn test_22_decrypt() {
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/22/msg.txt.gpg")
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success()
+        .stderr("Decrypted with key 0xE7888AE68CBC7F2B.\n");
+
+    let expected = fs::read("./tests/22/msg.txt").unwrap();
+    assert_eq!(assert.get_output().stdout, expected);
+}
+
+#[test]
+fn test_22_decrypt_armored_compressed() {
+    let temp = TempDir::new("pgp-rs-22-");
+    let output_path = temp.path("msg.txt");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/22/msg.txt.asc")
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("-o")
+        .arg(&output_path)
+        .assert()
+        .success();
+
+    let decrypted = fs::read(&output_path).unwrap();
+
+    assert_eq!(decrypted, fs::read("./tests/22/msg.txt").unwrap());
+}
+
+#[test]
+fn test_22_decrypt_modified() {
+    let temp = TempDir::new("pgp-rs-22-");
+    let modified_path = temp.path("modified.gpg");
+    let mut message = fs::read("./tests/22/msg.txt.gpg").unwrap();
+    let last = message.len() - 5;
+    message[last] ^= 0x01;
+    fs::write(&modified_path, message).unwrap();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&modified_path)
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr(
+            "Decrypted with key 0xE7888AE68CBC7F2B.\n\
+         Error: encrypted data has been modified: the modification detection code did not \
+         match\n",
+        );
+}
+
+#[test]
+fn test_22_decrypt_modified_session_key() {
+    // whether the modified session key is padded correctly or not, it fails with the same error.
+    let temp = TempDir::new("pgp-rs-22-");
+    let modified_path = temp.path("modified.gpg");
+    let mut message = fs::read("./tests/22/msg.txt.gpg").unwrap();
+    message[0x20] ^= 0x01;
+    fs::write(&modified_path, message).unwrap();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&modified_path)
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr("Error: could not decrypt the session key\n");
+}
+
+#[test]
+fn test_22_decrypt_with_other_key() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/22/msg.txt.gpg")
+        .arg("--secretKey")
+        .arg("./tests/20/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr(
+            "Error: message is not encrypted to this key, it is encrypted to \
+             0xE7888AE68CBC7F2B\n",
+        );
+}
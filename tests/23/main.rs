@@ -0,0 +1,211 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The passphrase which protects the secret keys. The second recipient key was made with gpg,
+/// and prefers only AES-192 and AES-128. The Curve25519 key was also made with gpg, and the v6
+/// key with a native X25519 subkey is the unprotected sample key of RFC 9580 appendix A.4.
+const PASSPHRASE: &str = "secret";
+
+fn decrypt(source: &PathBuf, secret_key_path: &str) -> Vec<u8> {
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(source)
+        .arg("--secretKey")
+        .arg(secret_key_path)
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success();
+
+    assert.get_output().stdout.clone()
+}
+
+#[test]
+fn test_23_encrypt() {
+    let temp = TempDir::new("pgp-rs-23-");
+    let encrypted_path = temp.path("msg.txt.gpg");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/23/msg.txt")
+        .arg("-r")
+        .arg("./tests/22/rsa-public.key")
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success()
+        .stderr("Encrypted to key 0xE7888AE68CBC7F2B.\nEncrypted with AES-256.\n");
+
+    let decrypted = decrypt(&encrypted_path, "./tests/22/rsa-secret.key");
+
+    assert_eq!(decrypted, fs::read("./tests/23/msg.txt").unwrap());
+}
+
+#[test]
+fn test_23_encrypt_to_multiple_recipients() {
+    let temp = TempDir::new("pgp-rs-23-");
+    let encrypted_path = temp.path("msg.txt.asc");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/23/msg.txt")
+        .arg("-r")
+        .arg("./tests/22/rsa-public.key")
+        .arg("-r")
+        .arg("./tests/23/recipient-public.key")
+        .arg("--armor")
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success()
+        .stderr(
+            "Encrypted to key 0xE7888AE68CBC7F2B.\n\
+             Encrypted to key 0x38AF0D349B9FB8AD.\n\
+             Encrypted with AES-192.\n",
+        );
+
+    let encrypted = fs::read_to_string(&encrypted_path).unwrap();
+    assert!(encrypted.starts_with("-----BEGIN PGP MESSAGE-----"));
+
+    let expected = fs::read("./tests/23/msg.txt").unwrap();
+    assert_eq!(
+        decrypt(&encrypted_path, "./tests/22/rsa-secret.key"),
+        expected
+    );
+    assert_eq!(
+        decrypt(&encrypted_path, "./tests/23/recipient-secret.key"),
+        expected
+    );
+}
+
+#[test]
+fn test_23_encrypt_without_encryption_key() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/23/msg.txt")
+        .arg("-r")
+        .arg("./tests/20/rsa-public.key")
+        .assert()
+        .failure()
+        .stderr(
+            "Error: key 0x7188CAB84AFAFE67 does not have a key which can be used for \
+             encryption\n",
+        );
+}
+
+#[test]
+fn test_23_encrypt_to_ed25519_key() {
+    let temp = TempDir::new("pgp-rs-23-");
+    let public_key_path = temp.path("public.pgp");
+    let secret_key_path = temp.path("secret.pgp");
+    let encrypted_path = temp.path("msg.txt.gpg");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("generate-key")
+        .arg("-u")
+        .arg("Ed25519 Recipient <ed25519@pgp-rs.example>")
+        .arg("--algorithm")
+        .arg("ed25519")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("--publicKey")
+        .arg(&public_key_path)
+        .arg("--secretKey")
+        .arg(&secret_key_path)
+        .assert()
+        .success();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/23/msg.txt")
+        .arg("-r")
+        .arg(&public_key_path)
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success();
+
+    let decrypted = decrypt(&encrypted_path, secret_key_path.to_str().unwrap());
+
+    assert_eq!(decrypted, fs::read("./tests/23/msg.txt").unwrap());
+}
+
+#[test]
+fn test_23_encrypt_to_cv25519_key() {
+    let temp = TempDir::new("pgp-rs-23-");
+    let encrypted_path = temp.path("msg.txt.gpg");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/23/msg.txt")
+        .arg("-r")
+        .arg("./tests/23/cv25519-public.key")
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success()
+        .stderr("Encrypted to key 0xC22D45907996047B.\nEncrypted with AES-256.\n");
+
+    let decrypted = decrypt(&encrypted_path, "./tests/23/cv25519-secret.key");
+
+    assert_eq!(decrypted, fs::read("./tests/23/msg.txt").unwrap());
+}
+
+#[test]
+fn test_23_decrypt_cv25519_message_from_gpg() {
+    let decrypted = decrypt(
+        &PathBuf::from("./tests/23/msg.txt.gpg"),
+        "./tests/23/cv25519-secret.key",
+    );
+
+    assert_eq!(decrypted, fs::read("./tests/23/msg.txt").unwrap());
+}
+
+#[test]
+fn test_23_encrypt_to_x25519_key() {
+    let temp = TempDir::new("pgp-rs-23-");
+    let expected = fs::read("./tests/23/msg.txt").unwrap();
+
+    // v3 session keys give the symmetric algorithm in the clear, and v6 session keys leave it to
+    // the v2 encrypted data packet.
+    for aead in [None, Some("ocb")].iter() {
+        let encrypted_path = temp.path("msg.txt.gpg");
+        let mut command = Command::cargo_bin("pgp-rs").unwrap();
+        command
+            .arg("encrypt")
+            .arg("-s")
+            .arg("./tests/23/msg.txt")
+            .arg("-r")
+            .arg("./tests/23/v6-public.key")
+            .arg("-o")
+            .arg(&encrypted_path);
+        if let Some(aead) = aead {
+            command.arg("--aead").arg(aead);
+        }
+        command.assert().success();
+
+        assert_eq!(
+            decrypt(&encrypted_path, "./tests/23/v6-secret.key"),
+            expected
+        );
+    }
+}
@@ -0,0 +1,218 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use std::fs;
+use std::process::Command;
+
+/// The passphrase the messages were encrypted with by `gpg --symmetric`. mixed.gpg is also
+/// encrypted to the RSA subkey of the tests/22 key.
+const PASSPHRASE: &str = "open sesame";
+/// The passphrase which protects the tests/22 secret key.
+const KEY_PASSPHRASE: &str = "secret";
+
+#[test]
+fn test_24_decrypt_with_passphrase() {
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/24/msg.txt.gpg")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success()
+        .stderr("Decrypted with a passphrase.\n");
+
+    let expected = fs::read("./tests/24/msg.txt").unwrap();
+    assert_eq!(assert.get_output().stdout, expected);
+}
+
+#[test]
+fn test_24_decrypt_armored_compressed_with_passphrase() {
+    let temp = TempDir::new("pgp-rs-24-");
+    let output_path = temp.path("msg.txt");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/24/msg.txt.asc")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("-o")
+        .arg(&output_path)
+        .assert()
+        .success();
+
+    let decrypted = fs::read(&output_path).unwrap();
+
+    assert_eq!(decrypted, fs::read("./tests/24/msg.txt").unwrap());
+}
+
+#[test]
+fn test_24_decrypt_mixed() {
+    let expected = fs::read("./tests/24/msg.txt").unwrap();
+
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/24/mixed.gpg")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success()
+        .stderr("Decrypted with a passphrase.\n");
+    assert_eq!(assert.get_output().stdout, expected);
+
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/24/mixed.gpg")
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(KEY_PASSPHRASE)
+        .assert()
+        .success()
+        .stderr("Decrypted with key 0xE7888AE68CBC7F2B.\n");
+    assert_eq!(assert.get_output().stdout, expected);
+}
+
+#[test]
+fn test_24_decrypt_with_wrong_passphrase() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/24/msg.txt.gpg")
+        .arg("--passphrase")
+        .arg("open sesame!")
+        .assert()
+        .failure()
+        .stderr(
+            "Error: could not decrypt the message: the passphrase is wrong, or the encrypted \
+             data has been modified\n",
+        );
+}
+
+#[test]
+fn test_24_decrypt_with_key_only_encrypted_with_passphrase() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg("./tests/24/msg.txt.gpg")
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(KEY_PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr(
+            "Error: message is not encrypted to any key, it can only be decrypted with a \
+             passphrase\n",
+        );
+}
+
+#[test]
+fn test_24_encrypt_symmetric() {
+    let temp = TempDir::new("pgp-rs-24-");
+    let encrypted_path = temp.path("msg.txt.gpg");
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/24/msg.txt")
+        .arg("--symmetric")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success()
+        .stderr("Encrypted with a passphrase.\nEncrypted with AES-256.\n");
+
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&encrypted_path)
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success();
+
+    assert_eq!(
+        assert.get_output().stdout,
+        fs::read("./tests/24/msg.txt").unwrap()
+    );
+}
+
+#[test]
+fn test_24_encrypt_to_recipient_and_passphrase() {
+    let temp = TempDir::new("pgp-rs-24-");
+    let encrypted_path = temp.path("mixed.asc");
+    let expected = fs::read("./tests/24/msg.txt").unwrap();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/24/msg.txt")
+        .arg("-r")
+        .arg("./tests/22/rsa-public.key")
+        .arg("-c")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("--armor")
+        .arg("-o")
+        .arg(&encrypted_path)
+        .assert()
+        .success()
+        .stderr(
+            "Encrypted to key 0xE7888AE68CBC7F2B.\n\
+             Encrypted with a passphrase.\n\
+             Encrypted with AES-256.\n",
+        );
+
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&encrypted_path)
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, expected);
+
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&encrypted_path)
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(KEY_PASSPHRASE)
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, expected);
+}
+
+#[test]
+fn test_24_encrypt_symmetric_without_passphrase() {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg("./tests/24/msg.txt")
+        .arg("--symmetric")
+        .assert()
+        .failure();
+}
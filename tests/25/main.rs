@@ -0,0 +1,188 @@
+#[path = "../common/mod.rs"]
+mod common;
+
+use assert_cmd::prelude::*;
+use common::TempDir;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The passphrase the messages are encrypted with.
+const PASSPHRASE: &str = "open sesame";
+/// The passphrase which protects the tests/22 secret key.
+const KEY_PASSPHRASE: &str = "secret";
+/// The passphrase of the known-answer messages, which were not made by pgp-rs.
+const KNOWN_ANSWER_PASSPHRASE: &str = "password";
+
+fn encrypt(source: &PathBuf, aead: &str, output: &PathBuf, mode: &str) {
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("encrypt")
+        .arg("-s")
+        .arg(source)
+        .arg("-r")
+        .arg("./tests/22/rsa-public.key")
+        .arg("-c")
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .arg("--aead")
+        .arg(aead)
+        .arg("-o")
+        .arg(output)
+        .assert()
+        .success()
+        .stderr(format!(
+            "Encrypted to key 0xE7888AE68CBC7F2B.\n\
+             Encrypted with a passphrase.\n\
+             Encrypted with AES-256 in {} mode.\n",
+            mode
+        ));
+}
+
+fn decrypt_with_passphrase(source: &PathBuf) -> Vec<u8> {
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(source)
+        .arg("--passphrase")
+        .arg(PASSPHRASE)
+        .assert()
+        .success()
+        .stderr("Decrypted with a passphrase.\n");
+
+    assert.get_output().stdout.clone()
+}
+
+fn decrypt_with_key(source: &PathBuf) -> Vec<u8> {
+    let assert = Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(source)
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(KEY_PASSPHRASE)
+        .assert()
+        .success()
+        .stderr("Decrypted with key 0xE7888AE68CBC7F2B.\n");
+
+    assert.get_output().stdout.clone()
+}
+
+#[test]
+fn test_25_encrypt_with_each_aead_algorithm() {
+    let temp = TempDir::new("pgp-rs-25-");
+    let source = PathBuf::from("./tests/24/msg.txt");
+    let expected = fs::read(&source).unwrap();
+
+    for (aead, mode) in &[("ocb", "OCB"), ("eax", "EAX"), ("gcm", "GCM")] {
+        let encrypted_path = temp.path(&format!("msg.txt.{}.gpg", aead));
+        encrypt(&source, aead, &encrypted_path, mode);
+
+        assert_eq!(decrypt_with_passphrase(&encrypted_path), expected);
+        assert_eq!(decrypt_with_key(&encrypted_path), expected);
+    }
+}
+
+#[test]
+fn test_25_encrypt_multiple_chunks() {
+    let temp = TempDir::new("pgp-rs-25-");
+    let source = temp.path("large.bin");
+    let encrypted_path = temp.path("large.bin.gpg");
+    // Larger than two chunks of the default chunk size of 256 KiB.
+    let data: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(&source, &data).unwrap();
+
+    encrypt(&source, "ocb", &encrypted_path, "OCB");
+
+    assert_eq!(decrypt_with_key(&encrypted_path), data);
+    assert_eq!(decrypt_with_passphrase(&encrypted_path), data);
+}
+
+#[test]
+fn test_25_decrypt_modified() {
+    let temp = TempDir::new("pgp-rs-25-");
+    let encrypted_path = temp.path("msg.txt.gpg");
+    let modified_path = temp.path("modified.gpg");
+    encrypt(
+        &PathBuf::from("./tests/24/msg.txt"),
+        "ocb",
+        &encrypted_path,
+        "OCB",
+    );
+
+    let mut encrypted = fs::read(&encrypted_path).unwrap();
+    let len = encrypted.len();
+    // The last 16 octets are the final authentication tag, the 16 before it the tag of the
+    // only chunk.
+    encrypted[len - 20] ^= 1;
+    fs::write(&modified_path, &encrypted).unwrap();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&modified_path)
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(KEY_PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr(
+            "Decrypted with key 0xE7888AE68CBC7F2B.\n\
+             Error: encrypted data has been modified: chunk 0 failed authentication\n",
+        );
+
+    encrypted[len - 20] ^= 1;
+    encrypted[len - 5] ^= 1;
+    fs::write(&modified_path, &encrypted).unwrap();
+
+    Command::cargo_bin("pgp-rs")
+        .unwrap()
+        .arg("decrypt")
+        .arg("-s")
+        .arg(&modified_path)
+        .arg("--secretKey")
+        .arg("./tests/22/rsa-secret.key")
+        .arg("--passphrase")
+        .arg(KEY_PASSPHRASE)
+        .assert()
+        .failure()
+        .stderr(
+            "Decrypted with key 0xE7888AE68CBC7F2B.\n\
+             Error: encrypted data has been modified: the final tag did not match\n",
+        );
+}
+
+#[test]
+fn test_25_decrypt_known_answers() {
+    // rfc9580-eax.asc is the sample message of RFC 9580 appendix A.9. ocb.asc keeps the session
+    // key packet of appendix A.10, and gcm.asc is split into 64 octet chunks. Their encrypted data
+    // was made with the OCB and GCM implementations of Python's cryptography package.
+    let messages = [
+        ("./tests/25/rfc9580-eax.asc", b"Hello, world!".to_vec()),
+        ("./tests/25/ocb.asc", b"Hello, world!".to_vec()),
+        (
+            "./tests/25/gcm.asc",
+            fs::read("./tests/24/msg.txt").unwrap(),
+        ),
+    ];
+
+    for (source, expected) in messages.iter() {
+        let assert = Command::cargo_bin("pgp-rs")
+            .unwrap()
+            .arg("decrypt")
+            .arg("-s")
+            .arg(source)
+            .arg("--passphrase")
+            .arg(KNOWN_ANSWER_PASSPHRASE)
+            .assert()
+            .success()
+            .stderr("Decrypted with a passphrase.\n");
+
+        assert_eq!(assert.get_output().stdout, *expected);
+    }
+}
@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+
+/// A temporary directory for the files written by a test. The directory and everything in it is
+/// removed when it is dropped, so files are cleaned up even when an assert fails.
+pub struct TempDir {
+    dir: tempfile::TempDir,
+}
+
+impl TempDir {
+    /// Create a temporary directory whose name starts with `prefix`, such as "pgp-rs-16-".
+    pub fn new(prefix: &str) -> TempDir {
+        let dir = tempfile::Builder::new().prefix(prefix).tempdir().unwrap();
+
+        TempDir { dir }
+    }
+
+    /// The path of the file `name` inside the directory.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+}